@@ -1,4 +1,4 @@
-import { WasmMat4f32, WasmBezierBuilder3f32, } from "../pkg/star_catalog_wasm.js";
+import { WasmMat4f32 } from "../pkg/star_catalog_wasm.js";
 import { WebglTexture, WebglUniform, } from "./web_gl.js";
 import { Planet } from "./planet.js";
 export class SolarSystem {
@@ -24,9 +24,8 @@ export class SolarSystem {
         return this.planets[2].texture;
     }
     set_time(secs_since_epoch) {
-        const builder = new WasmBezierBuilder3f32();
         for (const o of this.planets) {
-            o.set_time(secs_since_epoch, builder);
+            o.set_time(secs_since_epoch);
         }
     }
     webgl_init(webgl) {
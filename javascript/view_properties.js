@@ -1,5 +1,5 @@
 //a Imports
-import { WasmStar, WasmVec3f32, WasmVec3f64, WasmQuatf32, WasmQuatf64, } from "../pkg/star_catalog_wasm.js";
+import { WasmStar, WasmVec3f32, WasmVec3f64, WasmQuatf32, WasmQuatf64, WasmObserver, } from "../pkg/star_catalog_wasm.js";
 import * as html from "./html.js";
 import { Names } from "./hipparcos.js";
 import { Logger } from "./log.js";
@@ -248,6 +248,7 @@ export class ViewProperties {
         this.vector_x = new WasmVec3f64(1, 0, 0);
         this.vector_y = new WasmVec3f64(0, 1, 0);
         this.vector_z = new WasmVec3f64(0, 0, 1);
+        this.observer = new WasmObserver(0, 0, 0, 0);
         this.observer_up_ecef_v = new WasmVec3f64(0, 0, 0);
         this.observer_we_ecef_v = new WasmVec3f64(0, 0, 0);
         this.observer_ns_ecef_v = new WasmVec3f64(0, 0, 0);
@@ -452,62 +453,32 @@ export class ViewProperties {
         if (this.lon < -180) {
             this.lat += 360;
         }
-        const de = this.lat * this.deg2rad;
-        const ra_of_days = this.days_since_epoch * (366.25 / 365.25);
-        // This magic constant seems to be about right
-        const OFFSET = 100.4157224224 / 360;
-        const ra_time = this.lon / 360 +
-            fract(ra_of_days) +
-            ((this.time_of_day / 24) * 366.25) / 365.25 +
-            OFFSET;
-        this.ra = fract(ra_time) * 2 * Math.PI;
-        this.de = de;
+        const unix_time = this.days_since_epoch * 24 * 60 * 60 + this.time_of_day * 60 * 60;
+        this.observer.set_position(this.lat * this.deg2rad, this.lon * this.deg2rad, 0);
+        this.observer.set_unix_time(unix_time);
+        // The RA of the observer's zenith is the local sidereal time
+        this.ra = this.observer.local_sidereal_time;
+        this.de = this.lat * this.deg2rad;
     }
     //mp derive_observer_frame
     derive_observer_frame() {
-        this.observer_up_ecef_v = this.vec_of_ra_de(this.ra, this.de);
-        // Make v1 be star north - the north pole is +Z
-        //
-        // If at a pole, then at least make it non fragile
-        var v1 = this.vector_z;
-        const cos_ns = this.observer_up_ecef_v.dot(v1);
-        if (cos_ns > 0.999 || cos_ns < -0.999) {
-            v1 = this.vector_y;
-        }
-        // Create a right-handed set as north, west, p
-        //
-        // we = up x v1 normalized - i.e. up, (north-ish), we are a RHS
-        //
-        // ns = we x up
-        //
-        this.observer_we_ecef_v = this.observer_up_ecef_v
-            .cross_product(v1)
-            .normalize();
-        this.observer_ns_ecef_v = this.observer_we_ecef_v
-            .cross_product(this.observer_up_ecef_v)
-            .normalize();
+        // The observer frame is +X north, +Y west and +Z up; the
+        // WasmObserver provides these directions in ECEF, and the
+        // quaternions mapping between the frames
+        this.observer_up_ecef_v = this.observer.up;
+        this.observer_ns_ecef_v = this.observer.north;
+        this.observer_we_ecef_v = this.observer.west;
+        // apply this to an ECEF direction vector to get an observer vector
         //
-        // console.log(this.observer_up_ecef_v.array, this.observer_ns_ecef_v.array, this.observer_we_ecef_v.array);
+        // Mapping the observer ECEF 'north' direction yields (1,0,0),
+        // 'west' yields (0,1,0) and 'up' yields (0,0,1)
+        this.ecef_to_observer_q = this.observer.ecef_to_observer;
         // apply this to an observer vector to get an ECEF direction vector
         //
         // So applying this to (1,0,0) gives the ECEF direction that
         // is north-ward parallel to the horizon; apply this to
         // (0,0,1) to get the direction up from feet through the head
-        // this.observer_to_ecef_q = this.ecef_to_observer_q.conjugate();
-        this.ecef_to_observer_q = WasmQuatf64.unit()
-            .rotate_z(Math.PI)
-            .rotate_y(this.de - Math.PI / 2)
-            .rotate_z(-this.ra);
-        this.observer_to_ecef_q = this.ecef_to_observer_q.conjugate();
-        // apply this to an ECEF direction vector to get an observer vector
-        // Mapping the observer ECEF 'north' direction should yield (1,0,0)
-        //
-        // Mapping the observer ECEF 'west' direction should yield (0,1,0)
-        //
-        // Mapping the observer ECEF 'up' direction should yield (0,0,1)
-        // console.log(this.ecef_to_observer_q.apply3(this.observer_ns_ecef_v).array);
-        // console.log(this.ecef_to_observer_q.apply3(this.observer_we_ecef_v).array);
-        // console.log(this.ecef_to_observer_q.apply3(this.observer_up_ecef_v).array);
+        this.observer_to_ecef_q = this.observer.observer_to_ecef;
         // The observed compass direction, elevation of the center of the
         // *viewer* requires mapping the viewer to the observer space
         // - so map the view_ecef_center_dir to observer
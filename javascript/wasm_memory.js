@@ -18,4 +18,10 @@ export class WasmMemory {
     float_array_of_vec3f32(vec) {
         return this.float32_array(vec.buffer, 3);
     }
+    uint32_array(ptr, num_words) {
+        return new Uint32Array(this.memory.buffer, ptr, num_words);
+    }
+    uint32_array_of_star_buffer(star_buffer) {
+        return this.uint32_array(star_buffer.buffer, star_buffer.length);
+    }
 }
@@ -0,0 +1,32 @@
+//a Documentation
+//! Annual aberration and light time
+//!
+//! Aberration displaces a star towards the direction of the Earth's
+//! motion by up to about 20.5 arcseconds
+
+//a Imports
+use crate::rotation;
+
+//a Constants
+/// The speed of light in AU per day
+pub const C_AU_PER_DAY: f64 = 173.144_632_674;
+
+//a Functions
+//fp light_time
+/// The time taken for light to travel a distance in AU, in days
+pub fn light_time(distance_au: f64) -> f64 {
+    distance_au / C_AU_PER_DAY
+}
+
+//fp aberrate
+/// Apply aberration for an observer velocity (in units of the speed
+/// of light) to a unit direction vector, returning the apparent unit
+/// vector
+///
+/// This is the first-order (classical) formula; the second-order terms
+/// are below a milliarcsecond
+pub fn aberrate(p: &[f64; 3], beta: &[f64; 3]) -> [f64; 3] {
+    let pv = rotation::dot(p, beta);
+    let v = rotation::add(p, &rotation::sub(beta, &rotation::scale(p, pv)));
+    rotation::normalize(&v)
+}
@@ -0,0 +1,98 @@
+//a Documentation
+//! The apparent place of a star for an observation date
+//!
+//! The apparent place takes the catalog (J2000) direction of a star,
+//! already moved by its proper motion to the date, and applies
+//! precession, nutation and annual aberration to give its direction
+//! relative to the true equator and equinox of the date
+//!
+//! Gravitational light deflection by the Sun (under 0.01 arcseconds
+//! away from the Sun) and diurnal aberration are not included
+
+//a Imports
+use crate::aberration;
+use crate::precession;
+use crate::rotation::{self, Mat3};
+use crate::sun;
+
+//a ApparentPlace
+//tp ApparentPlace
+/// The transformations for the apparent place at a date, precomputed
+/// so that they can be applied to many stars
+#[derive(Debug, Clone)]
+pub struct ApparentPlace {
+    /// Precession-nutation matrix from J2000 to true of date
+    pn: Mat3,
+    /// Velocity of the Earth in units of the speed of light, true
+    /// equator and equinox of date
+    beta: [f64; 3],
+}
+
+//ip ApparentPlace
+impl ApparentPlace {
+    //cp new
+    /// Create the apparent place transformations for a Julian date
+    pub fn new(julian_date: f64) -> Self {
+        let pn = precession::precession_nutation_matrix(julian_date);
+        let n = precession::nutation_matrix(julian_date);
+        let velocity = rotation::apply(&n, &sun::earth_velocity_of_date(julian_date));
+        let beta = rotation::scale(&velocity, 1. / aberration::C_AU_PER_DAY);
+        Self { pn, beta }
+    }
+
+    //mp apply
+    /// Get the apparent direction of a J2000 unit vector
+    pub fn apply(&self, v: &[f64; 3]) -> [f64; 3] {
+        let v = rotation::apply(&self.pn, v);
+        aberration::aberrate(&v, &self.beta)
+    }
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proper_motion::{ProperMotion, DAYS_PER_JULIAN_YEAR, JD_J2000};
+
+    /// One arcsecond in radians
+    const ARCSEC: f64 = std::f64::consts::PI / (180. * 3600.);
+
+    /// The angle between two unit vectors in arcseconds
+    fn arcsec_between(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+        rotation::length(&rotation::cross(a, b)).asin() / ARCSEC
+    }
+
+    #[test]
+    fn theta_persei() {
+        // Meeus, Astronomical Algorithms, example 23.a: theta Persei at
+        // 2028 November 13.19 TD
+        let ra = ((2. + 44. / 60. + 11.986 / 3600.) * 15.0_f64).to_radians();
+        let de = (49. + 13. / 60. + 42.48 / 3600.0_f64).to_radians();
+        let pm_ra = 0.03425 * 15. * 1000. * de.cos();
+        let pm = ProperMotion::new(pm_ra, -89.5, 0., 0.);
+        let jd = 2462088.69;
+        let v = pm.propagate(ra, de, (jd - JD_J2000) / DAYS_PER_JULIAN_YEAR);
+        let apparent = ApparentPlace::new(jd).apply(&v);
+        // 2h46m14.390s +49 21' 07.45"
+        let expected = rotation::vec_of_ra_de(
+            ((2. + 46. / 60. + 14.390 / 3600.) * 15.0_f64).to_radians(),
+            (49. + 21. / 60. + 7.45 / 3600.0_f64).to_radians(),
+        );
+        let error = arcsec_between(&apparent, &expected);
+        assert!(error < 0.1, "{error}");
+    }
+
+    #[test]
+    fn aberration_at_ecliptic_pole() {
+        // The constant of aberration is 20.4955 arcseconds; the
+        // eccentricity of the Earth's orbit changes it by up to 1.7%
+        for jd in [2451545.0, 2451636.0, 2451727.0, 2451818.0] {
+            let apparent_place = ApparentPlace::new(jd);
+            let pn = precession::precession_nutation_matrix(jd);
+            let pole = rotation::vec_of_ra_de(-std::f64::consts::FRAC_PI_2, 66.56_f64.to_radians());
+            let aberration =
+                arcsec_between(&rotation::apply(&pn, &pole), &apparent_place.apply(&pole));
+            assert!((aberration - 20.4955).abs() < 0.4, "{aberration}");
+        }
+    }
+}
@@ -0,0 +1,540 @@
+//a Documentation
+//! Calibration of a lens (focal length, principal point and distortion)
+//! from image positions matched to catalog stars
+//!
+//! For a given orientation of the camera the image position of each
+//! star is linear in the principal point, the focal length, and the
+//! distortion coefficients multiplied by the focal length; these are
+//! fitted by linear least squares. The orientation is then refitted
+//! from the directions of the image positions given the new lens, and
+//! the two steps repeated until the residuals settle
+//!
+//! Coefficients that are not fitted are zero; the principal point, if
+//! not fitted, is that of the lens given
+//!
+//! A calibration (or an initial match of stars, with a guessed focal
+//! length) may then be refined by Levenberg-Marquardt, jointly
+//! optimizing the orientation, focal length, principal point and
+//! distortion to minimize the pixel residuals; the Jacobian is found by
+//! central differences. Terms that are not refined keep their values
+
+//a Imports
+use crate::distortion::Distortion;
+use crate::least_squares::LeastSquares;
+use crate::lens::Lens;
+use crate::rotation::{self, Mat3};
+
+//a Constants
+/// Relative change in the RMS residual at which calibration stops
+const RMS_CONVERGENCE: f64 = 1e-9;
+
+/// Unknowns of the linear fit: the principal point X and Y (scaled by
+/// the aspect ratio), the focal length, the focal length times k1, k2
+/// and k3, and the focal length times p1 and p2
+const UNKNOWNS: usize = 8;
+
+/// Unknowns of the refinement: rotations about the camera X, Y and Z
+/// axes, the focal length, the principal point X and Y, k1, k2, k3, p1
+/// and p2
+const REFINED_UNKNOWNS: usize = 11;
+
+/// Initial, smallest and largest Levenberg-Marquardt damping
+const INITIAL_DAMPING: f64 = 1e-3;
+const MIN_DAMPING: f64 = 1e-9;
+const MAX_DAMPING: f64 = 1e9;
+
+//a CalibrationParameters
+//tp CalibrationParameters
+/// The terms of a lens calibration to fit
+#[derive(Debug, Clone)]
+pub struct CalibrationParameters {
+    /// Number of radial distortion coefficients fitted (0 to 3)
+    pub radial_terms: usize,
+    /// True if the tangential distortion coefficients are fitted
+    pub tangential: bool,
+    /// True if the principal point is fitted
+    pub principal_point: bool,
+    /// Most iterations of fitting the lens and then the orientation
+    pub iterations: usize,
+}
+
+//ip Default for CalibrationParameters
+impl Default for CalibrationParameters {
+    fn default() -> Self {
+        Self {
+            radial_terms: 3,
+            tangential: true,
+            principal_point: true,
+            iterations: 200,
+        }
+    }
+}
+
+//ip CalibrationParameters
+impl CalibrationParameters {
+    //mi fitted
+    /// True if an unknown of the linear fit is fitted
+    fn fitted(&self, unknown: usize) -> bool {
+        match unknown {
+            0 | 1 => self.principal_point,
+            2 => true,
+            3..=5 => unknown - 3 < self.radial_terms,
+            _ => self.tangential,
+        }
+    }
+
+    //mi refined
+    /// True if an unknown of the refinement is refined
+    fn refined(&self, unknown: usize) -> bool {
+        match unknown {
+            0..=3 => true,
+            4 | 5 => self.principal_point,
+            6..=8 => unknown - 6 < self.radial_terms,
+            _ => self.tangential,
+        }
+    }
+}
+
+//a Calibration
+//tp Calibration
+/// A lens and camera orientation fitted to image positions of stars
+#[derive(Debug, Clone)]
+pub struct Calibration {
+    /// The lens with its fitted focal length, principal point and
+    /// distortion
+    pub lens: Lens,
+    /// Rotation from the camera frame to the catalog frame
+    pub orientation: Mat3,
+    /// Image position of each star less its matched image position, in
+    /// pixels; NaN if the lens cannot image the star
+    pub residuals: Vec<[f64; 2]>,
+    /// Root mean square residual in pixels
+    pub rms: f64,
+}
+
+//ip Calibration
+impl Calibration {
+    //cp of_lens
+    /// Create a calibration for a lens and orientation, calculating the
+    /// residuals for image positions and their star vectors
+    pub fn of_lens(lens: Lens, orientation: Mat3, xy: &[[f64; 2]], stars: &[[f64; 3]]) -> Self {
+        let to_camera = rotation::transpose(&orientation);
+        let residuals: Vec<[f64; 2]> = xy
+            .iter()
+            .zip(stars)
+            .map(|(p, s)| {
+                lens.xy_of_vector(&rotation::apply(&to_camera, s))
+                    .map_or([f64::NAN; 2], |sxy| [sxy[0] - p[0], sxy[1] - p[1]])
+            })
+            .collect();
+        let rms = rms_of_residuals(&residuals);
+        Self {
+            lens,
+            orientation,
+            residuals,
+            rms,
+        }
+    }
+}
+
+//a Calibrating
+//fp calibrate
+/// Calibrate a lens from image positions in pixels matched to the unit
+/// vectors of their catalog stars, starting from an approximate lens
+///
+/// Returns None if there are too few matches for the terms to fit
+pub fn calibrate(
+    lens: &Lens,
+    xy: &[[f64; 2]],
+    stars: &[[f64; 3]],
+    parameters: &CalibrationParameters,
+) -> Option<Calibration> {
+    let n = xy.len().min(stars.len());
+    let (xy, stars) = (&xy[..n], &stars[..n]);
+    let unknowns: Vec<usize> = (0..UNKNOWNS).filter(|u| parameters.fitted(*u)).collect();
+    if 2 * n < unknowns.len() + 3 {
+        return None;
+    }
+
+    let mut lens = *lens;
+    let mut orientation = fit_orientation(&lens, xy, stars);
+    let mut best = Calibration::of_lens(lens, orientation, xy, stars);
+    for _ in 0..parameters.iterations {
+        let aspect_ratio = lens.aspect_ratio;
+        let to_camera = rotation::transpose(&orientation);
+        let mut least_squares = LeastSquares::new(unknowns.len());
+        for (p, s) in xy.iter().zip(stars) {
+            let Some([u, v]) = lens.ideal_of_vector(&rotation::apply(&to_camera, s)) else {
+                continue;
+            };
+            let r2 = u * u + v * v;
+            let (r4, r6) = (r2 * r2, r2 * r2 * r2);
+            let rows = [
+                (
+                    [
+                        1.,
+                        0.,
+                        u,
+                        u * r2,
+                        u * r4,
+                        u * r6,
+                        2. * u * v,
+                        r2 + 2. * u * u,
+                    ],
+                    p[0],
+                ),
+                (
+                    [
+                        0.,
+                        1.,
+                        v,
+                        v * r2,
+                        v * r4,
+                        v * r6,
+                        r2 + 2. * v * v,
+                        2. * u * v,
+                    ],
+                    p[1] * aspect_ratio,
+                ),
+            ];
+            for (row, value) in rows {
+                let mut value = value;
+                if !parameters.principal_point {
+                    value -= row[0] * lens.center[0] + row[1] * lens.center[1] * aspect_ratio;
+                }
+                let row: Vec<f64> = unknowns.iter().map(|u| row[*u]).collect();
+                least_squares.add(&row, value, 1.);
+            }
+        }
+        let Some(fit) = least_squares.solve(0.) else {
+            break;
+        };
+        let mut t = [0.; UNKNOWNS];
+        t[0] = lens.center[0];
+        t[1] = lens.center[1] * aspect_ratio;
+        for (u, x) in unknowns.iter().zip(fit) {
+            t[*u] = x;
+        }
+        let f = t[2];
+        if f <= 0. || !f.is_finite() {
+            break;
+        }
+        lens.center = [t[0], t[1] / aspect_ratio];
+        lens.focal_length = f;
+        lens.distortion = Distortion::new([t[3] / f, t[4] / f, t[5] / f], [t[6] / f, t[7] / f]);
+        orientation = fit_orientation(&lens, xy, stars);
+
+        let calibration = Calibration::of_lens(lens, orientation, xy, stars);
+        let converged = (best.rms - calibration.rms).abs() <= RMS_CONVERGENCE * best.rms;
+        if calibration.rms < best.rms || best.rms.is_nan() {
+            best = calibration;
+        }
+        if converged {
+            break;
+        }
+    }
+    Some(best)
+}
+
+//fp refine
+/// Refine a lens and the rotation from the camera frame to the catalog
+/// frame by Levenberg-Marquardt, given image positions in pixels matched
+/// to the unit vectors of their catalog stars
+///
+/// The orientation and focal length are always refined; matches that
+/// the starting lens cannot image are ignored (but have residuals).
+/// Returns None if there are too few matches for the terms to refine
+pub fn refine(
+    lens: &Lens,
+    orientation: &Mat3,
+    xy: &[[f64; 2]],
+    stars: &[[f64; 3]],
+    parameters: &CalibrationParameters,
+) -> Option<Calibration> {
+    let unknowns: Vec<usize> = (0..REFINED_UNKNOWNS)
+        .filter(|u| parameters.refined(*u))
+        .collect();
+    let to_camera = rotation::transpose(orientation);
+    let (used_xy, used_stars): (Vec<[f64; 2]>, Vec<[f64; 3]>) = xy
+        .iter()
+        .zip(stars)
+        .filter(|(_, s)| lens.xy_of_vector(&rotation::apply(&to_camera, s)).is_some())
+        .map(|(p, s)| (*p, *s))
+        .unzip();
+    if 2 * used_xy.len() < unknowns.len() {
+        return None;
+    }
+
+    let mut lens = *lens;
+    let mut orientation = *orientation;
+    let mut residuals = pixel_residuals(&lens, &orientation, &used_xy, &used_stars);
+    let mut cost = residuals.iter().map(|r| r * r).sum::<f64>();
+    let mut damping = INITIAL_DAMPING;
+    for _ in 0..parameters.iterations {
+        let jacobian: Vec<Vec<f64>> = unknowns
+            .iter()
+            .map(|u| {
+                let h = difference_step(&lens, *u);
+                let (l0, o0) = adjusted(&lens, &orientation, *u, h);
+                let (l1, o1) = adjusted(&lens, &orientation, *u, -h);
+                let r0 = pixel_residuals(&l0, &o0, &used_xy, &used_stars);
+                let r1 = pixel_residuals(&l1, &o1, &used_xy, &used_stars);
+                r0.iter()
+                    .zip(&r1)
+                    .map(|(a, b)| (a - b) / (2. * h))
+                    .collect()
+            })
+            .collect();
+        let mut least_squares = LeastSquares::new(unknowns.len());
+        for (i, r) in residuals.iter().enumerate() {
+            let row: Vec<f64> = jacobian.iter().map(|d| d[i]).collect();
+            least_squares.add(&row, -r, 1.);
+        }
+
+        let mut improved = false;
+        while damping < MAX_DAMPING {
+            let Some(step) = least_squares.solve(damping) else {
+                damping *= 10.;
+                continue;
+            };
+            let (mut l, mut o) = (lens, orientation);
+            for (u, d) in unknowns.iter().zip(&step) {
+                (l, o) = adjusted(&l, &o, *u, *d);
+            }
+            let r = pixel_residuals(&l, &o, &used_xy, &used_stars);
+            let c = r.iter().map(|r| r * r).sum::<f64>();
+            if c < cost {
+                improved = cost - c > RMS_CONVERGENCE * cost;
+                (lens, orientation, residuals, cost) = (l, o, r, c);
+                damping = (damping / 10.).max(MIN_DAMPING);
+                break;
+            }
+            damping *= 10.;
+        }
+        if !improved {
+            break;
+        }
+    }
+    Some(Calibration::of_lens(lens, orientation, xy, stars))
+}
+
+//fi pixel_residuals
+/// The image positions of the stars less their matched image positions,
+/// as x0, y0, x1, y1, ...; NaN for stars that cannot be imaged
+fn pixel_residuals(
+    lens: &Lens,
+    orientation: &Mat3,
+    xy: &[[f64; 2]],
+    stars: &[[f64; 3]],
+) -> Vec<f64> {
+    let to_camera = rotation::transpose(orientation);
+    xy.iter()
+        .zip(stars)
+        .flat_map(|(p, s)| {
+            lens.xy_of_vector(&rotation::apply(&to_camera, s))
+                .map_or([f64::NAN; 2], |sxy| [sxy[0] - p[0], sxy[1] - p[1]])
+        })
+        .collect()
+}
+
+//fi difference_step
+/// The step in an unknown of the refinement for the central differences
+fn difference_step(lens: &Lens, unknown: usize) -> f64 {
+    match unknown {
+        0..=2 => 1e-6,
+        3 => lens.focal_length * 1e-6,
+        4 | 5 => 1e-3,
+        _ => 1e-6,
+    }
+}
+
+//fi adjusted
+/// A lens and orientation with an unknown of the refinement changed by
+/// an amount
+fn adjusted(lens: &Lens, orientation: &Mat3, unknown: usize, d: f64) -> (Lens, Mat3) {
+    let mut lens = *lens;
+    let mut orientation = *orientation;
+    match unknown {
+        0 => orientation = rotation::mul(&orientation, &rotation::rot_x(d)),
+        1 => orientation = rotation::mul(&orientation, &rotation::rot_y(d)),
+        2 => orientation = rotation::mul(&orientation, &rotation::rot_z(d)),
+        3 => lens.focal_length += d,
+        4 | 5 => lens.center[unknown - 4] += d,
+        6..=8 => lens.distortion.k[unknown - 6] += d,
+        _ => lens.distortion.p[unknown - 9] += d,
+    }
+    (lens, orientation)
+}
+
+//fi fit_orientation
+/// The rotation from the camera frame to the catalog frame that best
+/// fits the directions of image positions (given a lens) to their stars
+fn fit_orientation(lens: &Lens, xy: &[[f64; 2]], stars: &[[f64; 3]]) -> Mat3 {
+    let pairs: Vec<_> = xy
+        .iter()
+        .zip(stars)
+        .map(|(p, s)| (lens.vector_of_xy(*p), *s, 1.))
+        .collect();
+    rotation::fit_rotation(&pairs)
+}
+
+//fp rms_of_residuals
+/// The root mean square of residuals in pixels, ignoring any that are
+/// NaN
+pub fn rms_of_residuals(residuals: &[[f64; 2]]) -> f64 {
+    let (sum, count) = residuals
+        .iter()
+        .filter(|r| !r[0].is_nan())
+        .fold((0., 0), |(s, c), r| (s + r[0] * r[0] + r[1] * r[1], c + 1));
+    if count == 0 {
+        0.
+    } else {
+        (sum / count as f64).sqrt()
+    }
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lens::LensProjection;
+
+    /// A lens for a 3000 by 2000 image with known distortion
+    fn known_lens() -> Lens {
+        let mut lens = Lens::new(LensProjection::Rectilinear, 2400., [1500., 1000.]);
+        lens.distortion = Distortion::new([-0.12, 0.05, 0.], [0.002, 0.]);
+        lens
+    }
+
+    /// An orientation of the camera, from the camera frame to the
+    /// catalog frame
+    fn known_orientation() -> Mat3 {
+        rotation::mul(
+            &rotation::mul(&rotation::rot_z(0.7), &rotation::rot_y(-0.4)),
+            &rotation::rot_x(0.2),
+        )
+    }
+
+    /// Image positions on a grid and the star vectors that the known
+    /// lens and orientation image there
+    fn matches() -> (Vec<[f64; 2]>, Vec<[f64; 3]>) {
+        let lens = known_lens();
+        let orientation = known_orientation();
+        let mut xy = vec![];
+        let mut stars = vec![];
+        for i in 0..9 {
+            for j in 0..7 {
+                let p = [150. + 337.5 * i as f64, 100. + 300. * j as f64];
+                xy.push(p);
+                stars.push(rotation::apply(&orientation, &lens.vector_of_xy(p)));
+            }
+        }
+        (xy, stars)
+    }
+
+    #[test]
+    fn calibrate_distortion() {
+        let (xy, stars) = matches();
+        let guess = Lens::new(LensProjection::Rectilinear, 2000., [1500., 1000.]);
+        let parameters = CalibrationParameters {
+            radial_terms: 2,
+            tangential: true,
+            principal_point: false,
+            ..Default::default()
+        };
+        let calibration = calibrate(&guess, &xy, &stars, &parameters).unwrap();
+        let lens = calibration.lens;
+        let expected = known_lens();
+        assert!(calibration.rms < 1e-3, "rms {}", calibration.rms);
+        assert!((lens.focal_length - expected.focal_length).abs() < 1e-3);
+        let k = lens.distortion.k;
+        let p = lens.distortion.p;
+        assert!(
+            (k[0] - expected.distortion.k[0]).abs() < 1e-6,
+            "k1 {}",
+            k[0]
+        );
+        assert!(
+            (k[1] - expected.distortion.k[1]).abs() < 1e-6,
+            "k2 {}",
+            k[1]
+        );
+        assert!(
+            (p[0] - expected.distortion.p[0]).abs() < 1e-6,
+            "p1 {}",
+            p[0]
+        );
+        assert!(p[1].abs() < 1e-6, "p2 {}", p[1]);
+        let orientation = known_orientation();
+        for (a, b) in calibration.orientation.iter().zip(&orientation) {
+            for (a, b) in a.iter().zip(b) {
+                assert!((a - b).abs() < 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn refine_orientation() {
+        // Start a few tenths of a degree from the orientation, with a
+        // focal length 10% short and no distortion
+        let (xy, stars) = matches();
+        let orientation = rotation::mul(
+            &known_orientation(),
+            &rotation::mul(
+                &rotation::rot_y(0.3_f64.to_radians()),
+                &rotation::rot_z(-0.2_f64.to_radians()),
+            ),
+        );
+        let guess = Lens::new(LensProjection::Rectilinear, 2160., [1500., 1000.]);
+        let parameters = CalibrationParameters {
+            radial_terms: 2,
+            tangential: true,
+            principal_point: false,
+            ..Default::default()
+        };
+        let calibration = refine(&guess, &orientation, &xy, &stars, &parameters).unwrap();
+        let lens = calibration.lens;
+        let expected = known_lens();
+        assert!(calibration.rms < 1e-3, "rms {}", calibration.rms);
+        assert_eq!(calibration.residuals.len(), xy.len());
+        for r in &calibration.residuals {
+            assert!(r[0].hypot(r[1]) < 1e-2, "{r:?}");
+        }
+        assert!((lens.focal_length - expected.focal_length).abs() < 1e-2);
+        assert_eq!(lens.center, expected.center);
+        let (k, p) = (lens.distortion.k, lens.distortion.p);
+        assert!(
+            (k[0] - expected.distortion.k[0]).abs() < 1e-5,
+            "k1 {}",
+            k[0]
+        );
+        assert!(
+            (k[1] - expected.distortion.k[1]).abs() < 1e-5,
+            "k2 {}",
+            k[1]
+        );
+        assert!(
+            (p[0] - expected.distortion.p[0]).abs() < 1e-5,
+            "p1 {}",
+            p[0]
+        );
+        assert!(p[1].abs() < 1e-5 && k[2] == 0., "p2 {} k3 {}", p[1], k[2]);
+        let error = rotation::mul(
+            &rotation::transpose(&calibration.orientation),
+            &known_orientation(),
+        );
+        for (i, row) in error.iter().enumerate() {
+            for (j, e) in row.iter().enumerate() {
+                let identity = if i == j { 1. } else { 0. };
+                assert!((e - identity).abs() < 1e-8, "{error:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn too_few_matches() {
+        let (xy, stars) = matches();
+        let parameters = CalibrationParameters::default();
+        assert!(calibrate(&known_lens(), &xy[..5], &stars[..5], &parameters).is_none());
+    }
+}
@@ -0,0 +1,293 @@
+//a Documentation
+//! Simple CSV text format for star catalogs
+//!
+//! Each non-blank line describes one star as comma-separated fields:
+//!
+//!   id, ra, de, distance, magnitude, bv [, name]
+//!
+//! with the right ascension (0 to 360) and declination (-90 to 90) in
+//! degrees and the distance in light years. Lines starting with '#',
+//! and a header line whose first field is 'id', are ignored.
+
+//a Imports
+use star_catalog::Star;
+
+use crate::proper_motion::ProperMotion;
+
+//a Constants
+/// The header line written at the top of a CSV catalog
+pub const CSV_HEADER: &str = "id,ra,de,distance,magnitude,bv,name";
+
+//a CsvStar
+//tp CsvStar
+/// A star read from a CSV line, with its optional name
+#[derive(Debug, Clone)]
+pub struct CsvStar {
+    pub star: Star,
+    pub name: Option<String>,
+}
+
+//fi parse_field
+fn parse_field<T: std::str::FromStr>(
+    line: usize,
+    fields: &[&str],
+    n: usize,
+    what: &str,
+) -> Result<T, String> {
+    let Some(f) = fields.get(n) else {
+        return Err(format!("line {line}: missing {what} field"));
+    };
+    f.parse::<T>()
+        .map_err(|_| format!("line {line}: could not parse {what} from '{f}'"))
+}
+
+//fi parse_name
+/// Parse the optional name field, which may be quoted with '"' (with
+/// any quotes within it doubled)
+fn parse_name(line: usize, field: Option<&str>) -> Result<Option<String>, String> {
+    let Some(f) = field else {
+        return Ok(None);
+    };
+    let name = match f.strip_prefix('"') {
+        None => f.to_string(),
+        Some(quoted) => {
+            let Some(inner) = quoted.strip_suffix('"') else {
+                return Err(format!("line {line}: unterminated quoted name '{f}'"));
+            };
+            if inner.replace("\"\"", "").contains('"') {
+                return Err(format!("line {line}: unescaped quote in name '{f}'"));
+            }
+            inner.replace("\"\"", "\"")
+        }
+    };
+    Ok((!name.is_empty()).then_some(name))
+}
+
+//fi format_name
+/// Format a name as a CSV field, quoting it if it contains a comma or
+/// quote or has surrounding whitespace; names with line breaks cannot
+/// be written
+fn format_name(name: &str) -> Result<String, String> {
+    if name.contains(['\n', '\r']) {
+        return Err(format!("Star name '{name}' contains a line break"));
+    }
+    if name.contains([',', '"']) || name.trim() != name {
+        Ok(format!("\"{}\"", name.replace('"', "\"\"")))
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+//fp parse_line
+/// Parse a single CSV line (numbered from 1 for errors); returns None
+/// for comment, header and blank lines
+pub fn parse_line(line: usize, text: &str) -> Result<Option<CsvStar>, String> {
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('#') {
+        return Ok(None);
+    }
+    let fields: Vec<&str> = text.splitn(7, ',').map(|f| f.trim()).collect();
+    if fields[0].eq_ignore_ascii_case("id") {
+        return Ok(None);
+    }
+    let id: usize = parse_field(line, &fields, 0, "id")?;
+    let ra: f64 = parse_field(line, &fields, 1, "right ascension")?;
+    let de: f64 = parse_field(line, &fields, 2, "declination")?;
+    let distance: f32 = parse_field(line, &fields, 3, "distance")?;
+    let magnitude: f32 = parse_field(line, &fields, 4, "magnitude")?;
+    let bv: f32 = parse_field(line, &fields, 5, "blue-violet")?;
+    if !(0.0..360.0).contains(&ra) {
+        return Err(format!("line {line}: right ascension {ra} out of range"));
+    }
+    if !(-90.0..=90.0).contains(&de) {
+        return Err(format!("line {line}: declination {de} out of range"));
+    }
+    for (value, what) in [
+        (distance, "distance"),
+        (magnitude, "magnitude"),
+        (bv, "blue-violet"),
+    ] {
+        if !value.is_finite() {
+            return Err(format!("line {line}: {what} must be finite"));
+        }
+    }
+    let name = parse_name(line, fields.get(6).copied())?;
+    let star = Star::new(
+        id,
+        ra.to_radians(),
+        de.to_radians(),
+        distance,
+        magnitude,
+        bv,
+    );
+    Ok(Some(CsvStar { star, name }))
+}
+
+//fp parse
+/// Parse CSV text into stars, failing on the first malformed line
+pub fn parse(text: &str) -> Result<Vec<CsvStar>, String> {
+    let mut stars = vec![];
+    for (n, l) in text.lines().enumerate() {
+        if let Some(s) = parse_line(n + 1, l)? {
+            stars.push(s);
+        }
+    }
+    Ok(stars)
+}
+
+//fp format_star
+/// Format a star (and optional name) as a single CSV line, without a
+/// line terminator; this fails if the name contains a line break
+pub fn format_star(star: &Star, name: Option<&str>) -> Result<String, String> {
+    let name = name.map(format_name).transpose()?;
+    Ok(format!(
+        "{},{},{},{},{},{},{}",
+        star.id(),
+        star.ra().to_degrees(),
+        star.de().to_degrees(),
+        star.distance(),
+        star.magnitude(),
+        star.bv(),
+        name.unwrap_or_default()
+    ))
+}
+
+//fp parse_motions
+/// Parse CSV text of proper motions, with lines of
+/// 'id,pm_ra,pm_de[,parallax[,rv]]' in milliarcseconds per year,
+/// milliarcseconds and km/s
+pub fn parse_motions(text: &str) -> Result<Vec<(usize, ProperMotion)>, String> {
+    let mut motions = vec![];
+    for (n, l) in text.lines().enumerate() {
+        let line = n + 1;
+        let l = l.trim();
+        if l.is_empty() || l.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = l.split(',').map(|f| f.trim()).collect();
+        if fields[0].eq_ignore_ascii_case("id") {
+            continue;
+        }
+        let id: usize = parse_field(line, &fields, 0, "id")?;
+        let pm_ra: f64 = parse_field(line, &fields, 1, "proper motion in RA")?;
+        let pm_de: f64 = parse_field(line, &fields, 2, "proper motion in declination")?;
+        let parallax: f64 = {
+            if fields.len() > 3 {
+                parse_field(line, &fields, 3, "parallax")?
+            } else {
+                0.
+            }
+        };
+        let rv: f64 = {
+            if fields.len() > 4 {
+                parse_field(line, &fields, 4, "radial velocity")?
+            } else {
+                0.
+            }
+        };
+        for (value, what) in [
+            (pm_ra, "proper motion in RA"),
+            (pm_de, "proper motion in declination"),
+            (parallax, "parallax"),
+            (rv, "radial velocity"),
+        ] {
+            if !value.is_finite() {
+                return Err(format!("line {line}: {what} must be finite"));
+            }
+        }
+        motions.push((id, ProperMotion::new(pm_ra, pm_de, parallax, rv)));
+    }
+    Ok(motions)
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_round_trip() {
+        for name in ["Sirius", "Alpha, Centauri", "\"Bob\"", " padded ", "a\"b"] {
+            let field = format_name(name).unwrap();
+            assert_eq!(parse_name(1, Some(&field)).unwrap().as_deref(), Some(name));
+        }
+        assert_eq!(format_name("Sirius").unwrap(), "Sirius");
+        assert_eq!(format_name("a,b").unwrap(), "\"a,b\"");
+        assert!(format_name("two\nlines").is_err());
+    }
+
+    #[test]
+    fn bad_names() {
+        assert_eq!(parse_name(1, None).unwrap(), None);
+        assert_eq!(parse_name(1, Some("")).unwrap(), None);
+        assert!(parse_name(3, Some("\"open"))
+            .unwrap_err()
+            .starts_with("line 3"));
+        assert!(parse_name(4, Some("\"a\"b\""))
+            .unwrap_err()
+            .starts_with("line 4"));
+    }
+
+    #[test]
+    fn lines() {
+        assert!(parse_line(1, "").unwrap().is_none());
+        assert!(parse_line(1, "# comment").unwrap().is_none());
+        assert!(parse_line(1, "id,ra,de,distance,magnitude,bv,name")
+            .unwrap()
+            .is_none());
+        let s = parse_line(2, " 32349, 101.287, -16.716, 8.6, -1.46, 0.009, Sirius ")
+            .unwrap()
+            .unwrap();
+        assert_eq!(s.star.id(), 32349);
+        assert!((s.star.ra() - 101.287_f64.to_radians()).abs() < 1e-12);
+        assert!((s.star.de() - (-16.716_f64).to_radians()).abs() < 1e-12);
+        assert_eq!(s.star.magnitude(), -1.46);
+        assert_eq!(s.name.as_deref(), Some("Sirius"));
+        let s = parse_line(3, "1,0,90,1,2,3,\"Polaris, Alpha UMi\"")
+            .unwrap()
+            .unwrap();
+        assert_eq!(s.name.as_deref(), Some("Polaris, Alpha UMi"));
+        assert!(parse_line(4, "1,0,0,1,2,3")
+            .unwrap()
+            .unwrap()
+            .name
+            .is_none());
+    }
+
+    #[test]
+    fn bad_lines() {
+        for text in [
+            "1,0,0,1,2",
+            "x,0,0,1,2,3",
+            "1,360,0,1,2,3",
+            "1,-1,0,1,2,3",
+            "1,NaN,0,1,2,3",
+            "1,0,90.5,1,2,3",
+            "1,0,0,inf,2,3",
+            "1,0,0,1,NaN,3",
+            "1,0,0,1,2,-inf",
+        ] {
+            let e = parse_line(7, text).unwrap_err();
+            assert!(e.starts_with("line 7:"), "{text}: {e}");
+        }
+        assert!(parse("1,0,0,1,2,3\n2,0,0,1,2\n")
+            .unwrap_err()
+            .starts_with("line 2:"));
+    }
+
+    #[test]
+    fn motions() {
+        let motions =
+            parse_motions("id,pm_ra,pm_de\n87937,-798.58,10328.12,548.31,-110.51\n2,1,2\n")
+                .unwrap();
+        assert_eq!(motions.len(), 2);
+        assert_eq!(motions[0].0, 87937);
+        assert_eq!(motions[0].1.pm_de, 10328.12);
+        assert_eq!(motions[0].1.rv, -110.51);
+        assert_eq!(motions[1].1.parallax, 0.);
+        for text in ["1,NaN,0", "1,0,inf", "1,0,0,NaN", "1,0,0,1,-inf", "1,0"] {
+            let e = parse_motions(&format!("# motions\n{text}\n")).unwrap_err();
+            assert!(e.starts_with("line 2:"), "{text}: {e}");
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//a Documentation
+//! Brown–Conrady lens distortion
+//!
+//! Distortion moves the ideal image position of a direction (where the
+//! projection of the lens places it) to where it is actually imaged.
+//! Positions are in focal lengths from the optical axis, with radial
+//! terms k1, k2 and k3 (in r^2, r^4 and r^6) and tangential (decentering)
+//! terms p1 and p2:
+//!
+//!   x' = x (1 + k1 r^2 + k2 r^4 + k3 r^6) + 2 p1 x y + p2 (r^2 + 2 x^2)
+//!   y' = y (1 + k1 r^2 + k2 r^4 + k3 r^6) + p1 (r^2 + 2 y^2) + 2 p2 x y
+//!
+//! For a rectilinear lens this is the usual model; for other projections
+//! it corrects the projection in the same way
+//!
+//! Undistorting has no closed form, and uses Newton's method starting
+//! from the distorted position
+
+//a Constants
+/// Most Newton steps when undistorting a position
+const UNDISTORT_ITERATIONS: usize = 20;
+
+/// Change in a position (in focal lengths) at which undistorting stops
+const UNDISTORT_TOLERANCE: f64 = 1e-12;
+
+//a Distortion
+//tp Distortion
+/// Radial and tangential distortion coefficients
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Distortion {
+    /// Radial coefficients k1, k2 and k3
+    pub k: [f64; 3],
+    /// Tangential coefficients p1 and p2
+    pub p: [f64; 2],
+}
+
+//ip Distortion
+impl Distortion {
+    //cp new
+    pub fn new(k: [f64; 3], p: [f64; 2]) -> Self {
+        Self { k, p }
+    }
+
+    //ap is_none
+    /// True if there is no distortion
+    pub fn is_none(&self) -> bool {
+        self.k == [0.; 3] && self.p == [0.; 2]
+    }
+
+    //ap coefficients
+    /// The coefficients as k1, k2, k3, p1, p2
+    pub fn coefficients(&self) -> [f64; 5] {
+        [self.k[0], self.k[1], self.k[2], self.p[0], self.p[1]]
+    }
+
+    //mp distort
+    /// The distorted position of an ideal position (in focal lengths)
+    pub fn distort(&self, xy: [f64; 2]) -> [f64; 2] {
+        let [x, y] = xy;
+        let r2 = x * x + y * y;
+        let radial = 1. + r2 * (self.k[0] + r2 * (self.k[1] + r2 * self.k[2]));
+        [
+            x * radial + 2. * self.p[0] * x * y + self.p[1] * (r2 + 2. * x * x),
+            y * radial + self.p[0] * (r2 + 2. * y * y) + 2. * self.p[1] * x * y,
+        ]
+    }
+
+    //mp jacobian
+    /// The derivatives of the distorted position with respect to the
+    /// ideal position, as [[dx'/dx, dx'/dy], [dy'/dx, dy'/dy]]
+    pub fn jacobian(&self, xy: [f64; 2]) -> [[f64; 2]; 2] {
+        let [x, y] = xy;
+        let r2 = x * x + y * y;
+        let radial = 1. + r2 * (self.k[0] + r2 * (self.k[1] + r2 * self.k[2]));
+        let d_radial = self.k[0] + r2 * (2. * self.k[1] + 3. * r2 * self.k[2]);
+        let cross = 2. * x * y * d_radial + 2. * self.p[0] * x + 2. * self.p[1] * y;
+        [
+            [
+                radial + 2. * x * x * d_radial + 2. * self.p[0] * y + 6. * self.p[1] * x,
+                cross,
+            ],
+            [
+                cross,
+                radial + 2. * y * y * d_radial + 6. * self.p[0] * y + 2. * self.p[1] * x,
+            ],
+        ]
+    }
+
+    //mp undistort
+    /// The ideal position (in focal lengths) of a distorted position
+    pub fn undistort(&self, xy: [f64; 2]) -> [f64; 2] {
+        if self.is_none() {
+            return xy;
+        }
+        let mut ideal = xy;
+        for _ in 0..UNDISTORT_ITERATIONS {
+            let d = self.distort(ideal);
+            let (ex, ey) = (d[0] - xy[0], d[1] - xy[1]);
+            let [[a, b], [c, e]] = self.jacobian(ideal);
+            let det = a * e - b * c;
+            if det.abs() < 1e-12 {
+                break;
+            }
+            let dx = (e * ex - b * ey) / det;
+            let dy = (a * ey - c * ex) / det;
+            ideal = [ideal[0] - dx, ideal[1] - dy];
+            if dx.abs() + dy.abs() < UNDISTORT_TOLERANCE {
+                break;
+            }
+        }
+        ideal
+    }
+}
@@ -0,0 +1,567 @@
+//a Documentation
+//! Extraction of point sources (stars) from camera images
+//!
+//! An image is reduced to a single luminance channel, and the sky
+//! background and its noise are estimated in square cells by a sigma
+//! clipped median and median absolute deviation; these are interpolated
+//! between the centers of the cells to give the background and noise at
+//! every pixel
+//!
+//! Pixels above the background by a number of standard deviations of
+//! the noise are grouped into 8-connected components; each component
+//! with a suitable number of pixels is a source. Each source is
+//! measured over its pixels and the unlabelled pixels around them, with
+//! the background removed, giving its flux, intensity weighted centroid
+//! and FWHM (from the second moments, assuming a Gaussian profile)
+//!
+//! Pixel coordinates have the origin at the top left corner of the
+//! image, so the center of pixel (i, j) is at (i + 0.5, j + 0.5); these
+//! match the image coordinates of a canvas
+
+//a Constants
+/// Number of iterations of sigma clipping of the background of a cell
+const CLIP_ITERATIONS: usize = 3;
+
+/// Number of standard deviations from the median beyond which values
+/// are clipped when estimating the background
+const CLIP_SIGMA: f32 = 3.0;
+
+/// Ratio of the standard deviation of a Gaussian to its median
+/// absolute deviation
+const MAD_TO_SIGMA: f32 = 1.4826;
+
+/// Smallest noise permitted, about the quantization noise of an 8-bit
+/// image, so that a noiseless background does not detect every pixel
+const MIN_NOISE: f32 = 0.5;
+
+/// Ratio of the FWHM of a Gaussian to its standard deviation
+const FWHM_OF_SIGMA: f64 = 2.354_820_045;
+
+/// Number of pixels around a component included in its measurement
+const MEASURE_MARGIN: usize = 2;
+
+//a Image
+//tp Image
+/// A single channel image with a flag for each pixel that is saturated
+#[derive(Debug, Clone)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<f32>,
+    saturated: Vec<bool>,
+}
+
+//ip Image
+impl Image {
+    //cp of_bytes
+    /// Create from greyscale, greyscale and alpha, RGB or RGBA bytes (as
+    /// given by a canvas), with the number of channels deduced from the
+    /// length of the data
+    ///
+    /// Color is converted to luminance with the Rec. 601 weights; a pixel
+    /// is saturated if any of its color channels is at or above the
+    /// saturation level
+    pub fn of_bytes(
+        data: &[u8],
+        width: usize,
+        height: usize,
+        saturation: u8,
+    ) -> Result<Self, String> {
+        let Some(n) = width.checked_mul(height) else {
+            return Err(format!("Image of {width} by {height} pixels is too large"));
+        };
+        if n == 0 {
+            return Err("Image has no pixels".into());
+        }
+        let channels = data.len() / n;
+        if channels * n != data.len() || !(1..=4).contains(&channels) {
+            return Err(format!(
+                "Image data of {} bytes does not match {width} by {height} pixels",
+                data.len()
+            ));
+        }
+        let mut pixels = Vec::with_capacity(n);
+        let mut saturated = Vec::with_capacity(n);
+        for p in data.chunks_exact(channels) {
+            let color = if channels >= 3 { &p[0..3] } else { &p[0..1] };
+            saturated.push(color.iter().any(|c| *c >= saturation));
+            if channels >= 3 {
+                pixels.push(0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32);
+            } else {
+                pixels.push(p[0] as f32);
+            }
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            saturated,
+        })
+    }
+
+    //ap pixel
+    /// The value of the pixel at a column and row
+    pub fn pixel(&self, x: usize, y: usize) -> f32 {
+        self.pixels[y * self.width + x]
+    }
+}
+
+//a Background
+//tp Background
+/// The background level and noise of an image, estimated in square
+/// cells
+#[derive(Debug, Clone)]
+pub struct Background {
+    cell: usize,
+    columns: usize,
+    rows: usize,
+    level: Vec<f32>,
+    noise: Vec<f32>,
+}
+
+//ip Background
+impl Background {
+    //cp of_image
+    /// Estimate the background of an image in cells of a size in pixels
+    ///
+    /// The estimates of the cells are median filtered with their
+    /// neighbours, so that a cell covered by a bright object takes the
+    /// background of its surroundings
+    pub fn of_image(image: &Image, cell: usize) -> Self {
+        let cell = cell.max(4);
+        let columns = image.width.div_ceil(cell);
+        let rows = image.height.div_ceil(cell);
+        let mut level = Vec::with_capacity(columns * rows);
+        let mut noise = Vec::with_capacity(columns * rows);
+        let mut values = Vec::with_capacity(cell * cell);
+        for r in 0..rows {
+            for c in 0..columns {
+                values.clear();
+                for y in (r * cell)..((r + 1) * cell).min(image.height) {
+                    let row = &image.pixels[y * image.width..(y + 1) * image.width];
+                    values.extend_from_slice(&row[(c * cell)..((c + 1) * cell).min(image.width)]);
+                }
+                let (l, n) = clipped_median(&mut values);
+                level.push(l);
+                noise.push(n);
+            }
+        }
+        let level = median_filter(&level, columns, rows);
+        let noise = median_filter(&noise, columns, rows);
+        Self {
+            cell,
+            columns,
+            rows,
+            level,
+            noise,
+        }
+    }
+
+    //ap median_level
+    /// The median of the background level over the cells
+    pub fn median_level(&self) -> f32 {
+        median(&mut self.level.clone())
+    }
+
+    //ap median_noise
+    /// The median of the noise over the cells
+    pub fn median_noise(&self) -> f32 {
+        median(&mut self.noise.clone())
+    }
+
+    //mp level_at
+    /// The background level at a column and row
+    pub fn level_at(&self, x: usize, y: usize) -> f32 {
+        self.interpolate(&self.level, x, y)
+    }
+
+    //mp noise_at
+    /// The standard deviation of the noise at a column and row
+    pub fn noise_at(&self, x: usize, y: usize) -> f32 {
+        self.interpolate(&self.noise, x, y).max(MIN_NOISE)
+    }
+
+    //mi interpolate
+    /// Bilinearly interpolate values of the cells between their centers
+    /// at a column and row
+    fn interpolate(&self, values: &[f32], x: usize, y: usize) -> f32 {
+        let position = |p: usize, n: usize| {
+            let f = ((p as f32 + 0.5) / self.cell as f32 - 0.5).clamp(0., (n - 1) as f32);
+            let i = (f as usize).min(n.saturating_sub(2));
+            (i, (i + 1).min(n - 1), f - i as f32)
+        };
+        let (c0, c1, fx) = position(x, self.columns);
+        let (r0, r1, fy) = position(y, self.rows);
+        let v = |c: usize, r: usize| values[r * self.columns + c];
+        let top = v(c0, r0) * (1. - fx) + v(c1, r0) * fx;
+        let bottom = v(c0, r1) * (1. - fx) + v(c1, r1) * fx;
+        top * (1. - fy) + bottom * fy
+    }
+}
+
+//a ExtractParameters
+//tp ExtractParameters
+/// Parameters controlling the extraction of sources from an image
+#[derive(Debug, Clone)]
+pub struct ExtractParameters {
+    /// Detection threshold in standard deviations of the noise above the
+    /// background
+    pub threshold: f64,
+    /// Fewest pixels above the threshold in a source; fewer than this
+    /// are noise or hot pixels
+    pub min_pixels: usize,
+    /// Most pixels above the threshold in a source; more than this are
+    /// extended objects (or the foreground)
+    pub max_pixels: usize,
+    /// Size in pixels of the cells used to estimate the background
+    pub background_cell: usize,
+    /// Level of an image byte at or above which the pixel is saturated
+    pub saturation: u8,
+    /// Electrons per image unit, for the photon noise of a source
+    pub gain: f64,
+    /// Most sources returned, the brightest first
+    pub max_sources: usize,
+}
+
+//ip Default for ExtractParameters
+impl Default for ExtractParameters {
+    fn default() -> Self {
+        Self {
+            threshold: 5.0,
+            min_pixels: 3,
+            max_pixels: 2500,
+            background_cell: 64,
+            saturation: 255,
+            gain: 1.0,
+            max_sources: 500,
+        }
+    }
+}
+
+//a ImageSource
+//tp ImageSource
+/// A source found in an image
+#[derive(Debug, Clone, Default)]
+pub struct ImageSource {
+    /// Column of the centroid in pixels
+    pub x: f64,
+    /// Row of the centroid in pixels
+    pub y: f64,
+    /// Sum of the values above the background
+    pub flux: f64,
+    /// Largest value above the background
+    pub peak: f64,
+    /// Background level at the centroid
+    pub background: f64,
+    /// Full width at half maximum in pixels
+    pub fwhm: f64,
+    /// Signal to noise ratio of the flux
+    pub snr: f64,
+    /// Number of pixels above the detection threshold
+    pub pixels: usize,
+    /// True if any of the pixels of the source is saturated, in which
+    /// case the flux and FWHM are underestimated
+    pub saturated: bool,
+}
+
+//a Extraction
+//fp extract_sources
+/// Extract the sources from an image, brightest first, with the
+/// background of the image
+pub fn extract_sources(
+    image: &Image,
+    parameters: &ExtractParameters,
+) -> (Background, Vec<ImageSource>) {
+    let background = Background::of_image(image, parameters.background_cell);
+    let (w, h) = (image.width, image.height);
+
+    let mut above = vec![false; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            let threshold = background.level_at(x, y) as f64
+                + parameters.threshold * background.noise_at(x, y) as f64;
+            above[y * w + x] = image.pixel(x, y) as f64 > threshold;
+        }
+    }
+
+    let (labels, components) = label_components(&above, w, h);
+    let mut sources: Vec<ImageSource> = components
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| (parameters.min_pixels..=parameters.max_pixels).contains(&c.len()))
+        .filter_map(|(i, c)| {
+            measure(
+                image,
+                &background,
+                &labels,
+                (i + 1) as u32,
+                c,
+                parameters.gain,
+            )
+        })
+        .collect();
+    sources.sort_by(|a, b| b.flux.total_cmp(&a.flux));
+    sources.truncate(parameters.max_sources);
+    (background, sources)
+}
+
+//fi label_components
+/// Label the 8-connected components of the pixels that are set, with
+/// labels from 1 (0 for pixels that are clear), returning the labels and
+/// the pixel indices of each component
+fn label_components(set: &[bool], w: usize, h: usize) -> (Vec<u32>, Vec<Vec<usize>>) {
+    let mut labels = vec![0_u32; w * h];
+    let mut components = vec![];
+    let mut stack = vec![];
+    for start in 0..(w * h) {
+        if !set[start] || labels[start] != 0 {
+            continue;
+        }
+        let label = (components.len() + 1) as u32;
+        let mut component = vec![];
+        labels[start] = label;
+        stack.push(start);
+        while let Some(p) = stack.pop() {
+            component.push(p);
+            let (x, y) = (p % w, p / w);
+            for ny in y.saturating_sub(1)..=(y + 1).min(h - 1) {
+                for nx in x.saturating_sub(1)..=(x + 1).min(w - 1) {
+                    let n = ny * w + nx;
+                    if set[n] && labels[n] == 0 {
+                        labels[n] = label;
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+        components.push(component);
+    }
+    (labels, components)
+}
+
+//fi measure
+/// Measure a component of an image, over its bounding box expanded by
+/// a margin excluding the pixels of other components
+fn measure(
+    image: &Image,
+    background: &Background,
+    labels: &[u32],
+    label: u32,
+    component: &[usize],
+    gain: f64,
+) -> Option<ImageSource> {
+    let w = image.width;
+    let (mut x0, mut y0, mut x1, mut y1) = (usize::MAX, usize::MAX, 0, 0);
+    let mut saturated = false;
+    for p in component {
+        let (x, y) = (p % w, p / w);
+        (x0, x1) = (x0.min(x), x1.max(x));
+        (y0, y1) = (y0.min(y), y1.max(y));
+        saturated |= image.saturated[*p];
+    }
+    x0 = x0.saturating_sub(MEASURE_MARGIN);
+    y0 = y0.saturating_sub(MEASURE_MARGIN);
+    x1 = (x1 + MEASURE_MARGIN).min(w - 1);
+    y1 = (y1 + MEASURE_MARGIN).min(image.height - 1);
+
+    let mut samples = vec![];
+    let mut variance = 0.;
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let l = labels[y * w + x];
+            if l != 0 && l != label {
+                continue;
+            }
+            let v = (image.pixel(x, y) - background.level_at(x, y)) as f64;
+            variance += (background.noise_at(x, y) as f64).powi(2);
+            samples.push((x as f64 + 0.5, y as f64 + 0.5, v));
+        }
+    }
+    let flux: f64 = samples.iter().map(|(_, _, v)| v).sum();
+    if flux <= 0. {
+        return None;
+    }
+    // The moments are weighted by the positive values only, so that the
+    // noise around a faint source cannot make them negative
+    let weight: f64 = samples.iter().map(|(_, _, v)| v.max(0.)).sum();
+    let x = samples.iter().map(|(x, _, v)| x * v.max(0.)).sum::<f64>() / weight;
+    let y = samples.iter().map(|(_, y, v)| y * v.max(0.)).sum::<f64>() / weight;
+    let r2 = samples
+        .iter()
+        .map(|(sx, sy, v)| ((sx - x).powi(2) + (sy - y).powi(2)) * v.max(0.))
+        .sum::<f64>()
+        / weight;
+    let peak = component
+        .iter()
+        .map(|p| (image.pixels[*p] - background.level_at(p % w, p / w)) as f64)
+        .fold(0., f64::max);
+    let snr = flux / (flux / gain.max(1e-6) + variance).sqrt();
+    let (cx, cy) = ((x as usize).min(w - 1), (y as usize).min(image.height - 1));
+    Some(ImageSource {
+        x,
+        y,
+        flux,
+        peak,
+        background: background.level_at(cx, cy) as f64,
+        fwhm: FWHM_OF_SIGMA * (r2 / 2.).sqrt(),
+        snr,
+        pixels: component.len(),
+        saturated,
+    })
+}
+
+//a Statistics
+//fi median
+/// The median of some values, reordering them
+fn median(values: &mut [f32]) -> f32 {
+    if values.is_empty() {
+        return 0.;
+    }
+    let n = values.len() / 2;
+    *values.select_nth_unstable_by(n, f32::total_cmp).1
+}
+
+//fi clipped_median
+/// The sigma clipped median and standard deviation (from the median
+/// absolute deviation) of some values, reordering them
+fn clipped_median(values: &mut Vec<f32>) -> (f32, f32) {
+    let mut level = median(values);
+    let mut sigma = 0.;
+    let mut deviations = Vec::with_capacity(values.len());
+    for _ in 0..CLIP_ITERATIONS {
+        deviations.clear();
+        deviations.extend(values.iter().map(|v| (v - level).abs()));
+        sigma = median(&mut deviations) * MAD_TO_SIGMA;
+        let limit = CLIP_SIGMA * sigma.max(MIN_NOISE);
+        let n = values.len();
+        values.retain(|v| (v - level).abs() <= limit);
+        if values.is_empty() || values.len() == n {
+            break;
+        }
+        level = median(values);
+    }
+    (level, sigma)
+}
+
+//fi median_filter
+/// Replace each value of a grid by the median of it and its neighbours
+fn median_filter(values: &[f32], columns: usize, rows: usize) -> Vec<f32> {
+    let mut result = Vec::with_capacity(values.len());
+    let mut window = Vec::with_capacity(9);
+    for r in 0..rows {
+        for c in 0..columns {
+            window.clear();
+            for nr in r.saturating_sub(1)..=(r + 1).min(rows - 1) {
+                for nc in c.saturating_sub(1)..=(c + 1).min(columns - 1) {
+                    window.push(values[nr * columns + nc]);
+                }
+            }
+            result.push(median(&mut window));
+        }
+    }
+    result
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stars of an image as (x, y, peak above the background)
+    const STARS: [(f64, f64, f64); 4] = [
+        (40.3, 30.7, 150.),
+        (100.62, 80.25, 100.),
+        (150.45, 40.85, 200.),
+        (60.5, 110.5, 800.),
+    ];
+
+    /// Standard deviation of the Gaussian profile of the stars
+    const STAR_SIGMA: f64 = 1.5;
+
+    /// A 200 by 150 greyscale image of the stars on a background of 40
+    /// with noise of standard deviation 3, clipped at 255
+    fn synthetic_image() -> Vec<u8> {
+        // A linear congruential generator, with the sum of 12 uniform
+        // values less 6 being nearly Gaussian with unit variance
+        let mut seed: u64 = 12345;
+        let mut uniform = move || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1_u64 << 53) as f64
+        };
+        let mut data = Vec::with_capacity(200 * 150);
+        for y in 0..150 {
+            for x in 0..200 {
+                let noise: f64 = (0..12).map(|_| uniform()).sum::<f64>() - 6.;
+                let mut v = 40. + 3. * noise;
+                for (sx, sy, peak) in STARS {
+                    let r2 = (x as f64 + 0.5 - sx).powi(2) + (y as f64 + 0.5 - sy).powi(2);
+                    v += peak * (-r2 / (2. * STAR_SIGMA * STAR_SIGMA)).exp();
+                }
+                data.push(v.round().clamp(0., 255.) as u8);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn sources() {
+        let image = Image::of_bytes(&synthetic_image(), 200, 150, 255).unwrap();
+        let parameters = ExtractParameters {
+            background_cell: 32,
+            ..Default::default()
+        };
+        let (background, sources) = extract_sources(&image, &parameters);
+        assert!((background.median_level() - 40.).abs() < 0.5);
+        assert!((background.median_noise() - 3.).abs() < 0.3);
+        assert_eq!(sources.len(), STARS.len(), "{sources:?}");
+
+        // The clipped star is the brightest; the others are in order of
+        // their peaks
+        assert!(sources[0].saturated);
+        assert!((sources[0].x - 60.5).abs() < 0.05 && (sources[0].y - 110.5).abs() < 0.05);
+        let fwhm = FWHM_OF_SIGMA * STAR_SIGMA;
+        for (source, (x, y, peak)) in sources[1..].iter().zip([STARS[2], STARS[0], STARS[1]]) {
+            assert!(!source.saturated);
+            assert!((source.x - x).abs() < 0.1, "{source:?}");
+            assert!((source.y - y).abs() < 0.1, "{source:?}");
+            assert!((source.fwhm / fwhm - 1.).abs() < 0.15, "{source:?}");
+            assert!((source.peak / peak - 1.).abs() < 0.15, "{source:?}");
+            let flux = std::f64::consts::TAU * STAR_SIGMA * STAR_SIGMA * peak;
+            assert!((source.flux / flux - 1.).abs() < 0.1, "{source:?}");
+            assert!((source.background - 40.).abs() < 1., "{source:?}");
+        }
+    }
+
+    #[test]
+    fn channels() {
+        // Greyscale, greyscale and alpha, RGB and RGBA of two pixels
+        let image = Image::of_bytes(&[10, 250], 2, 1, 250).unwrap();
+        assert_eq!((image.pixel(0, 0), image.pixel(1, 0)), (10., 250.));
+        assert_eq!(image.saturated, [false, true]);
+
+        let image = Image::of_bytes(&[10, 255, 250, 0], 1, 2, 250).unwrap();
+        assert_eq!((image.pixel(0, 0), image.pixel(0, 1)), (10., 250.));
+        assert_eq!(image.saturated, [false, true]);
+
+        let luminance = 0.299 * 100. + 0.587 * 50. + 0.114 * 250.;
+        let image = Image::of_bytes(&[100, 50, 250, 0, 0, 0], 2, 1, 250).unwrap();
+        assert!((image.pixel(0, 0) - luminance).abs() < 1e-3);
+        assert_eq!(image.pixel(1, 0), 0.);
+        assert_eq!(image.saturated, [true, false]);
+
+        let image = Image::of_bytes(&[100, 50, 250, 255, 0, 0, 0, 255], 2, 1, 255).unwrap();
+        assert!((image.pixel(0, 0) - luminance).abs() < 1e-3);
+        assert_eq!(image.saturated, [false, false]);
+    }
+
+    #[test]
+    fn bad_images() {
+        assert!(Image::of_bytes(&[], 0, 10, 255).is_err());
+        assert!(Image::of_bytes(&[0; 3], 2, 1, 255).is_err());
+        assert!(Image::of_bytes(&[0; 10], 2, 1, 255).is_err());
+        assert!(Image::of_bytes(&[0; 4], usize::MAX, 2, 255).is_err());
+        assert!(Image::of_bytes(&[0; 4], 1 << 32, 1 << 32, 255).is_err());
+    }
+}
@@ -0,0 +1,431 @@
+//a Documentation
+//! Keplerian orbits of any eccentricity
+//!
+//! An orbit is described by its periapsis distance and eccentricity,
+//! its orientation (inclination, longitude of the ascending node and
+//! argument of periapsis) relative to the parent frame, and the time
+//! of periapsis; using the periapsis distance rather than the
+//! semi-major axis allows parabolic orbits (e = 1), as used for many
+//! comets
+//!
+//! Kepler's equation is solved by Newton's method for elliptical and
+//! hyperbolic orbits, and Barker's equation is solved directly for
+//! parabolic orbits. Orbits within [NEAR_PARABOLIC] of an eccentricity
+//! of one are treated as parabolic, as the elliptical and hyperbolic
+//! forms lose precision there
+//!
+//! Distances are in meters and times in seconds (Unix times for
+//! epochs)
+
+//a Imports
+use crate::rotation::{self, Mat3};
+
+//a Constants
+/// Gravitational parameter of the Sun in m^3/s^2
+pub const GM_SUN: f64 = 1.327_124_400_18e20;
+
+/// Difference of an eccentricity from one within which an orbit is
+/// treated as parabolic
+pub const NEAR_PARABOLIC: f64 = 1.0e-8;
+
+/// Maximum number of Newton iterations when solving Kepler's equation
+const MAX_ITERATIONS: usize = 50;
+
+/// Convergence tolerance (radians) when solving Kepler's equation
+const TOLERANCE: f64 = 1.0e-14;
+
+//a Kepler
+//tp Kepler
+/// A Keplerian orbit around a parent of a given gravitational parameter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Kepler {
+    /// Periapsis distance in meters
+    pub periapsis: f64,
+    /// Eccentricity; less than one is elliptical, one parabolic and
+    /// greater than one hyperbolic
+    pub eccentricity: f64,
+    /// Inclination to the parent frame's XY plane in radians
+    pub inclination: f64,
+    /// Longitude of the ascending node in radians
+    pub ascending_node: f64,
+    /// Argument of periapsis in radians
+    pub arg_periapsis: f64,
+    /// Unix time of periapsis in seconds
+    pub periapsis_time: f64,
+    /// Gravitational parameter of the parent (plus body) in m^3/s^2
+    pub gm: f64,
+}
+
+//ip Kepler
+impl Kepler {
+    //cp new
+    pub fn new(
+        periapsis: f64,
+        eccentricity: f64,
+        inclination: f64,
+        ascending_node: f64,
+        arg_periapsis: f64,
+        periapsis_time: f64,
+        gm: f64,
+    ) -> Self {
+        Self {
+            periapsis,
+            eccentricity,
+            inclination,
+            ascending_node,
+            arg_periapsis,
+            periapsis_time,
+            gm,
+        }
+    }
+
+    //cp of_mean_anomaly
+    /// Create from a semi-major axis (negative for a hyperbolic orbit)
+    /// and the mean anomaly at an epoch, as used for planets and
+    /// asteroids; this cannot describe a parabolic orbit
+    #[allow(clippy::too_many_arguments)]
+    pub fn of_mean_anomaly(
+        semi_major_axis: f64,
+        eccentricity: f64,
+        inclination: f64,
+        ascending_node: f64,
+        arg_periapsis: f64,
+        mean_anomaly: f64,
+        epoch: f64,
+        gm: f64,
+    ) -> Self {
+        let periapsis = semi_major_axis * (1. - eccentricity);
+        let n = (gm / semi_major_axis.abs().powi(3)).sqrt();
+        let periapsis_time = epoch - mean_anomaly / n;
+        Self::new(
+            periapsis,
+            eccentricity,
+            inclination,
+            ascending_node,
+            arg_periapsis,
+            periapsis_time,
+            gm,
+        )
+    }
+
+    //mp validate
+    /// Check that the elements describe an orbit
+    pub fn validate(&self) -> Result<(), String> {
+        if self.periapsis.is_nan() || self.periapsis <= 0. {
+            Err(format!(
+                "Periapsis distance must be positive, not {}",
+                self.periapsis
+            ))
+        } else if self.eccentricity.is_nan() || self.eccentricity < 0. {
+            Err(format!(
+                "Eccentricity must be non-negative, not {}",
+                self.eccentricity
+            ))
+        } else if self.gm.is_nan() || self.gm <= 0. {
+            Err(format!(
+                "Gravitational parameter must be positive, not {}",
+                self.gm
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    //ap is_parabolic
+    pub fn is_parabolic(&self) -> bool {
+        (self.eccentricity - 1.).abs() < NEAR_PARABOLIC
+    }
+
+    //ap is_elliptical
+    pub fn is_elliptical(&self) -> bool {
+        !self.is_parabolic() && self.eccentricity < 1.
+    }
+
+    //ap semi_major_axis
+    /// The semi-major axis in meters; negative for a hyperbolic orbit,
+    /// and infinite for a parabolic orbit
+    pub fn semi_major_axis(&self) -> f64 {
+        if self.is_parabolic() {
+            f64::INFINITY
+        } else {
+            self.periapsis / (1. - self.eccentricity)
+        }
+    }
+
+    //ap apoapsis
+    /// The apoapsis distance in meters; infinite unless the orbit is
+    /// elliptical
+    pub fn apoapsis(&self) -> f64 {
+        if self.is_elliptical() {
+            self.semi_major_axis() * (1. + self.eccentricity)
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    //ap period
+    /// The period in seconds; infinite unless the orbit is elliptical
+    pub fn period(&self) -> f64 {
+        if self.is_elliptical() {
+            std::f64::consts::TAU / self.mean_motion()
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    //ap mean_motion
+    /// The mean motion in radians per second; for a parabolic orbit this
+    /// is the rate used in Barker's equation
+    pub fn mean_motion(&self) -> f64 {
+        if self.is_parabolic() {
+            (self.gm / (2. * self.periapsis.powi(3))).sqrt()
+        } else {
+            (self.gm / self.semi_major_axis().abs().powi(3)).sqrt()
+        }
+    }
+
+    //mp orbit_to_parent
+    /// The rotation taking orbit plane vectors (+X towards periapsis,
+    /// +Z along the angular momentum) to the parent frame
+    pub fn orbit_to_parent(&self) -> Mat3 {
+        rotation::mul(
+            &rotation::rot_z(-self.ascending_node),
+            &rotation::mul(
+                &rotation::rot_x(-self.inclination),
+                &rotation::rot_z(-self.arg_periapsis),
+            ),
+        )
+    }
+
+    //mp true_anomaly_of_time
+    /// The true anomaly in radians at a Unix time
+    pub fn true_anomaly_of_time(&self, time: f64) -> f64 {
+        let e = self.eccentricity;
+        let m = self.mean_motion() * (time - self.periapsis_time);
+        if self.is_parabolic() {
+            2. * solve_barker(m).atan()
+        } else if e < 1. {
+            let big_e = solve_elliptic(m, e);
+            let (s, c) = (big_e / 2.).sin_cos();
+            2. * ((1. + e).sqrt() * s).atan2((1. - e).sqrt() * c)
+        } else {
+            let h = solve_hyperbolic(m, e);
+            2. * (((e + 1.) / (e - 1.)).sqrt() * (h / 2.).tanh()).atan()
+        }
+    }
+
+    //mp orbit_vec_of_true_anomaly
+    /// The position in the orbit plane (meters) at a true anomaly
+    pub fn orbit_vec_of_true_anomaly(&self, true_anomaly: f64) -> [f64; 3] {
+        let p = self.periapsis * (1. + self.eccentricity);
+        let (s, c) = true_anomaly.sin_cos();
+        let r = p / (1. + self.eccentricity * c);
+        [r * c, r * s, 0.]
+    }
+
+    //mp orbit_velocity_of_true_anomaly
+    /// The velocity in the orbit plane (meters per second) at a true
+    /// anomaly
+    pub fn orbit_velocity_of_true_anomaly(&self, true_anomaly: f64) -> [f64; 3] {
+        let h = (self.gm * self.periapsis * (1. + self.eccentricity)).sqrt();
+        let (s, c) = true_anomaly.sin_cos();
+        let k = self.gm / h;
+        [-k * s, k * (self.eccentricity + c), 0.]
+    }
+
+    //mp state_of_time
+    /// The position (meters) and velocity (meters per second) in the
+    /// parent frame at a Unix time
+    pub fn state_of_time(&self, time: f64) -> ([f64; 3], [f64; 3]) {
+        let nu = self.true_anomaly_of_time(time);
+        let m = self.orbit_to_parent();
+        (
+            rotation::apply(&m, &self.orbit_vec_of_true_anomaly(nu)),
+            rotation::apply(&m, &self.orbit_velocity_of_true_anomaly(nu)),
+        )
+    }
+}
+
+//a Functions
+//fp solve_elliptic
+/// Solve Kepler's equation M = E - e.sin(E) for the eccentric anomaly
+/// of an elliptical orbit (e < 1)
+pub fn solve_elliptic(mean_anomaly: f64, e: f64) -> f64 {
+    // Reduce M to -PI..PI, keeping the whole turns to add back
+    let turns = (mean_anomaly / std::f64::consts::TAU).round();
+    let m = mean_anomaly - turns * std::f64::consts::TAU;
+    // Starting from PI converges for all M and e
+    let mut big_e = if e > 0.8 {
+        std::f64::consts::PI.copysign(m)
+    } else {
+        m
+    };
+    for _ in 0..MAX_ITERATIONS {
+        let f = big_e - e * big_e.sin() - m;
+        let d = f / (1. - e * big_e.cos());
+        big_e -= d;
+        if d.abs() < TOLERANCE {
+            break;
+        }
+    }
+    big_e + turns * std::f64::consts::TAU
+}
+
+//fp solve_hyperbolic
+/// Solve Kepler's equation M = e.sinh(H) - H for the hyperbolic
+/// anomaly of a hyperbolic orbit (e > 1)
+pub fn solve_hyperbolic(mean_anomaly: f64, e: f64) -> f64 {
+    let m = mean_anomaly;
+    let mut h = (2. * m.abs() / e + 1.8).ln().copysign(m);
+    for _ in 0..MAX_ITERATIONS {
+        let f = e * h.sinh() - h - m;
+        let d = f / (e * h.cosh() - 1.);
+        h -= d;
+        if d.abs() < TOLERANCE * (1. + h.abs()) {
+            break;
+        }
+    }
+    h
+}
+
+//fp solve_barker
+/// Solve Barker's equation D + D^3/3 = W for D = tan(true anomaly / 2)
+/// of a parabolic orbit, where W is the mean motion (from
+/// [Kepler::mean_motion]) times the time since periapsis
+pub fn solve_barker(w: f64) -> f64 {
+    // The solution is odd in W; solving for positive W avoids
+    // cancellation
+    let b = 1.5 * w.abs();
+    let y = (b + (b * b + 1.).sqrt()).cbrt();
+    (y - 1. / y).copysign(w)
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gravitational parameter of the Earth in m^3/s^2
+    const GM_EARTH: f64 = 3.986_004_418e14;
+
+    /// Meters per astronomical unit
+    const AU: f64 = 149_597_870_700.0;
+
+    /// Seconds per day
+    const DAY: f64 = 86400.0;
+
+    fn orbit(periapsis: f64, eccentricity: f64, gm: f64) -> Kepler {
+        Kepler::new(periapsis, eccentricity, 0., 0., 0., 0., gm)
+    }
+
+    fn radius(kepler: &Kepler, time: f64) -> f64 {
+        rotation::length(&kepler.state_of_time(time).0)
+    }
+
+    fn speed(kepler: &Kepler, time: f64) -> f64 {
+        rotation::length(&kepler.state_of_time(time).1)
+    }
+
+    #[test]
+    fn kepler_equation() {
+        // Meeus, Astronomical Algorithms, example 30.a
+        let big_e = solve_elliptic(5_f64.to_radians(), 0.1);
+        assert!((big_e.to_degrees() - 5.554589).abs() < 1e-6);
+        for e in [0., 0.5, 0.9, 0.99, 0.999999] {
+            for m in [-7., -3., -1e-6, 0.1, 1., 3.1, 20.] {
+                let big_e = solve_elliptic(m, e);
+                assert!((big_e - e * big_e.sin() - m).abs() < 1e-12, "{e} {m}");
+            }
+        }
+        for e in [1.000001, 1.5, 3., 100.] {
+            for m in [-50., -1., 1e-6, 2., 1000.] {
+                let h = solve_hyperbolic(m, e);
+                let f = e * h.sinh() - h - m;
+                assert!(f.abs() < 1e-10 * (1. + m.abs()), "{e} {m}");
+            }
+        }
+    }
+
+    #[test]
+    fn elliptic() {
+        // Curtis, Orbital Mechanics for Engineering Students, examples
+        // 3.2 and 3.3: perigee 9600 km, apogee 21000 km
+        let kepler = orbit(9600e3, 11400. / 30600., GM_EARTH);
+        assert!((kepler.apoapsis() - 21000e3).abs() < 1e-6);
+        assert!((kepler.period() - 18834.).abs() < 1., "{}", kepler.period());
+        let nu = kepler.true_anomaly_of_time(4077.).to_degrees();
+        assert!((nu - 120.).abs() < 0.02, "{nu}");
+        let nu = kepler.true_anomaly_of_time(3. * 3600.).to_degrees();
+        assert!((nu.rem_euclid(360.) - 193.2).abs() < 0.05, "{nu}");
+        // The orbit closes after a period
+        let (r0, v0) = kepler.state_of_time(1000.);
+        let (r1, v1) = kepler.state_of_time(1000. + kepler.period());
+        assert!(rotation::length(&rotation::sub(&r0, &r1)) < 1e-3);
+        assert!(rotation::length(&rotation::sub(&v0, &v1)) < 1e-6);
+
+        // A circular orbit of 1 AU about the Sun takes a sidereal year
+        // (for a massless body)
+        let kepler = orbit(AU, 0., GM_SUN);
+        assert!((kepler.period() / DAY - 365.2568983).abs() < 1e-6);
+    }
+
+    #[test]
+    fn parabolic() {
+        // A parabolic comet with a perihelion of 0.5 AU spends the
+        // longest possible time within the Earth's orbit, 2/(3.PI) years
+        // or about 77.5 days; it crosses 1 AU at a true anomaly of 90
+        // degrees
+        let kepler = orbit(0.5 * AU, 1., GM_SUN);
+        assert!(kepler.is_parabolic());
+        let half_time = 2. / (3. * std::f64::consts::PI) * 365.2568983 * DAY / 2.;
+        let nu = kepler.true_anomaly_of_time(half_time).to_degrees();
+        assert!((nu - 90.).abs() < 1e-6, "{nu}");
+        assert!((radius(&kepler, half_time) / AU - 1.).abs() < 1e-6);
+        assert!((kepler.true_anomaly_of_time(-half_time).to_degrees() + 90.).abs() < 1e-6);
+        // The speed is always the escape speed
+        for t in [-1e9, 0., half_time, 1e10] {
+            let r = radius(&kepler, t);
+            let escape = (2. * GM_SUN / r).sqrt();
+            assert!((speed(&kepler, t) / escape - 1.).abs() < 1e-12, "{t}");
+        }
+    }
+
+    #[test]
+    fn hyperbolic() {
+        // Curtis, Orbital Mechanics for Engineering Students, example
+        // 3.5: perigee altitude 300 km and perigee speed 15 km/s
+        let periapsis = 6678e3;
+        let h = periapsis * 15e3;
+        let e = h * h / (GM_EARTH * periapsis) - 1.;
+        assert!((e - 2.7696).abs() < 1e-4, "{e}");
+        let kepler = orbit(periapsis, e, GM_EARTH);
+        assert!(!kepler.is_elliptical());
+
+        // A true anomaly of 100 degrees is reached 4141 s after perigee,
+        // at a radius of 48497 km
+        let nu = kepler.true_anomaly_of_time(4141.).to_degrees();
+        assert!((nu - 100.).abs() < 0.01, "{nu}");
+        assert!((radius(&kepler, 4141.) - 48497e3).abs() < 20e3);
+
+        // Three hours later the true anomaly is 107.78 degrees, the
+        // radius 163180 km and the speed 10.51 km/s
+        let t = 4141. + 3. * 3600.;
+        let nu = kepler.true_anomaly_of_time(t).to_degrees();
+        assert!((nu - 107.78).abs() < 0.01, "{nu}");
+        assert!((radius(&kepler, t) - 163180e3).abs() < 50e3);
+        assert!((speed(&kepler, t) - 10.51e3).abs() < 10.);
+    }
+
+    #[test]
+    fn orientation() {
+        // With an inclination of 90 degrees and node and argument of
+        // periapsis of 90 degrees, periapsis is along +Z, the motion
+        // there is along -Y, and the angular momentum is along +X
+        let half_pi = std::f64::consts::FRAC_PI_2;
+        let kepler = Kepler::new(AU, 0.2, half_pi, half_pi, half_pi, 0., GM_SUN);
+        let (r, v) = kepler.state_of_time(0.);
+        assert!((r[2] / AU - 1.).abs() < 1e-12 && r[0].abs() < 1e-3 && r[1].abs() < 1e-3);
+        assert!(v[1] < 0. && v[0].abs() < 1e-9 && v[2].abs() < 1e-9);
+        assert!(rotation::cross(&r, &v)[0] > 0.);
+    }
+}
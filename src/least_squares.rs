@@ -0,0 +1,93 @@
+//a Documentation
+//! Linear least squares through the normal equations
+//!
+//! Rows of a design matrix (with their target values and weights) are
+//! accumulated into A'A and A'b, which are then solved by Gaussian
+//! elimination; an optional damping (as used by Levenberg-Marquardt)
+//! scales up the diagonal of A'A
+
+//a LeastSquares
+//tp LeastSquares
+/// Normal equations for a linear least squares fit of a number of
+/// unknowns
+#[derive(Debug, Clone)]
+pub struct LeastSquares {
+    /// Number of unknowns
+    n: usize,
+    /// A'A, row by row
+    ata: Vec<f64>,
+    /// A'b
+    atb: Vec<f64>,
+}
+
+//ip LeastSquares
+impl LeastSquares {
+    //cp new
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            ata: vec![0.; n * n],
+            atb: vec![0.; n],
+        }
+    }
+
+    //mp add
+    /// Add a row of the design matrix with its target value and weight
+    pub fn add(&mut self, row: &[f64], value: f64, weight: f64) {
+        assert_eq!(row.len(), self.n, "Row must have one entry per unknown");
+        for (i, ri) in row.iter().enumerate() {
+            let wri = weight * ri;
+            self.atb[i] += wri * value;
+            for (a, rj) in self.ata[i * self.n..(i + 1) * self.n].iter_mut().zip(row) {
+                *a += wri * rj;
+            }
+        }
+    }
+
+    //mp solve
+    /// Solve for the unknowns, with the diagonal of A'A scaled by one
+    /// plus a damping factor; None if the equations are singular (or an
+    /// unknown has no effect)
+    pub fn solve(&self, damping: f64) -> Option<Vec<f64>> {
+        let n = self.n;
+        // Scale the unknowns so that A'A has a unit diagonal, as the
+        // unknowns may differ in scale by many orders of magnitude
+        let d: Vec<f64> = (0..n).map(|i| self.ata[i * n + i].sqrt()).collect();
+        if d.iter().any(|d| *d == 0. || !d.is_finite()) {
+            return None;
+        }
+        let mut m: Vec<Vec<f64>> = (0..n)
+            .map(|i| {
+                let mut row: Vec<f64> = (0..n)
+                    .map(|j| self.ata[i * n + j] / (d[i] * d[j]))
+                    .collect();
+                row[i] *= 1. + damping;
+                row.push(self.atb[i] / d[i]);
+                row
+            })
+            .collect();
+        for c in 0..n {
+            let pivot = (c..n).max_by(|a, b| m[*a][c].abs().total_cmp(&m[*b][c].abs()))?;
+            if m[pivot][c].abs() <= 1e-14 {
+                return None;
+            }
+            m.swap(c, pivot);
+            let pivot_row = m[c].clone();
+            for row in m.iter_mut().skip(c + 1) {
+                let f = row[c] / pivot_row[c];
+                for (r, p) in row.iter_mut().zip(&pivot_row).skip(c) {
+                    *r -= f * p;
+                }
+            }
+        }
+        let mut x = vec![0.; n];
+        for c in (0..n).rev() {
+            let s = (c + 1..n).fold(m[c][n], |s, j| s - m[c][j] * x[j]);
+            x[c] = s / m[c][c];
+        }
+        for (x, d) in x.iter_mut().zip(&d) {
+            *x /= d;
+        }
+        x.iter().all(|x| x.is_finite()).then_some(x)
+    }
+}
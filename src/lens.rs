@@ -0,0 +1,308 @@
+//a Documentation
+//! Lens projections between image pixels and directions in the camera
+//! frame
+//!
+//! The camera frame has +X along the optical axis (out of the camera),
+//! +Y to the left of the image and +Z up the image, so that image
+//! coordinates (with the origin at the top left and Y down) map to the
+//! camera frame as the Find tab has always done
+//!
+//! A projection maps the angle of a direction from the optical axis to
+//! a distance from the image center in focal lengths
+//!
+//! The optical axis need not be at the center of the image (the
+//! principal point may be offset), and pixels need not be square: the
+//! aspect ratio is the height of a pixel over its width, and the focal
+//! length is in pixel widths
+//!
+//! Positions in focal lengths from the optical axis given by the
+//! projection are 'ideal'; the distortion of the lens moves them to
+//! where they are imaged
+
+//a Imports
+use crate::distortion::Distortion;
+use crate::rotation;
+
+//a Constants
+/// Width in mm of a full frame (35mm) sensor, for focal lengths given
+/// as 35mm equivalents
+pub const FULL_FRAME_WIDTH: f64 = 36.0;
+
+//a LensProjection
+//tp LensProjection
+/// The projection of a lens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LensProjection {
+    /// r = f tan(a): an ordinary (pinhole) lens
+    #[default]
+    Rectilinear,
+    /// r = 2f tan(a/2)
+    Stereographic,
+    /// r = f a: many fisheye lenses
+    Equidistant,
+    /// r = 2f sin(a/2): equal area fisheye lenses
+    Equisolid,
+    /// r = f sin(a)
+    Orthographic,
+}
+
+//ip LensProjection
+impl LensProjection {
+    //cp of_name
+    /// Get a projection from its name (case insensitive)
+    pub fn of_name(name: &str) -> Option<Self> {
+        use LensProjection::*;
+        match name.to_lowercase().as_str() {
+            "rectilinear" => Some(Rectilinear),
+            "stereographic" => Some(Stereographic),
+            "equidistant" => Some(Equidistant),
+            "equisolid" => Some(Equisolid),
+            "orthographic" => Some(Orthographic),
+            _ => None,
+        }
+    }
+
+    //ap name
+    pub fn name(&self) -> &'static str {
+        use LensProjection::*;
+        match self {
+            Rectilinear => "Rectilinear",
+            Stereographic => "Stereographic",
+            Equidistant => "Equidistant",
+            Equisolid => "Equisolid",
+            Orthographic => "Orthographic",
+        }
+    }
+
+    //ap max_angle
+    /// The largest angle (radians) from the optical axis that the
+    /// projection can image
+    pub fn max_angle(&self) -> f64 {
+        use std::f64::consts::PI;
+        use LensProjection::*;
+        match self {
+            Rectilinear | Orthographic => PI / 2.,
+            Stereographic | Equidistant | Equisolid => PI,
+        }
+    }
+
+    //mp radius_of_angle
+    /// The distance from the image center, in focal lengths, of a
+    /// direction at an angle (radians) from the optical axis
+    pub fn radius_of_angle(&self, angle: f64) -> f64 {
+        use LensProjection::*;
+        match self {
+            Rectilinear => angle.tan(),
+            Stereographic => 2. * (angle / 2.).tan(),
+            Equidistant => angle,
+            Equisolid => 2. * (angle / 2.).sin(),
+            Orthographic => angle.sin(),
+        }
+    }
+
+    //mp angle_of_radius
+    /// The angle (radians) from the optical axis of the direction imaged
+    /// at a distance from the image center in focal lengths
+    pub fn angle_of_radius(&self, radius: f64) -> f64 {
+        use LensProjection::*;
+        match self {
+            Rectilinear => radius.atan(),
+            Stereographic => 2. * (radius / 2.).atan(),
+            Equidistant => radius,
+            Equisolid => 2. * (radius / 2.).clamp(-1., 1.).asin(),
+            Orthographic => radius.clamp(-1., 1.).asin(),
+        }
+    }
+}
+
+//a Lens
+//tp Lens
+/// A lens with a projection, a focal length in pixels and the pixel
+/// position of the optical axis in the image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lens {
+    pub projection: LensProjection,
+    /// Focal length in pixels (pixel widths)
+    pub focal_length: f64,
+    /// Image position of the optical axis in pixels
+    pub center: [f64; 2],
+    /// Height of a pixel over its width
+    pub aspect_ratio: f64,
+    /// Distortion of the lens
+    pub distortion: Distortion,
+}
+
+//ip Lens
+impl Lens {
+    //cp new
+    pub fn new(projection: LensProjection, focal_length: f64, center: [f64; 2]) -> Self {
+        Self {
+            projection,
+            focal_length,
+            center,
+            aspect_ratio: 1.0,
+            distortion: Distortion::default(),
+        }
+    }
+
+    //cp with_focal_length
+    /// The same lens with a different focal length in pixels
+    pub fn with_focal_length(mut self, focal_length: f64) -> Self {
+        self.focal_length = focal_length;
+        self
+    }
+
+    //mp offset_of_xy
+    /// The offset of an image position from the optical axis in pixel
+    /// widths, corrected for the aspect ratio
+    pub fn offset_of_xy(&self, xy: [f64; 2]) -> [f64; 2] {
+        [
+            xy[0] - self.center[0],
+            (xy[1] - self.center[1]) * self.aspect_ratio,
+        ]
+    }
+
+    //mp radius_of_xy
+    /// The distance of an image position from the optical axis in pixel
+    /// widths
+    pub fn radius_of_xy(&self, xy: [f64; 2]) -> f64 {
+        let [dx, dy] = self.offset_of_xy(xy);
+        dx.hypot(dy)
+    }
+
+    //mp ideal_of_xy
+    /// The ideal (undistorted) position in focal lengths from the
+    /// optical axis of an image position in pixels
+    pub fn ideal_of_xy(&self, xy: [f64; 2]) -> [f64; 2] {
+        let [dx, dy] = self.offset_of_xy(xy);
+        self.distortion
+            .undistort([dx / self.focal_length, dy / self.focal_length])
+    }
+
+    //mp xy_of_ideal
+    /// The image position in pixels of an ideal (undistorted) position in
+    /// focal lengths from the optical axis
+    pub fn xy_of_ideal(&self, ideal: [f64; 2]) -> [f64; 2] {
+        let [x, y] = self.distortion.distort(ideal);
+        [
+            self.center[0] + x * self.focal_length,
+            self.center[1] + y * self.focal_length / self.aspect_ratio,
+        ]
+    }
+
+    //mp vector_of_ideal
+    /// The unit vector in the camera frame of the direction at an ideal
+    /// position in focal lengths from the optical axis
+    pub fn vector_of_ideal(&self, ideal: [f64; 2]) -> [f64; 3] {
+        let r = ideal[0].hypot(ideal[1]);
+        if r == 0. {
+            return [1., 0., 0.];
+        }
+        let angle = self.projection.angle_of_radius(r);
+        let (s, c) = angle.sin_cos();
+        [c, -ideal[0] / r * s, -ideal[1] / r * s]
+    }
+
+    //mp ideal_of_vector
+    /// The ideal position in focal lengths from the optical axis of a
+    /// direction in the camera frame, if the projection can image it
+    pub fn ideal_of_vector(&self, v: &[f64; 3]) -> Option<[f64; 2]> {
+        let v = rotation::normalize(v);
+        let across = (v[1] * v[1] + v[2] * v[2]).sqrt();
+        let angle = across.atan2(v[0]);
+        if angle >= self.projection.max_angle() {
+            return None;
+        }
+        if across == 0. {
+            return Some([0., 0.]);
+        }
+        let r = self.projection.radius_of_angle(angle);
+        Some([-v[1] / across * r, -v[2] / across * r])
+    }
+
+    //mp vector_of_xy
+    /// The unit vector in the camera frame of the direction imaged at an
+    /// image position in pixels
+    pub fn vector_of_xy(&self, xy: [f64; 2]) -> [f64; 3] {
+        self.vector_of_ideal(self.ideal_of_xy(xy))
+    }
+
+    //mp xy_of_vector
+    /// The image position in pixels of a direction in the camera frame,
+    /// if the projection can image it
+    pub fn xy_of_vector(&self, v: &[f64; 3]) -> Option<[f64; 2]> {
+        self.ideal_of_vector(v).map(|ideal| self.xy_of_ideal(ideal))
+    }
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECTIONS: [LensProjection; 5] = [
+        LensProjection::Rectilinear,
+        LensProjection::Stereographic,
+        LensProjection::Equidistant,
+        LensProjection::Equisolid,
+        LensProjection::Orthographic,
+    ];
+
+    /// A lens for a 1200 by 800 image with the principal point off
+    /// center and pixels 1.1 times as high as they are wide
+    fn lens(projection: LensProjection) -> Lens {
+        Lens {
+            aspect_ratio: 1.1,
+            ..Lens::new(projection, 900., [610.5, 395.25])
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for projection in PROJECTIONS {
+            let lens = lens(projection);
+            assert_eq!(LensProjection::of_name(projection.name()), Some(projection));
+            for x in [0., 13.7, 300., 610.5, 611., 900., 1199.] {
+                for y in [0., 41.2, 395.25, 396., 600., 799.] {
+                    let v = lens.vector_of_xy([x, y]);
+                    assert!((rotation::length(&v) - 1.).abs() < 1e-12);
+                    let xy = lens.xy_of_vector(&v).unwrap();
+                    assert!(
+                        (xy[0] - x).abs() < 1e-6 && (xy[1] - y).abs() < 1e-6,
+                        "{projection:?} {xy:?} {x} {y}"
+                    );
+
+                    // The angle from the optical axis is that of the
+                    // projection for the radius, allowing for the
+                    // aspect ratio
+                    let r = (x - 610.5).hypot((y - 395.25) * 1.1) / 900.;
+                    let angle = v[1].hypot(v[2]).atan2(v[0]);
+                    assert!((angle - projection.angle_of_radius(r)).abs() < 1e-12);
+                    assert!((projection.radius_of_angle(angle) - r).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn orientation() {
+        // Right of the optical axis is -Y, and up the image is +Z
+        for projection in PROJECTIONS {
+            let lens = lens(projection);
+            assert_eq!(lens.vector_of_xy([610.5, 395.25]), [1., 0., 0.]);
+            let right = lens.vector_of_xy([700., 395.25]);
+            assert!(right[1] < 0. && right[2].abs() < 1e-12);
+            let up = lens.vector_of_xy([610.5, 300.]);
+            assert!(up[2] > 0. && up[1].abs() < 1e-12);
+            // The same angle is 1.1 times as far across as up
+            let [x, y] = lens.xy_of_vector(&[1., -0.1, 0.1]).unwrap();
+            assert!(((x - 610.5) / (395.25 - y) - 1.1).abs() < 1e-9);
+        }
+        // Only the fisheye projections image directions behind the lens
+        let behind = [-0.5, 0.5, 0.];
+        for projection in PROJECTIONS {
+            let fisheye = projection.max_angle() > std::f64::consts::FRAC_PI_2;
+            assert_eq!(lens(projection).xy_of_vector(&behind).is_some(), fisheye);
+        }
+    }
+}
@@ -96,18 +96,122 @@ pub use types::Rrc;
 mod wasm_import;
 pub use wasm_import::log as wasm_log;
 
+mod rotation;
+
+mod time;
+
+mod wasm_time;
+pub use wasm_time::WasmTime;
+
+mod precession;
+
+mod sun;
+
+mod moon;
+
+mod aberration;
+
+mod apparent_place;
+
+mod refraction;
+
+mod wasm_refraction;
+pub use wasm_refraction::WasmRefraction;
+
+mod rise_set;
+
+mod wasm_rise_set;
+pub use wasm_rise_set::WasmRiseSet;
+
+mod observer;
+
+mod wasm_observer;
+pub use wasm_observer::WasmObserver;
+
+mod sun_moon;
+
+mod wasm_sun_moon;
+pub use wasm_sun_moon::WasmSunMoon;
+
+mod twilight;
+
+mod wasm_twilight;
+pub use wasm_twilight::WasmTwilight;
+
+mod catalog_csv;
+
+mod proper_motion;
+
 mod wasm_catalog;
 pub use wasm_catalog::WasmCatalog;
 
+mod star_buffer;
+pub use star_buffer::WasmStarBuffer;
+
+mod star_layout;
+pub use star_layout::WasmStarLayout;
+
 mod wasm_polynomial;
 pub use wasm_polynomial::WasmPolynomial;
 
 mod wasm_star;
 pub use wasm_star::WasmStar;
 
+mod kepler;
+
+mod moons;
+
+mod planet_position;
+
+mod wasm_planet_position;
+pub use wasm_planet_position::WasmPlanetPosition;
+
+mod orbit_bezier;
+
 mod wasm_orbit;
 pub use wasm_orbit::WasmOrbit;
 
+mod wasm_orbit_tree;
+pub use wasm_orbit_tree::WasmOrbitTree;
+
+mod mpc;
+
+mod wasm_mpc;
+pub use wasm_mpc::WasmMpcOrbits;
+
+mod tle;
+
+mod sgp4;
+
+mod satellite;
+
+mod wasm_satellite;
+pub use wasm_satellite::{WasmSatellite, WasmSatellitePass, WasmSatellitePosition, WasmSatellites};
+
+mod image_sources;
+
+mod wasm_image_sources;
+pub use wasm_image_sources::{WasmImageSource, WasmImageSources, WasmSourceExtractor};
+
+mod least_squares;
+
+mod distortion;
+
+mod lens;
+
+mod wasm_lens;
+pub use wasm_lens::WasmLensModel;
+
+mod calibration;
+
+mod wasm_calibration;
+pub use wasm_calibration::WasmLensCalibration;
+
+mod plate_solve;
+
+mod wasm_plate_solver;
+pub use wasm_plate_solver::{WasmPlateSolution, WasmPlateSolver};
+
 mod icosphere;
 pub use icosphere::{Icosphere, Vertex};
 
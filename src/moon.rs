@@ -0,0 +1,191 @@
+//a Documentation
+//! Position of the Moon
+//!
+//! This uses the principal terms of the lunar theory in Meeus,
+//! 'Astronomical Algorithms' chapter 47 (the largest 32 terms in
+//! longitude and distance and 20 in latitude), which gives a position
+//! to about 0.01 degrees and a distance to about 20km
+
+//a Imports
+use crate::precession;
+use crate::rotation;
+
+//a Constants
+/// Terms in longitude (1e-6 degrees) and distance (1e-3 km), as
+/// multipliers of D, M, M' and F
+#[rustfmt::skip]
+const LONGITUDE_DISTANCE_TERMS: [([i8; 4], f64, f64); 32] = [
+    ([0,  0,  1,  0], 6288774.0, -20905355.0),
+    ([2,  0, -1,  0], 1274027.0,  -3699111.0),
+    ([2,  0,  0,  0],  658314.0,  -2955968.0),
+    ([0,  0,  2,  0],  213618.0,   -569925.0),
+    ([0,  1,  0,  0], -185116.0,     48888.0),
+    ([0,  0,  0,  2], -114332.0,     -3149.0),
+    ([2,  0, -2,  0],   58793.0,    246158.0),
+    ([2, -1, -1,  0],   57066.0,   -152138.0),
+    ([2,  0,  1,  0],   53322.0,   -170733.0),
+    ([2, -1,  0,  0],   45758.0,   -204586.0),
+    ([0,  1, -1,  0],  -40923.0,   -129620.0),
+    ([1,  0,  0,  0],  -34720.0,    108743.0),
+    ([0,  1,  1,  0],  -30383.0,    104755.0),
+    ([2,  0,  0, -2],   15327.0,     10321.0),
+    ([0,  0,  1,  2],  -12528.0,         0.0),
+    ([0,  0,  1, -2],   10980.0,     79661.0),
+    ([4,  0, -1,  0],   10675.0,    -34782.0),
+    ([0,  0,  3,  0],   10034.0,    -23210.0),
+    ([4,  0, -2,  0],    8548.0,    -21636.0),
+    ([2,  1, -1,  0],   -7888.0,     24208.0),
+    ([2,  1,  0,  0],   -6766.0,     30824.0),
+    ([1,  0, -1,  0],   -5163.0,     -8379.0),
+    ([1,  1,  0,  0],    4987.0,    -16675.0),
+    ([2, -1,  1,  0],    4036.0,    -12831.0),
+    ([2,  0,  2,  0],    3994.0,    -10445.0),
+    ([4,  0,  0,  0],    3861.0,    -11650.0),
+    ([2,  0, -3,  0],    3665.0,     14403.0),
+    ([0,  1, -2,  0],   -2689.0,     -7003.0),
+    ([2,  0, -1,  2],   -2602.0,         0.0),
+    ([2, -1, -2,  0],    2390.0,     10056.0),
+    ([1,  0,  1,  0],   -2348.0,      6322.0),
+    ([2, -2,  0,  0],    2236.0,     -9884.0),
+];
+
+/// Terms in latitude (1e-6 degrees), as multipliers of D, M, M' and F
+#[rustfmt::skip]
+const LATITUDE_TERMS: [([i8; 4], f64); 20] = [
+    ([0,  0,  0,  1], 5128122.0),
+    ([0,  0,  1,  1],  280602.0),
+    ([0,  0,  1, -1],  277693.0),
+    ([2,  0,  0, -1],  173237.0),
+    ([2,  0, -1,  1],   55413.0),
+    ([2,  0, -1, -1],   46271.0),
+    ([2,  0,  0,  1],   32573.0),
+    ([0,  0,  2,  1],   17198.0),
+    ([2,  0,  1, -1],    9266.0),
+    ([0,  0,  2, -1],    8822.0),
+    ([2, -1,  0, -1],    8216.0),
+    ([2,  0, -2, -1],    4324.0),
+    ([2,  0,  1,  1],    4200.0),
+    ([2,  1,  0, -1],   -3359.0),
+    ([2, -1, -1,  1],    2463.0),
+    ([2, -1,  0,  1],    2211.0),
+    ([2, -1, -1, -1],    2065.0),
+    ([0,  1, -1, -1],   -1870.0),
+    ([4,  0, -1, -1],    1828.0),
+    ([0,  1,  0,  1],   -1794.0),
+];
+
+/// Mean distance of the Moon in km
+const MEAN_DISTANCE_KM: f64 = 385000.56;
+
+//a Functions
+//fi term
+/// The argument of a term, and its eccentricity factor
+fn term(mult: &[i8; 4], args: &[f64; 4], e: f64) -> (f64, f64) {
+    let arg: f64 = mult
+        .iter()
+        .zip(args.iter())
+        .map(|(m, a)| (*m as f64) * a)
+        .sum();
+    let e_factor = match mult[1].abs() {
+        0 => 1.,
+        1 => e,
+        _ => e * e,
+    };
+    (arg, e_factor)
+}
+
+//fp moon_ecliptic
+/// The Moon's geocentric ecliptic longitude and latitude (radians, mean
+/// equinox of date) and distance (km) at a Julian date (TT)
+pub fn moon_ecliptic(julian_date: f64) -> (f64, f64, f64) {
+    let t = precession::centuries_since_j2000(julian_date);
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let t4 = t3 * t;
+    let lp = 218.3164477 + 481267.88123421 * t - 0.0015786 * t2 + t3 / 538841.0 - t4 / 65194000.0;
+    let d = 297.8501921 + 445267.1114034 * t - 0.0018819 * t2 + t3 / 545868.0 - t4 / 113065000.0;
+    let m = 357.5291092 + 35999.0502909 * t - 0.0001536 * t2 + t3 / 24490000.0;
+    let mp = 134.9633964 + 477198.8675055 * t + 0.0087414 * t2 + t3 / 69699.0 - t4 / 14712000.0;
+    let f = 93.2720950 + 483202.0175233 * t - 0.0036539 * t2 - t3 / 3526000.0 + t4 / 863310000.0;
+    let a1 = (119.75 + 131.849 * t).to_radians();
+    let a2 = (53.09 + 479264.290 * t).to_radians();
+    let a3 = (313.45 + 481266.484 * t).to_radians();
+    let e = 1. - 0.002516 * t - 0.0000074 * t2;
+
+    let lp = lp.to_radians();
+    let args = [d, m, mp, f].map(f64::to_radians);
+
+    let mut sl = 0.;
+    let mut sr = 0.;
+    for (mult, l, r) in LONGITUDE_DISTANCE_TERMS.iter() {
+        let (arg, ef) = term(mult, &args, e);
+        sl += l * ef * arg.sin();
+        sr += r * ef * arg.cos();
+    }
+    let mut sb = 0.;
+    for (mult, b) in LATITUDE_TERMS.iter() {
+        let (arg, ef) = term(mult, &args, e);
+        sb += b * ef * arg.sin();
+    }
+    let (f, mp) = (args[3], args[2]);
+    sl += 3958.0 * a1.sin() + 1962.0 * (lp - f).sin() + 318.0 * a2.sin();
+    sb += -2235.0 * lp.sin()
+        + 382.0 * a3.sin()
+        + 175.0 * (a1 - f).sin()
+        + 175.0 * (a1 + f).sin()
+        + 127.0 * (lp - mp).sin()
+        - 115.0 * (lp + mp).sin();
+
+    let longitude = (lp + (sl / 1.0e6).to_radians()).rem_euclid(std::f64::consts::TAU);
+    let latitude = (sb / 1.0e6).to_radians();
+    let distance = MEAN_DISTANCE_KM + sr / 1000.0;
+    (longitude, latitude, distance)
+}
+
+//fp moon_vector_of_date
+/// The geocentric position of the Moon in km relative to the true
+/// equator and equinox of date, including nutation
+pub fn moon_vector_of_date(julian_date: f64) -> [f64; 3] {
+    let (lon, lat, r) = moon_ecliptic(julian_date);
+    let (dpsi, deps) = precession::nutation(julian_date);
+    let eps = precession::mean_obliquity(julian_date) + deps;
+    let ecl = rotation::scale(&rotation::vec_of_ra_de(lon + dpsi, lat), r);
+    rotation::apply(&rotation::rot_x(-eps), &ecl)
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meeus_47a() {
+        // Meeus example 47.a: 1992 April 12 0h TD
+        let (lon, lat, r) = moon_ecliptic(2448724.5);
+        assert!(
+            (lon.to_degrees() - 133.162655).abs() < 0.01,
+            "{}",
+            lon.to_degrees()
+        );
+        assert!(
+            (lat.to_degrees() + 3.229126).abs() < 0.01,
+            "{}",
+            lat.to_degrees()
+        );
+        assert!((r - 368409.7).abs() < 20., "{r}");
+
+        // The apparent right ascension and declination are 134.688470
+        // and 13.768368 degrees
+        let (ra, de) = rotation::ra_de_of_vec(&moon_vector_of_date(2448724.5));
+        assert!(
+            (ra.to_degrees() - 134.688470).abs() < 0.01,
+            "{}",
+            ra.to_degrees()
+        );
+        assert!(
+            (de.to_degrees() - 13.768368).abs() < 0.01,
+            "{}",
+            de.to_degrees()
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//a Documentation
+//! Orbits of the major moons of the planets about their planets
+//!
+//! The Galilean moons of Jupiter and Saturn's Titan use the JPL mean
+//! elements (epoch J2000) referred to each moon's Laplace plane, with
+//! the periapsis advancing and the node regressing at their mean
+//! rates; these ignore the mutual perturbations of the moons, and give
+//! positions to a degree or so along the orbit
+//!
+//! The Earth's Moon has mean elements referred to the ecliptic, which
+//! describe its orbit, but its position uses the lunar theory of the
+//! [crate::moon] module
+//!
+//! Positions are in meters relative to the planet, in the J2000
+//! ecliptic frame used for the planets' heliocentric positions
+
+//a Imports
+use std::f64::consts::TAU;
+
+use crate::kepler::Kepler;
+use crate::moon;
+use crate::precession;
+use crate::rotation::{self, Mat3};
+use crate::time::{self, JD_J2000, SECONDS_PER_DAY};
+
+//a Constants
+/// Seconds in a Julian year, for the precession periods
+const SECONDS_PER_YEAR: f64 = 365.25 * SECONDS_PER_DAY;
+
+/// Interval in seconds either side of a time used to find the velocity
+/// of a moon
+const VELOCITY_INTERVAL: f64 = 60.0;
+
+//a MoonElements
+//tp MoonElements
+/// Mean elements of a moon at J2000, with angles in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoonElements {
+    /// Name of the moon
+    pub name: &'static str,
+    /// Name of the planet it orbits
+    pub parent: &'static str,
+    /// Semi-major axis in km
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub arg_periapsis: f64,
+    pub mean_anomaly: f64,
+    pub inclination: f64,
+    pub ascending_node: f64,
+    /// Sidereal period in days
+    pub period: f64,
+    /// Period of the advance of the argument of periapsis in years
+    pub periapsis_period: f64,
+    /// Period of the regression of the node in years
+    pub node_period: f64,
+    /// J2000 right ascension of the pole of the reference plane
+    pub pole_ra: f64,
+    /// J2000 declination of the pole of the reference plane
+    pub pole_de: f64,
+}
+
+//a Moons
+/// The mean elements of the major moons; the reference plane of the
+/// Moon is the J2000 ecliptic, whose pole is at RA 270 degrees
+pub const MOONS: [MoonElements; 6] = [
+    MoonElements {
+        name: "Moon",
+        parent: "Earth",
+        semi_major_axis: 384400.0,
+        eccentricity: 0.0554,
+        arg_periapsis: 318.15,
+        mean_anomaly: 135.27,
+        inclination: 5.16,
+        ascending_node: 125.08,
+        period: 27.322,
+        periapsis_period: 5.997,
+        node_period: 18.600,
+        pole_ra: 270.0,
+        pole_de: 66.560709,
+    },
+    MoonElements {
+        name: "Io",
+        parent: "Jupiter",
+        semi_major_axis: 421800.0,
+        eccentricity: 0.004,
+        arg_periapsis: 49.1,
+        mean_anomaly: 330.9,
+        inclination: 0.0,
+        ascending_node: 0.0,
+        period: 1.769137786,
+        periapsis_period: 1.625,
+        node_period: 7.420,
+        pole_ra: 268.05,
+        pole_de: 64.50,
+    },
+    MoonElements {
+        name: "Europa",
+        parent: "Jupiter",
+        semi_major_axis: 671100.0,
+        eccentricity: 0.009,
+        arg_periapsis: 45.0,
+        mean_anomaly: 345.4,
+        inclination: 0.5,
+        ascending_node: 184.0,
+        period: 3.551181041,
+        periapsis_period: 1.394,
+        node_period: 30.184,
+        pole_ra: 268.08,
+        pole_de: 64.51,
+    },
+    MoonElements {
+        name: "Ganymede",
+        parent: "Jupiter",
+        semi_major_axis: 1070400.0,
+        eccentricity: 0.001,
+        arg_periapsis: 198.3,
+        mean_anomaly: 324.8,
+        inclination: 0.2,
+        ascending_node: 58.5,
+        period: 7.15455296,
+        periapsis_period: 63.549,
+        node_period: 137.884,
+        pole_ra: 268.20,
+        pole_de: 64.57,
+    },
+    MoonElements {
+        name: "Callisto",
+        parent: "Jupiter",
+        semi_major_axis: 1882700.0,
+        eccentricity: 0.007,
+        arg_periapsis: 43.8,
+        mean_anomaly: 87.4,
+        inclination: 0.3,
+        ascending_node: 309.1,
+        period: 16.6890184,
+        periapsis_period: 205.816,
+        node_period: 577.264,
+        pole_ra: 268.72,
+        pole_de: 64.83,
+    },
+    MoonElements {
+        name: "Titan",
+        parent: "Saturn",
+        semi_major_axis: 1221870.0,
+        eccentricity: 0.0288,
+        arg_periapsis: 180.532,
+        mean_anomaly: 163.310,
+        inclination: 0.306,
+        ascending_node: 28.060,
+        period: 15.945421,
+        periapsis_period: 703.812,
+        node_period: 702.292,
+        pole_ra: 36.41,
+        pole_de: 83.94,
+    },
+];
+
+//fp moon_elements
+/// Find the mean elements of a moon by name
+pub fn moon_elements(name: &str) -> Option<&'static MoonElements> {
+    MOONS.iter().find(|m| m.name == name)
+}
+
+//a MoonOrbit
+//tp MoonOrbit
+/// The precessing orbit of a moon about its planet, with the Keplerian
+/// orbit (in the J2000 ecliptic frame) at an epoch
+#[derive(Debug, Clone)]
+pub struct MoonOrbit {
+    elements: &'static MoonElements,
+    /// Matrix taking vectors in the reference plane frame of the
+    /// elements to the J2000 ecliptic
+    plane_to_ecliptic: Mat3,
+    /// The orbit at the epoch
+    kepler: Kepler,
+}
+
+//ip MoonOrbit
+impl MoonOrbit {
+    //cp of_name
+    /// Create the orbit of a moon with its orbit at an epoch (a Unix
+    /// time), if the moon is known
+    pub fn of_name(name: &str, epoch: f64) -> Option<Self> {
+        let elements = moon_elements(name)?;
+        // The node of the reference plane is on the J2000 equator at a
+        // right ascension 90 degrees beyond that of its pole
+        let pole_ra = elements.pole_ra.to_radians();
+        let pole_de = elements.pole_de.to_radians();
+        let plane_to_equatorial = rotation::mul(
+            &rotation::rot_z(-(pole_ra + TAU / 4.)),
+            &rotation::rot_x(-(TAU / 4. - pole_de)),
+        );
+        let to_ecliptic = rotation::rot_x(precession::mean_obliquity(JD_J2000));
+        let plane_to_ecliptic = rotation::mul(&to_ecliptic, &plane_to_equatorial);
+        let mut s = Self {
+            elements,
+            plane_to_ecliptic,
+            kepler: Kepler::new(1., 0., 0., 0., 0., 0., 1.),
+        };
+        s.kepler = s.kepler_of_time(epoch);
+        Some(s)
+    }
+
+    //ap parent
+    /// The name of the planet that the moon orbits
+    pub fn parent(&self) -> &'static str {
+        self.elements.parent
+    }
+
+    //ap kepler
+    /// The orbit at the epoch
+    pub fn kepler(&self) -> &Kepler {
+        &self.kepler
+    }
+
+    //ap period
+    /// The sidereal period in seconds
+    pub fn period(&self) -> f64 {
+        self.elements.period * SECONDS_PER_DAY
+    }
+
+    //mp kepler_of_time
+    /// The Keplerian orbit in the J2000 ecliptic frame at a Unix time,
+    /// with the node and periapsis precessed to that time
+    pub fn kepler_of_time(&self, time: f64) -> Kepler {
+        let e = self.elements;
+        let dt = time - time::unix_time_of_jd(JD_J2000);
+        let node_rate = -TAU / (e.node_period * SECONDS_PER_YEAR);
+        let periapsis_rate = TAU / (e.periapsis_period * SECONDS_PER_YEAR);
+        // The mean longitude advances at the sidereal rate, so the mean
+        // anomaly advances more slowly as the periapsis and node move
+        let mean_anomaly_rate = TAU / self.period() - node_rate - periapsis_rate;
+        let node = e.ascending_node.to_radians() + node_rate * dt;
+        let arg_periapsis = e.arg_periapsis.to_radians() + periapsis_rate * dt;
+        let mean_anomaly = (e.mean_anomaly.to_radians() + mean_anomaly_rate * dt).rem_euclid(TAU);
+
+        // Find the orientation of the orbit in the ecliptic frame
+        let orbit_to_plane = rotation::mul(
+            &rotation::rot_z(-node),
+            &rotation::mul(
+                &rotation::rot_x(-e.inclination.to_radians()),
+                &rotation::rot_z(-arg_periapsis),
+            ),
+        );
+        let m = rotation::mul(&self.plane_to_ecliptic, &orbit_to_plane);
+        let inclination = m[2][2].clamp(-1., 1.).acos();
+        let ascending_node = m[0][2].atan2(-m[1][2]).rem_euclid(TAU);
+        let arg_periapsis = m[2][0].atan2(m[2][1]).rem_euclid(TAU);
+
+        let a = e.semi_major_axis * 1000.;
+        let gm = mean_anomaly_rate * mean_anomaly_rate * a * a * a;
+        Kepler::of_mean_anomaly(
+            a,
+            e.eccentricity,
+            inclination,
+            ascending_node,
+            arg_periapsis,
+            mean_anomaly,
+            time,
+            gm,
+        )
+    }
+
+    //mp position_of_time
+    /// The position of the moon relative to its planet in meters in the
+    /// J2000 ecliptic at a Unix time
+    pub fn position_of_time(&self, time: f64) -> [f64; 3] {
+        if self.elements.parent == "Earth" {
+            lunar_position_of_time(time)
+        } else {
+            self.kepler_of_time(time).state_of_time(time).0
+        }
+    }
+
+    //mp state_of_time
+    /// The position (meters) and velocity (meters per second) of the
+    /// moon relative to its planet in the J2000 ecliptic at a Unix time
+    ///
+    /// The velocity is found by differencing positions, so that it
+    /// includes the precession of the orbit
+    pub fn state_of_time(&self, time: f64) -> ([f64; 3], [f64; 3]) {
+        let before = self.position_of_time(time - VELOCITY_INTERVAL);
+        let after = self.position_of_time(time + VELOCITY_INTERVAL);
+        let v = rotation::scale(&rotation::sub(&after, &before), 0.5 / VELOCITY_INTERVAL);
+        (self.position_of_time(time), v)
+    }
+}
+
+//a Functions
+//fp lunar_position_of_time
+/// The geocentric position of the Moon in meters in the J2000 ecliptic
+/// at a Unix time, from the lunar theory
+pub fn lunar_position_of_time(time: f64) -> [f64; 3] {
+    let jd_tt = time::jd_tt_of_unix_time(time);
+    let (lon, lat, r) = moon::moon_ecliptic(jd_tt);
+    let ecliptic_of_date = rotation::scale(&rotation::vec_of_ra_de(lon, lat), r * 1000.);
+    let equatorial_of_date = rotation::apply(
+        &rotation::rot_x(-precession::mean_obliquity(jd_tt)),
+        &ecliptic_of_date,
+    );
+    let equatorial = rotation::apply(
+        &rotation::transpose(&precession::precession_matrix(jd_tt)),
+        &equatorial_of_date,
+    );
+    rotation::apply(
+        &rotation::rot_x(precession::mean_obliquity(JD_J2000)),
+        &equatorial,
+    )
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The pole of the orbit of a moon at a time, as J2000 right
+    /// ascension and declination in degrees
+    fn orbit_pole(orbit: &MoonOrbit, time: f64) -> (f64, f64) {
+        let (r, v) = orbit.kepler_of_time(time).state_of_time(time);
+        let to_equatorial = rotation::rot_x(-precession::mean_obliquity(JD_J2000));
+        let pole = rotation::apply(&to_equatorial, &rotation::cross(&r, &v));
+        let (ra, de) = rotation::ra_de_of_vec(&pole);
+        (ra.to_degrees().rem_euclid(360.), de.to_degrees())
+    }
+
+    #[test]
+    fn jupiter_pole() {
+        // Io orbits in Jupiter's equator, and Europa within half a
+        // degree of it, as their nodes regress
+        let j2000 = time::unix_time_of_jd(JD_J2000);
+        let io = MoonOrbit::of_name("Io", j2000).unwrap();
+        let europa = MoonOrbit::of_name("Europa", j2000).unwrap();
+        for years in [0., 1., 3.7, 10., 25.] {
+            let t = j2000 + years * SECONDS_PER_YEAR;
+            let (ra, de) = orbit_pole(&io, t);
+            let cos_de = 64.5_f64.to_radians().cos();
+            assert!(((ra - 268.05) * cos_de).abs() < 0.01, "{ra}");
+            assert!((de - 64.50).abs() < 0.01, "{de}");
+
+            let (ra, de) = orbit_pole(&europa, t);
+            let pole = rotation::vec_of_ra_de(ra.to_radians(), de.to_radians());
+            let jupiter = rotation::vec_of_ra_de(268.05_f64.to_radians(), 64.5_f64.to_radians());
+            let angle = rotation::dot(&pole, &jupiter)
+                .clamp(-1., 1.)
+                .acos()
+                .to_degrees();
+            assert!(angle < 0.6, "{angle}");
+        }
+    }
+
+    #[test]
+    fn io_period() {
+        // Io returns to the same place relative to Jupiter after its
+        // sidereal period, within the small motion of its periapsis
+        let j2000 = time::unix_time_of_jd(JD_J2000);
+        let io = MoonOrbit::of_name("Io", j2000).unwrap();
+        let p0 = io.position_of_time(j2000);
+        let p1 = io.position_of_time(j2000 + io.period());
+        let r = rotation::length(&p0);
+        assert!((r / 421800e3 - 1.).abs() < 0.005, "{r}");
+        assert!(rotation::length(&rotation::sub(&p1, &p0)) < 1e-3 * r);
+    }
+}
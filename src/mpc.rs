@@ -0,0 +1,342 @@
+//a Documentation
+//! Minor Planet Center one-line orbital element formats
+//!
+//! Two fixed-column formats are supported: MPCORB (as in MPCORB.DAT,
+//! for minor planets, with a semi-major axis and the mean anomaly at
+//! an epoch) and CometEls (for comets, with a perihelion distance and
+//! time of perihelion). Elements are relative to the J2000 ecliptic,
+//! and distances are in AU; angles are in degrees
+//!
+//! Blank lines are ignored, as is any MPCORB header up to and
+//! including its line of dashes. Each malformed line gives an error
+//! with its line number, and parsing continues with the next line
+
+//a Imports
+use crate::kepler::{self, Kepler};
+use crate::planet_position::METERS_PER_AU;
+use crate::time::{self, SECONDS_PER_DAY};
+
+//a MpcElements
+//tp MpcElements
+/// The orbit of a minor planet or comet read from an MPC line
+#[derive(Debug, Clone)]
+pub struct MpcElements {
+    /// The designation - the readable designation (with any name) if
+    /// the line has one, else the packed designation
+    pub designation: String,
+    /// The orbit around the Sun, in meters relative to the J2000
+    /// ecliptic
+    pub kepler: Kepler,
+    /// Absolute magnitude (H for minor planets, the total absolute
+    /// magnitude for comets), if given
+    pub absolute_magnitude: Option<f64>,
+    /// Slope parameter (G for minor planets, K for comets), if given
+    pub slope: Option<f64>,
+}
+
+//fi column
+/// Get the trimmed text of 1-based inclusive columns of a line; this
+/// is empty if the line is too short
+fn column(text: &str, first: usize, last: usize) -> &str {
+    let last = last.min(text.len());
+    if first > last {
+        ""
+    } else {
+        text.get(first - 1..last).unwrap_or("").trim()
+    }
+}
+
+//fi parse_column
+/// Parse the value in 1-based inclusive columns of a line
+fn parse_column(
+    line: usize,
+    text: &str,
+    first: usize,
+    last: usize,
+    what: &str,
+) -> Result<f64, String> {
+    let f = column(text, first, last);
+    if f.is_empty() {
+        return Err(format!(
+            "line {line}: missing {what} in columns {first}-{last}"
+        ));
+    }
+    f.parse::<f64>()
+        .map_err(|_| format!("line {line}: could not parse {what} from '{f}'"))
+}
+
+//fi optional_column
+/// Parse the value in 1-based inclusive columns of a line, if present
+fn optional_column(text: &str, first: usize, last: usize) -> Option<f64> {
+    column(text, first, last).parse::<f64>().ok()
+}
+
+//fi unpack_digit
+/// Unpack an MPC packed digit: 0-9, then A-V for 10 to 31
+fn unpack_digit(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => Some(c as u32 - '0' as u32),
+        'A'..='V' => Some(c as u32 - 'A' as u32 + 10),
+        _ => None,
+    }
+}
+
+//fp unix_time_of_date
+/// The Unix time of a calendar date with a fractional day (0h TT is
+/// taken as 0h UTC, which is well within the accuracy of the elements)
+pub fn unix_time_of_date(year: i32, month: u32, day: f64) -> f64 {
+    (time::days_from_civil(year, month, 1) as f64 + day - 1.) * SECONDS_PER_DAY
+}
+
+//fp unpack_epoch
+/// Unpack a five character MPC packed date (such as 'K2426' for
+/// 2024-02-06) to a Unix time
+pub fn unpack_epoch(packed: &str) -> Option<f64> {
+    let c: Vec<char> = packed.chars().collect();
+    if c.len() != 5 {
+        return None;
+    }
+    let century = match c[0] {
+        'I' => 1800,
+        'J' => 1900,
+        'K' => 2000,
+        'L' => 2100,
+        _ => return None,
+    };
+    let year = century + (unpack_digit(c[1])? * 10 + unpack_digit(c[2])?) as i32;
+    let month = unpack_digit(c[3])?;
+    let day = unpack_digit(c[4])?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(unix_time_of_date(year, month, day as f64))
+}
+
+//fp parse_mpcorb_line
+/// Parse a single MPCORB line (numbered from 1 for errors); returns
+/// None for a blank line
+pub fn parse_mpcorb_line(line: usize, text: &str) -> Result<Option<MpcElements>, String> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    if !text.is_ascii() {
+        return Err(format!("line {line}: not an MPCORB line"));
+    }
+    let packed = column(text, 1, 7);
+    if packed.is_empty() {
+        return Err(format!("line {line}: missing designation"));
+    }
+    let epoch = column(text, 21, 25);
+    let Some(epoch) = unpack_epoch(epoch) else {
+        return Err(format!("line {line}: could not unpack epoch '{epoch}'"));
+    };
+    let mean_anomaly = parse_column(line, text, 27, 35, "mean anomaly")?;
+    let arg_periapsis = parse_column(line, text, 38, 46, "argument of perihelion")?;
+    let ascending_node = parse_column(line, text, 49, 57, "ascending node")?;
+    let inclination = parse_column(line, text, 60, 68, "inclination")?;
+    let eccentricity = parse_column(line, text, 71, 79, "eccentricity")?;
+    let semi_major_axis = parse_column(line, text, 93, 103, "semi-major axis")?;
+    if !(0.0..1.0).contains(&eccentricity) || semi_major_axis <= 0. {
+        return Err(format!(
+            "line {line}: eccentricity {eccentricity} and semi-major axis {semi_major_axis} are not an elliptical orbit"
+        ));
+    }
+    let kepler = Kepler::of_mean_anomaly(
+        semi_major_axis * METERS_PER_AU,
+        eccentricity,
+        inclination.to_radians(),
+        ascending_node.to_radians(),
+        arg_periapsis.to_radians(),
+        mean_anomaly.to_radians(),
+        epoch,
+        kepler::GM_SUN,
+    );
+    let readable = column(text, 167, 194);
+    let designation = if readable.is_empty() {
+        packed
+    } else {
+        readable
+    };
+    Ok(Some(MpcElements {
+        designation: designation.into(),
+        kepler,
+        absolute_magnitude: optional_column(text, 9, 13),
+        slope: optional_column(text, 15, 19),
+    }))
+}
+
+//fp parse_comet_els_line
+/// Parse a single CometEls line (numbered from 1 for errors); returns
+/// None for a blank line
+pub fn parse_comet_els_line(line: usize, text: &str) -> Result<Option<MpcElements>, String> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    if !text.is_ascii() {
+        return Err(format!("line {line}: not a CometEls line"));
+    }
+    let year = parse_column(line, text, 15, 18, "perihelion year")?;
+    let month = parse_column(line, text, 20, 21, "perihelion month")?;
+    let day = parse_column(line, text, 23, 29, "perihelion day")?;
+    if year.fract() != 0. || month.fract() != 0. || !(1.0..=12.0).contains(&month) {
+        return Err(format!("line {line}: bad perihelion date {year} {month}"));
+    }
+    let periapsis_time = unix_time_of_date(year as i32, month as u32, day);
+    let periapsis = parse_column(line, text, 31, 39, "perihelion distance")?;
+    let eccentricity = parse_column(line, text, 42, 49, "eccentricity")?;
+    let arg_periapsis = parse_column(line, text, 52, 59, "argument of perihelion")?;
+    let ascending_node = parse_column(line, text, 62, 69, "ascending node")?;
+    let inclination = parse_column(line, text, 72, 79, "inclination")?;
+    let kepler = Kepler::new(
+        periapsis * METERS_PER_AU,
+        eccentricity,
+        inclination.to_radians(),
+        ascending_node.to_radians(),
+        arg_periapsis.to_radians(),
+        periapsis_time,
+        kepler::GM_SUN,
+    );
+    kepler.validate().map_err(|e| format!("line {line}: {e}"))?;
+    let name = column(text, 103, 158);
+    let designation = {
+        if !name.is_empty() {
+            name.to_string()
+        } else {
+            let number = column(text, 1, 4).trim_start_matches('0');
+            let kind = column(text, 5, 5);
+            let provisional = column(text, 6, 12);
+            if number.is_empty() {
+                format!("{kind}/{provisional}")
+            } else {
+                format!("{number}{kind}")
+            }
+        }
+    };
+    Ok(Some(MpcElements {
+        designation,
+        kepler,
+        absolute_magnitude: optional_column(text, 92, 95),
+        slope: optional_column(text, 97, 100),
+    }))
+}
+
+//fp parse_mpcorb
+/// Parse MPCORB text, skipping any header; returns the elements of
+/// the good lines and an error for each malformed line
+pub fn parse_mpcorb(text: &str) -> (Vec<MpcElements>, Vec<String>) {
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines
+        .iter()
+        .position(|l| l.starts_with("-----"))
+        .map(|n| n + 1)
+        .unwrap_or(0);
+    parse_lines(&lines, start, parse_mpcorb_line)
+}
+
+//fp parse_comet_els
+/// Parse CometEls text; returns the elements of the good lines and an
+/// error for each malformed line
+pub fn parse_comet_els(text: &str) -> (Vec<MpcElements>, Vec<String>) {
+    let lines: Vec<&str> = text.lines().collect();
+    parse_lines(&lines, 0, parse_comet_els_line)
+}
+
+//fi parse_lines
+fn parse_lines<F>(lines: &[&str], start: usize, parse_line: F) -> (Vec<MpcElements>, Vec<String>)
+where
+    F: Fn(usize, &str) -> Result<Option<MpcElements>, String>,
+{
+    let mut elements = vec![];
+    let mut errors = vec![];
+    for (n, l) in lines.iter().enumerate().skip(start) {
+        match parse_line(n + 1, l) {
+            Ok(Some(e)) => elements.push(e),
+            Ok(None) => (),
+            Err(e) => errors.push(e),
+        }
+    }
+    (elements, errors)
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ceres from MPCORB.DAT, a copy with a corrupted mean anomaly,
+    /// and 1P/Halley from CometEls.txt
+    const CERES: &str = "00001    3.34  0.15 K2555 188.70269   73.27343   80.25221   10.58780  0.0795753  0.21424651   2.7660512  0 E2025-A12  7334 125 1801-2025 0.65 M-v 30k MPCLINUX   4000      (1) Ceres              20250113";
+    const BAD: &str = "00001    3.34  0.15 K2555 188.7O269   73.27343   80.25221   10.58780  0.0795753  0.21424651   2.7660512  0 E2025-A12  7334 125 1801-2025 0.65 M-v 30k MPCLINUX   4000      (1) Ceres              20250113";
+    const HALLEY: &str = "0001P         1986 02  9.4589  0.587104  0.967277  111.8657   58.8601  162.2422  19860211   5.5  8.0  1P/Halley                                                NK 2175";
+
+    #[test]
+    fn epochs() {
+        let day = |days: f64| days * SECONDS_PER_DAY;
+        assert_eq!(unpack_epoch("K2426"), Some(day(19759.)));
+        assert_eq!(unpack_epoch("J9611"), Some(day(9496.)));
+        assert_eq!(unpack_epoch("K24AV"), Some(day(20027.)));
+        assert_eq!(unpack_epoch("I0011"), Some(day(-62091.)));
+        assert_eq!(unpack_epoch("K2406"), None);
+        assert_eq!(unpack_epoch("K24D1"), None);
+        assert_eq!(unpack_epoch("K24A0"), None);
+        assert_eq!(unpack_epoch("M2426"), None);
+        assert_eq!(unpack_epoch("K242"), None);
+        assert_eq!(unpack_epoch("k2426"), None);
+    }
+
+    #[test]
+    fn mpcorb() {
+        let text = format!("Header\n---------\n{CERES}\n{BAD}\n\n{HALLEY}\n");
+        let (elements, errors) = parse_mpcorb(&text);
+        assert_eq!(elements.len(), 1);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("line 4: could not parse mean anomaly"));
+        assert!(errors[1].starts_with("line 6: could not unpack epoch"));
+
+        let ceres = &elements[0];
+        assert_eq!(ceres.designation, "(1) Ceres");
+        assert_eq!(ceres.absolute_magnitude, Some(3.34));
+        assert_eq!(ceres.slope, Some(0.15));
+        let k = &ceres.kepler;
+        assert!((k.semi_major_axis() / METERS_PER_AU - 2.7660512).abs() < 1e-9);
+        assert!((k.eccentricity - 0.0795753).abs() < 1e-12);
+        assert!((k.inclination.to_degrees() - 10.5878).abs() < 1e-9);
+        assert!((k.ascending_node.to_degrees() - 80.25221).abs() < 1e-9);
+        assert!((k.arg_periapsis.to_degrees() - 73.27343).abs() < 1e-9);
+
+        // The mean anomaly at the epoch (2025-05-05) is as given, and
+        // the mean motion matches the MPC's to its precision
+        let epoch = unix_time_of_date(2025, 5, 5.);
+        let m = (k.mean_motion() * (epoch - k.periapsis_time)).to_degrees();
+        let n = k.mean_motion() * SECONDS_PER_DAY;
+        assert!((m - 188.70269).abs() < 1e-6, "{m}");
+        assert!(
+            (n.to_degrees() - 0.21424651).abs() < 1e-7,
+            "{}",
+            n.to_degrees()
+        );
+    }
+
+    #[test]
+    fn comet_els() {
+        let text = format!("{CERES}\n{BAD}\n{HALLEY}\n");
+        let (elements, errors) = parse_comet_els(&text);
+        assert_eq!(elements.len(), 1);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("line 1: could not parse perihelion month"));
+        assert!(errors[1].starts_with("line 2: could not parse perihelion month"));
+
+        let halley = &elements[0];
+        assert_eq!(halley.designation, "1P/Halley");
+        assert_eq!(halley.absolute_magnitude, Some(5.5));
+        assert_eq!(halley.slope, Some(8.0));
+        let k = &halley.kepler;
+        assert!((k.periapsis / METERS_PER_AU - 0.587104).abs() < 1e-12);
+        assert!((k.eccentricity - 0.967277).abs() < 1e-12);
+        assert!((k.inclination.to_degrees() - 162.2422).abs() < 1e-9);
+        let t = (5875. + 9.4589 - 1.) * SECONDS_PER_DAY;
+        assert!((k.periapsis_time - t).abs() < 1e-3);
+        let a = k.semi_major_axis() / METERS_PER_AU;
+        assert!((a - 0.587104 / (1. - 0.967277)).abs() < 1e-9, "{a}");
+    }
+}
@@ -0,0 +1,356 @@
+//a Documentation
+//! An observer on the Earth at a time
+//!
+//! The observer frame has +X north along the horizon, +Y west along
+//! the horizon and +Z up (the zenith), as used by the sky view; the
+//! 'ECEF' frame in which the stars lie is the J2000 equatorial frame
+//! of the catalog, or the true equator and equinox of date for
+//! apparent places
+//!
+//! Azimuths are measured from north through east
+
+//a Imports
+use crate::precession;
+use crate::refraction::Refraction;
+use crate::rise_set::RiseSet;
+use crate::rotation::{self, Mat3};
+use crate::time::Time;
+
+//a Constants
+/// Equatorial radius of the Earth (WGS84) in meters
+pub const EARTH_EQUATORIAL_RADIUS: f64 = 6378137.0;
+
+/// Flattening of the Earth (WGS84)
+pub const EARTH_FLATTENING: f64 = 1.0 / 298.257223563;
+
+//a Observer
+//tp Observer
+/// An observer at a geodetic latitude, longitude and elevation at a
+/// time
+#[derive(Debug, Clone)]
+pub struct Observer {
+    /// Geodetic latitude in radians
+    latitude: f64,
+    /// East longitude in radians
+    longitude: f64,
+    /// Elevation above the ellipsoid in meters
+    elevation: f64,
+    /// Time of the observation
+    time: Time,
+    /// True if the ECEF frame is the true equator and equinox of date,
+    /// false for the J2000 frame
+    of_date: bool,
+    /// Refraction to apply to altitudes, if any
+    refraction: Option<Refraction>,
+    /// Matrix mapping ECEF vectors to the observer frame
+    ecef_to_observer: Mat3,
+}
+
+//ip Observer
+impl Observer {
+    //cp new
+    /// Create a new observer at a latitude and longitude (radians) and
+    /// elevation (meters) at a time
+    pub fn new(latitude: f64, longitude: f64, elevation: f64, time: Time) -> Self {
+        let mut s = Self {
+            latitude,
+            longitude,
+            elevation,
+            time,
+            of_date: false,
+            refraction: None,
+            ecef_to_observer: rotation::IDENTITY,
+        };
+        s.derive();
+        s
+    }
+
+    //mi derive
+    /// Derive the frame matrix after a change
+    fn derive(&mut self) {
+        let lst = self.time.local_sidereal_time(self.longitude);
+        let (sl, cl) = self.latitude.sin_cos();
+        let (st, ct) = lst.sin_cos();
+        let up = [cl * ct, cl * st, sl];
+        let north = [-sl * ct, -sl * st, cl];
+        let west = rotation::cross(&up, &north);
+        let of_date_to_observer = [north, west, up];
+        self.ecef_to_observer = {
+            if self.of_date {
+                of_date_to_observer
+            } else {
+                let pn = precession::precession_nutation_matrix(self.time.jd_tt());
+                rotation::mul(&of_date_to_observer, &pn)
+            }
+        };
+    }
+
+    //mp set_position
+    /// Set the latitude and longitude (radians) and elevation (meters)
+    pub fn set_position(&mut self, latitude: f64, longitude: f64, elevation: f64) {
+        self.latitude = latitude;
+        self.longitude = longitude;
+        self.elevation = elevation;
+        self.derive();
+    }
+
+    //mp set_time
+    pub fn set_time(&mut self, time: Time) {
+        self.time = time;
+        self.derive();
+    }
+
+    //mp set_of_date
+    /// Select whether the ECEF frame is the true equator and equinox of
+    /// date (for apparent places) or J2000 (for catalog positions)
+    pub fn set_of_date(&mut self, of_date: bool) {
+        self.of_date = of_date;
+        self.derive();
+    }
+
+    //mp set_refraction
+    pub fn set_refraction(&mut self, refraction: Option<Refraction>) {
+        self.refraction = refraction;
+    }
+
+    //ap latitude
+    pub fn latitude(&self) -> f64 {
+        self.latitude
+    }
+
+    //ap longitude
+    pub fn longitude(&self) -> f64 {
+        self.longitude
+    }
+
+    //ap elevation
+    pub fn elevation(&self) -> f64 {
+        self.elevation
+    }
+
+    //ap time
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+
+    //ap of_date
+    pub fn of_date(&self) -> bool {
+        self.of_date
+    }
+
+    //ap refraction
+    pub fn refraction(&self) -> Option<&Refraction> {
+        self.refraction.as_ref()
+    }
+
+    //ap ecef_to_observer
+    /// The matrix mapping ECEF vectors to the observer frame
+    pub fn ecef_to_observer(&self) -> &Mat3 {
+        &self.ecef_to_observer
+    }
+
+    //mp observer_to_ecef
+    /// The matrix mapping observer frame vectors to ECEF
+    pub fn observer_to_ecef(&self) -> Mat3 {
+        rotation::transpose(&self.ecef_to_observer)
+    }
+
+    //mp alt_az_of_vector
+    /// The altitude and azimuth (radians) of an ECEF direction, with
+    /// refraction applied if the observer has it
+    pub fn alt_az_of_vector(&self, v: &[f64; 3]) -> (f64, f64) {
+        let o = rotation::apply(&self.ecef_to_observer, v);
+        let (alt, az) = alt_az_of_observer(&o);
+        match &self.refraction {
+            Some(r) => (r.apparent_altitude(alt), az),
+            None => (alt, az),
+        }
+    }
+
+    //mp vector_of_alt_az
+    /// The ECEF unit vector for an altitude and azimuth (radians),
+    /// removing refraction if the observer has it
+    pub fn vector_of_alt_az(&self, alt: f64, az: f64) -> [f64; 3] {
+        let alt = match &self.refraction {
+            Some(r) => r.true_altitude(alt),
+            None => alt,
+        };
+        let o = observer_of_alt_az(alt, az);
+        rotation::apply(&self.observer_to_ecef(), &o)
+    }
+
+    //mp rise_set_altitude
+    /// The true altitude at which an object appears to be on a horizon
+    /// at the given (apparent) altitude, allowing for refraction if the
+    /// observer has it
+    pub fn rise_set_altitude(&self, horizon_altitude: f64) -> f64 {
+        match &self.refraction {
+            Some(r) => horizon_altitude - r.refraction_of_apparent(horizon_altitude),
+            None => horizon_altitude,
+        }
+    }
+
+    //mp rise_set_of_ra_de
+    /// Find the next rise, transits and set after the observer's time
+    /// of an object at a fixed right ascension and declination (radians,
+    /// true equator and equinox of date), rising and setting at an
+    /// apparent horizon altitude
+    pub fn rise_set_of_ra_de(&self, ra: f64, de: f64, horizon_altitude: f64) -> RiseSet {
+        RiseSet::of_ra_de(
+            ra,
+            de,
+            self.latitude,
+            self.longitude,
+            &self.time,
+            self.rise_set_altitude(horizon_altitude),
+        )
+    }
+
+    //mp geocentric_position
+    /// The position of the observer relative to the center of the Earth
+    /// in meters, in the ECEF frame
+    pub fn geocentric_position(&self) -> [f64; 3] {
+        let (sl, cl) = self.latitude.sin_cos();
+        let e2 = EARTH_FLATTENING * (2. - EARTH_FLATTENING);
+        let n = EARTH_EQUATORIAL_RADIUS / (1. - e2 * sl * sl).sqrt();
+        // Distance from the axis, and height above the equator
+        let rho = (n + self.elevation) * cl;
+        let z = (n * (1. - e2) + self.elevation) * sl;
+        // The observer lies at a right ascension of the local sidereal
+        // time
+        let lst = self.time.local_sidereal_time(self.longitude);
+        let (st, ct) = lst.sin_cos();
+        let of_date = [rho * ct, rho * st, z];
+        if self.of_date {
+            of_date
+        } else {
+            let pn = precession::precession_nutation_matrix(self.time.jd_tt());
+            rotation::apply(&rotation::transpose(&pn), &of_date)
+        }
+    }
+}
+
+//a Functions
+//fp alt_az_of_observer
+/// The altitude and azimuth (radians, azimuth 0 to 2*PI from north
+/// through east) of an observer frame vector
+pub fn alt_az_of_observer(o: &[f64; 3]) -> (f64, f64) {
+    let h = (o[0] * o[0] + o[1] * o[1]).sqrt();
+    let alt = o[2].atan2(h);
+    let az = (-o[1]).atan2(o[0]).rem_euclid(std::f64::consts::TAU);
+    (alt, az)
+}
+
+//fp observer_of_alt_az
+/// The observer frame unit vector of an altitude and azimuth (radians)
+pub fn observer_of_alt_az(alt: f64, az: f64) -> [f64; 3] {
+    let (sa, ca) = alt.sin_cos();
+    let (sz, cz) = az.sin_cos();
+    [ca * cz, -ca * sz, sa]
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{days_from_civil, SECONDS_PER_DAY};
+
+    fn degrees(d: f64, m: f64, s: f64) -> f64 {
+        (d + m / 60. + s / 3600.).to_radians()
+    }
+
+    /// Meeus, Astronomical Algorithms, example 13.b: Venus from the US
+    /// Naval Observatory at 1987 April 10 19h 21m UT
+    fn usno_1987() -> Observer {
+        let unix_time =
+            days_from_civil(1987, 4, 10) as f64 * SECONDS_PER_DAY + (19. * 60. + 21.) * 60.;
+        let mut observer = Observer::new(
+            degrees(38., 55., 17.),
+            -degrees(77., 3., 56.),
+            0.,
+            Time::of_unix_time(unix_time),
+        );
+        observer.set_of_date(true);
+        observer
+    }
+
+    #[test]
+    fn frame() {
+        let observer = usno_1987();
+        let m = observer.ecef_to_observer();
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 1. } else { 0. };
+                assert!((rotation::dot(&m[i], &m[j]) - expected).abs() < 1e-12);
+            }
+        }
+        // North, west, up is right-handed
+        let up = rotation::cross(&m[0], &m[1]);
+        assert!(rotation::dot(&up, &m[2]) > 1. - 1e-12);
+        // The zenith is at the latitude and local sidereal time
+        let (ra, de) = rotation::ra_de_of_vec(&m[2]);
+        let lst = observer.time().local_sidereal_time(observer.longitude());
+        assert!((ra - lst.rem_euclid(std::f64::consts::TAU)).abs() < 1e-12);
+        assert!((de - observer.latitude()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn venus_alt_az() {
+        let observer = usno_1987();
+        let ra = degrees(23., 9., 16.641) * 15.;
+        let de = -degrees(6., 43., 11.61);
+        let (alt, az) = observer.alt_az_of_vector(&rotation::vec_of_ra_de(ra, de));
+        // Meeus gives an altitude of 15.1249 degrees and an azimuth of
+        // 68.0337 degrees westward from south; the difference of UT1
+        // from UTC is ignored here
+        assert!(
+            (alt.to_degrees() - 15.1249).abs() < 0.005,
+            "{}",
+            alt.to_degrees()
+        );
+        assert!(
+            (az.to_degrees() - 248.0337).abs() < 0.005,
+            "{}",
+            az.to_degrees()
+        );
+        let v = observer.vector_of_alt_az(alt, az);
+        let (ra_1, de_1) = rotation::ra_de_of_vec(&v);
+        assert!((ra_1 - ra).abs() < 1e-12 && (de_1 - de).abs() < 1e-12);
+    }
+
+    #[test]
+    fn azimuths() {
+        let o = observer_of_alt_az(0., std::f64::consts::FRAC_PI_2);
+        assert!((o[1] + 1.).abs() < 1e-12, "East is -Y");
+        let (alt, az) = alt_az_of_observer(&[1., -1., 2_f64.sqrt()]);
+        assert!((alt.to_degrees() - 45.).abs() < 1e-12);
+        assert!((az.to_degrees() - 45.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn refracted_round_trip() {
+        let mut observer = usno_1987();
+        observer.set_refraction(Some(Refraction::default()));
+        let v = observer.vector_of_alt_az(0., 1.);
+        let (alt, az) = observer.alt_az_of_vector(&v);
+        // Bennett's and Saemundsson's formulae agree to about 0.1'
+        assert!(alt.to_degrees().abs() * 60. < 0.2 && (az - 1.).abs() < 1e-12);
+        // An object on the apparent horizon is truly about 34' below it
+        let (alt, _) = alt_az_of_observer(&rotation::apply(observer.ecef_to_observer(), &v));
+        assert!((alt.to_degrees() * 60. + 34.5).abs() < 1., "{alt}");
+    }
+
+    #[test]
+    fn geocentric_radius() {
+        let mut observer = usno_1987();
+        observer.set_position(0., 0., 0.);
+        let r = rotation::length(&observer.geocentric_position());
+        assert!((r - EARTH_EQUATORIAL_RADIUS).abs() < 1e-6);
+        observer.set_position(std::f64::consts::FRAC_PI_2, 0., 100.);
+        let r = rotation::length(&observer.geocentric_position());
+        assert!((r - 6356852.314).abs() < 1e-3, "{r}");
+        observer.set_of_date(false);
+        let r = rotation::length(&observer.geocentric_position());
+        assert!((r - 6356852.314).abs() < 1e-3, "{r}");
+    }
+}
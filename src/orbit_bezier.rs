@@ -0,0 +1,163 @@
+//a Documentation
+//! Approximation of a path in time (such as an orbit) by cubic Bezier
+//! segments with a bounded error
+//!
+//! Each segment covers an interval of time, with its parameter 0 to 1
+//! proportional to the time within the interval; the cubic passes
+//! through the positions at the start, one third, two thirds and end of
+//! the interval. The positions at a sixth, a half and five sixths of
+//! the interval are compared with the cubic, and if any is further
+//! than half the permitted error (allowing for the error between those
+//! points) the interval is split in two
+//!
+//! If the error cannot be met within [MAX_SEGMENTS] segments, or by
+//! splitting an interval [MAX_DEPTH] times, no segments are returned
+//! but an error instead
+
+//a Constants
+/// Maximum depth to which an interval is split
+const MAX_DEPTH: usize = 20;
+
+/// Maximum number of segments produced
+const MAX_SEGMENTS: usize = 4096;
+
+/// Fraction of the permitted error allowed at the test points
+const ERROR_MARGIN: f64 = 0.5;
+
+//a Types
+/// The four control points of a cubic Bezier
+pub type Cubic = [[f64; 3]; 4];
+
+//a Functions
+//fp point_of_cubic
+/// The point at a parameter (0 to 1) on a cubic Bezier
+pub fn point_of_cubic(c: &Cubic, u: f64) -> [f64; 3] {
+    let v = 1. - u;
+    let b = [v * v * v, 3. * v * v * u, 3. * v * u * u, u * u * u];
+    let mut p = [0.; 3];
+    for (bi, ci) in b.iter().zip(c.iter()) {
+        for (pj, cj) in p.iter_mut().zip(ci.iter()) {
+            *pj += bi * cj;
+        }
+    }
+    p
+}
+
+//fp cubic_of_points
+/// The cubic Bezier passing through points at parameters 0, 1/3, 2/3
+/// and 1
+pub fn cubic_of_points(p: &[[f64; 3]; 4]) -> Cubic {
+    let mut c1 = [0.; 3];
+    let mut c2 = [0.; 3];
+    for i in 0..3 {
+        let a = 27. * p[1][i] - 8. * p[0][i] - p[3][i];
+        let b = 27. * p[2][i] - p[0][i] - 8. * p[3][i];
+        c1[i] = (2. * a - b) / 18.;
+        c2[i] = (2. * b - a) / 18.;
+    }
+    [p[0], c1, c2, p[3]]
+}
+
+//fp cubics_of_path
+/// Approximate a path over a time interval by cubic Bezier segments,
+/// starting with a number of equal segments and splitting any whose
+/// error is greater than 'max_error'
+///
+/// Returns the time interval and control points of each segment, in
+/// order
+pub fn cubics_of_path<F: Fn(f64) -> [f64; 3]>(
+    path: F,
+    start: f64,
+    end: f64,
+    initial_segments: usize,
+    max_error: f64,
+) -> Result<Vec<(f64, f64, Cubic)>, String> {
+    if max_error.is_nan() || max_error <= 0. {
+        return Err(format!("Maximum error must be positive, not {max_error}"));
+    }
+    let n = initial_segments.max(1);
+    if n > MAX_SEGMENTS {
+        return Err(format!(
+            "{n} segments are needed to start with, more than the maximum of {MAX_SEGMENTS}"
+        ));
+    }
+    let mut result = vec![];
+    for i in 0..n {
+        let t0 = start + (end - start) * (i as f64) / (n as f64);
+        let t1 = start + (end - start) * ((i + 1) as f64) / (n as f64);
+        add_segments(&path, t0, t1, path(t0), path(t1), max_error, 0, &mut result)?;
+    }
+    Ok(result)
+}
+
+//fi add_segments
+/// Add the segments approximating the path between two times, given
+/// the positions there
+#[allow(clippy::too_many_arguments)]
+fn add_segments<F: Fn(f64) -> [f64; 3]>(
+    path: &F,
+    t0: f64,
+    t1: f64,
+    p0: [f64; 3],
+    p1: [f64; 3],
+    max_error: f64,
+    depth: usize,
+    result: &mut Vec<(f64, f64, Cubic)>,
+) -> Result<(), String> {
+    let at = |u: f64| path(t0 + (t1 - t0) * u);
+    let cubic = cubic_of_points(&[p0, at(1. / 3.), at(2. / 3.), p1]);
+    let mid = at(0.5);
+    let error = [(1. / 6., at(1. / 6.)), (0.5, mid), (5. / 6., at(5. / 6.))]
+        .iter()
+        .map(|(u, p)| {
+            let c = point_of_cubic(&cubic, *u);
+            (0..3).map(|i| (c[i] - p[i]).powi(2)).sum::<f64>().sqrt()
+        })
+        .fold(0., f64::max);
+    if error <= max_error * ERROR_MARGIN {
+        result.push((t0, t1, cubic));
+        return Ok(());
+    }
+    if depth >= MAX_DEPTH || result.len() >= MAX_SEGMENTS {
+        return Err(format!(
+            "The path cannot be approximated to within {max_error} with at most {MAX_SEGMENTS} segments"
+        ));
+    }
+    let tm = (t0 + t1) / 2.;
+    add_segments(path, t0, tm, p0, mid, max_error, depth + 1, result)?;
+    add_segments(path, tm, t1, mid, p1, max_error, depth + 1, result)
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(t: f64) -> [f64; 3] {
+        [t.cos(), t.sin(), 0.]
+    }
+
+    #[test]
+    fn bounded_error() {
+        let max_error = 1e-6;
+        let cubics = cubics_of_path(circle, 0., std::f64::consts::TAU, 4, max_error).unwrap();
+        assert!(cubics.len() > 4);
+        for (t0, t1, c) in &cubics {
+            for i in 0..=20 {
+                let u = i as f64 / 20.;
+                let p = point_of_cubic(c, u);
+                let q = circle(t0 + (t1 - t0) * u);
+                let error = (0..3).map(|j| (p[j] - q[j]).powi(2)).sum::<f64>().sqrt();
+                assert!(error < max_error, "{t0} {u} {error}");
+            }
+        }
+    }
+
+    #[test]
+    fn unbounded_error() {
+        assert!(cubics_of_path(circle, 0., 1e6, 4, 1e-6).is_err());
+        assert!(cubics_of_path(circle, 0., 1., MAX_SEGMENTS + 1, 1.).is_err());
+        assert!(cubics_of_path(circle, 0., 1., 1, 0.).is_err());
+        assert!(cubics_of_path(circle, 0., 1., 1, f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//a Documentation
+//! Geocentric positions of planets from heliocentric orbits
+//!
+//! The heliocentric positions of the Earth and of a target come from
+//! their orbits (in meters, relative to the J2000 ecliptic); the
+//! geocentric position of the target is corrected for light time, so
+//! it is where the target was when the light now arriving left it,
+//! and is given in the J2000 equatorial frame of the star catalog
+//!
+//! Apparent magnitudes use the formulae of Meeus, 'Astronomical
+//! Algorithms' chapter 41; Saturn's rings are not included
+
+//a Imports
+use crate::aberration;
+use crate::precession;
+use crate::rotation;
+use crate::time::{JD_J2000, SECONDS_PER_DAY};
+
+//a Constants
+/// Meters per astronomical unit
+pub const METERS_PER_AU: f64 = 149_597_870_700.0;
+
+/// Number of iterations of the light time correction
+const LIGHT_TIME_ITERATIONS: usize = 3;
+
+/// Apparent magnitude of planets at 1AU from the Sun and Earth, with
+/// coefficients of the phase angle (degrees) and its square and cube
+#[rustfmt::skip]
+const MAGNITUDES: [(&str, f64, [f64; 3]); 8] = [
+    ("Mercury", -0.42, [0.0380, -0.000273, 0.000002]),
+    ("Venus",   -4.40, [0.0009,  0.000239, -0.00000065]),
+    ("Mars",    -1.52, [0.016,   0.0,       0.0]),
+    ("Jupiter", -9.40, [0.005,   0.0,       0.0]),
+    ("Saturn",  -8.88, [0.0,     0.0,       0.0]),
+    ("Uranus",  -7.19, [0.0,     0.0,       0.0]),
+    ("Neptune", -6.87, [0.0,     0.0,       0.0]),
+    ("Pluto",   -1.00, [0.0,     0.0,       0.0]),
+];
+
+//a PlanetPosition
+//tp PlanetPosition
+/// The position of a planet as seen from the Earth at a time
+#[derive(Debug, Clone, Copy)]
+pub struct PlanetPosition {
+    /// Geocentric position corrected for light time, in AU in the
+    /// J2000 equatorial frame
+    pub position: [f64; 3],
+    /// Geometric geocentric position at the time (without light time)
+    /// in AU in the J2000 equatorial frame
+    pub geometric: [f64; 3],
+    /// Light time in seconds
+    pub light_time: f64,
+    /// Distance of the planet from the Sun in AU when the light left it
+    pub sun_distance: f64,
+    /// Distance of the Earth from the Sun in AU
+    pub earth_sun_distance: f64,
+    /// Phase angle (Sun - planet - Earth) in radians
+    pub phase_angle: f64,
+    /// Apparent magnitude, if the planet is known
+    pub magnitude: Option<f64>,
+}
+
+//ip PlanetPosition
+impl PlanetPosition {
+    //cp of_heliocentric
+    /// Find the position of a target from the Earth at a Unix time,
+    /// given functions returning their heliocentric positions (meters,
+    /// J2000 ecliptic) at a Unix time
+    ///
+    /// The name is used to find the magnitude of the planet
+    pub fn of_heliocentric<E, T>(earth: E, target: T, unix_time: f64, name: &str) -> Self
+    where
+        E: Fn(f64) -> [f64; 3],
+        T: Fn(f64) -> [f64; 3],
+    {
+        let to_equatorial = rotation::rot_x(-precession::mean_obliquity(JD_J2000));
+        let au = |v: [f64; 3]| rotation::scale(&v, 1. / METERS_PER_AU);
+
+        let earth = au(earth(unix_time));
+        let geometric_helio = au(target(unix_time));
+        let geometric = rotation::sub(&geometric_helio, &earth);
+
+        let mut light_time = 0.;
+        let mut helio = geometric_helio;
+        for _ in 0..LIGHT_TIME_ITERATIONS {
+            let distance = rotation::length(&rotation::sub(&helio, &earth));
+            light_time = aberration::light_time(distance) * SECONDS_PER_DAY;
+            helio = au(target(unix_time - light_time));
+        }
+        let position = rotation::sub(&helio, &earth);
+
+        let r = rotation::length(&helio);
+        let delta = rotation::length(&position);
+        let big_r = rotation::length(&earth);
+        let cos_i = (r * r + delta * delta - big_r * big_r) / (2. * r * delta);
+        let phase_angle = cos_i.clamp(-1., 1.).acos();
+        let magnitude = magnitude(name, r, delta, phase_angle);
+
+        Self {
+            position: rotation::apply(&to_equatorial, &position),
+            geometric: rotation::apply(&to_equatorial, &geometric),
+            light_time,
+            sun_distance: r,
+            earth_sun_distance: big_r,
+            phase_angle,
+            magnitude,
+        }
+    }
+
+    //ap distance
+    /// The distance from the Earth in AU
+    pub fn distance(&self) -> f64 {
+        rotation::length(&self.position)
+    }
+
+    //ap ra_de
+    /// The J2000 right ascension and declination in radians
+    pub fn ra_de(&self) -> (f64, f64) {
+        rotation::ra_de_of_vec(&self.position)
+    }
+
+    //ap illuminated_fraction
+    /// The fraction of the disc that is lit, 0 to 1
+    pub fn illuminated_fraction(&self) -> f64 {
+        (1. + self.phase_angle.cos()) / 2.
+    }
+}
+
+//a Functions
+//fp magnitude
+/// The apparent magnitude of a planet at distances (AU) from the Sun
+/// and the Earth and a phase angle (radians), if it is known
+pub fn magnitude(name: &str, sun_distance: f64, distance: f64, phase_angle: f64) -> Option<f64> {
+    let (_, h, c) = MAGNITUDES.iter().find(|(n, _, _)| *n == name)?;
+    let i = phase_angle.to_degrees();
+    Some(h + 5. * (sun_distance * distance).log10() + i * (c[0] + i * (c[1] + i * c[2])))
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Meters per second at 1 AU per day
+    const AU_PER_DAY: f64 = METERS_PER_AU / SECONDS_PER_DAY;
+
+    #[test]
+    fn venus_1992() {
+        // Meeus example 41.a: Venus on 1992 December 20 at r = 0.724604
+        // AU from the Sun and 0.910947 AU from the Earth, at a phase
+        // angle of 72.96 degrees, so 0.647 of the disc is lit and the
+        // light time is 0.0052612 days
+        let (r, delta, i) = (0.724604, 0.910947, 72.96_f64.to_radians());
+        let venus = [r * METERS_PER_AU, 0., 0.];
+        let earth = [
+            (r - delta * i.cos()) * METERS_PER_AU,
+            delta * i.sin() * METERS_PER_AU,
+            0.,
+        ];
+        let p = PlanetPosition::of_heliocentric(|_| earth, |_| venus, 0., "Venus");
+        assert!((p.distance() - delta).abs() < 1e-9);
+        assert!((p.sun_distance - r).abs() < 1e-9);
+        assert!((p.phase_angle - i).abs() < 1e-9);
+        assert!((p.illuminated_fraction() - 0.647).abs() < 5e-4);
+        assert!((p.light_time / SECONDS_PER_DAY - 0.0052612).abs() < 1e-7);
+        // The Astronomical Almanac formula gives -4.2; Meeus's older
+        // formula gives -3.8
+        assert!((p.magnitude.unwrap() + 4.217).abs() < 1e-3);
+        assert!(
+            PlanetPosition::of_heliocentric(|_| earth, |_| venus, 0., "Vulcan")
+                .magnitude
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn light_time() {
+        // A target 2 AU from a fixed Earth moving at 0.02 AU per day
+        // towards it is seen where it was when the light left it
+        let earth = [METERS_PER_AU, 0., 0.];
+        let velocity = -0.02 * AU_PER_DAY;
+        let target = |t: f64| [3. * METERS_PER_AU + velocity * t, 0., 0.];
+        let p = PlanetPosition::of_heliocentric(|_| earth, target, 0., "");
+        // 2 AU - 0.02 AU/day * tau = c * tau, with c in AU per day
+        let c = 1. / aberration::light_time(1.);
+        let tau = 2. / (c - 0.02);
+        assert!((p.light_time / SECONDS_PER_DAY - tau).abs() < 1e-12);
+        assert!((p.distance() - 2. - 0.02 * tau).abs() < 1e-12);
+        assert!((rotation::length(&p.geometric) - 2.).abs() < 1e-12);
+        assert!(p.magnitude.is_none());
+    }
+
+    #[test]
+    fn equatorial() {
+        // The J2000 ecliptic pole is at RA 18h, and 23 26' 21.406" (the
+        // IAU 2006 obliquity) from the celestial pole
+        let earth = [0.; 3];
+        let north = [0., 0., METERS_PER_AU];
+        let p = PlanetPosition::of_heliocentric(|_| earth, |_| north, 0., "");
+        let (ra, de) = p.ra_de();
+        assert!((ra.to_degrees() - 270.).abs() < 1e-9);
+        assert!((de.to_degrees() - (90. - 84381.406 / 3600.)).abs() < 1e-9);
+    }
+}
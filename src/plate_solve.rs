@@ -0,0 +1,816 @@
+//a Documentation
+//! Blind plate solving: finding the orientation of a camera (and the
+//! focal length of its lens) from the image positions of stars
+//!
+//! Triangles of the brightest sources are converted to directions in
+//! the camera frame for a focal length, and the angles between them
+//! searched for in the catalog. Focal lengths are stepped coarsely
+//! through the permitted range, with the tolerance on the angles
+//! widened to cover the gap between the steps; the catalog triangles
+//! found must then match the shape of the source triangle (its angles
+//! to a common scale) to the tolerance, and the scale corrects the
+//! focal length
+//!
+//! Each candidate triangle of catalog stars gives an orientation, which
+//! must place a catalog star on one of the next brightest sources
+//! (making a quad); this is then verified by projecting the catalog
+//! into the image and matching stars to all of the sources, refining
+//! the orientation and focal length from the matches
+//!
+//! The confidence of a solution is one less the chance that as many of
+//! the sources beyond the triangle would match catalog stars by
+//! accident (given the density of the catalog stars), multiplied by the
+//! number of candidates tried
+
+//a Imports
+use std::collections::HashSet;
+
+use crate::lens::Lens;
+use crate::rotation::{self, Mat3};
+
+//a Constants
+/// Fewest matched stars for a solution; three are the triangle itself
+const MIN_MATCHES: usize = 4;
+
+/// Largest ratio between successive focal length steps
+const MAX_FOCAL_STEP: f64 = 1.2;
+
+/// Most focal length steps for each source triangle; if the tolerance
+/// would need more, the steps are larger and the searches widened to
+/// suit
+const MAX_FOCAL_STEPS: usize = 64;
+
+/// Greatest widening of the tolerance for the triangle searches, as a
+/// multiple of the tolerance, which sets the focal length step
+const SEARCH_WIDENING: f64 = 4.0;
+
+/// Number of the brightest sources outside a triangle checked for a
+/// fourth star
+const QUAD_SOURCES: usize = 4;
+
+/// Number of iterations refining the orientation and focal length from
+/// the matched stars
+const REFINE_ITERATIONS: usize = 3;
+
+/// Smallest side of a source triangle, as a fraction of the image
+/// diagonal; smaller triangles are too sensitive to centroid errors
+const MIN_TRIANGLE_SIDE: f64 = 0.05;
+
+/// Largest side of a source triangle, as a fraction of the image
+/// diagonal; larger triangles match many more catalog triangles by
+/// chance
+const MAX_TRIANGLE_SIDE: f64 = 0.35;
+
+//a StarSearch
+//tt StarSearch
+/// A catalog of stars that can be searched for plate solving; stars are
+/// identified by an index
+pub trait StarSearch {
+    /// Find triangles of stars whose sides subtend three angles (in
+    /// radians) to within a tolerance, up to a maximum number; the
+    /// stars of each triangle may be in any order
+    ///
+    /// This is an error if the search could not be completed
+    fn triangles(
+        &self,
+        angles: [f64; 3],
+        tolerance: f64,
+        max_triangles: usize,
+    ) -> Result<Vec<[usize; 3]>, String>;
+
+    /// Find the stars within an angle (radians) of a unit vector
+    fn stars_around(&self, v: &[f64; 3], angle: f64) -> Vec<usize>;
+
+    /// The unit vector of a star
+    fn vector(&self, star: usize) -> [f64; 3];
+
+    /// The catalog id of a star
+    fn id(&self, star: usize) -> usize;
+
+    /// The number of stars per steradian, for the chance of a match
+    fn density(&self) -> f64;
+}
+
+//a SolveParameters
+//tp SolveParameters
+/// Parameters of a plate solve
+#[derive(Debug, Clone)]
+pub struct SolveParameters {
+    /// The lens (projection, principal point and aspect ratio); its
+    /// focal length is ignored
+    pub lens: Lens,
+    /// Width and height of the image in pixels
+    pub size: [f64; 2],
+    /// Smallest and largest focal lengths in pixels
+    pub focal_length: [f64; 2],
+    /// Tolerance in radians on the angles of a triangle
+    pub tolerance: f64,
+    /// Greatest distance in pixels between a source and its matching
+    /// star
+    pub match_radius: f64,
+    /// Number of the brightest sources from which triangles are formed
+    pub triangle_sources: usize,
+    /// Most source triangles searched for
+    pub max_triangles: usize,
+    /// Most catalog triangles tried for each search
+    pub max_candidates: usize,
+    /// Confidence at which the search stops
+    pub min_confidence: f64,
+}
+
+//ip SolveParameters
+impl SolveParameters {
+    //cp new
+    /// Create parameters for a lens, an image size and focal length
+    /// range (both in pixels), with the default tolerances
+    pub fn new(lens: Lens, size: [f64; 2], focal_length: [f64; 2]) -> Self {
+        Self {
+            lens,
+            size,
+            focal_length,
+            tolerance: 0.2_f64.to_radians(),
+            match_radius: 5.0,
+            triangle_sources: 12,
+            max_triangles: 10,
+            max_candidates: 10000,
+            min_confidence: 0.999,
+        }
+    }
+}
+
+//a StarMatch and PlateSolution
+//tp StarMatch
+/// A source matched to a catalog star
+#[derive(Debug, Clone, Copy)]
+pub struct StarMatch {
+    /// Index of the source
+    pub source: usize,
+    /// Index of the star in the catalog
+    pub star: usize,
+    /// Catalog id of the star
+    pub id: usize,
+    /// Image position of the star less that of the source, in pixels
+    pub residual: [f64; 2],
+}
+
+//tp PlateSolution
+/// The solution of a plate solve
+#[derive(Debug, Clone)]
+pub struct PlateSolution {
+    /// Rotation from the camera frame to the catalog frame
+    pub orientation: Mat3,
+    /// The lens, with its fitted focal length
+    pub lens: Lens,
+    /// The sources matched to stars, in source order
+    pub matches: Vec<StarMatch>,
+    /// Root mean square residual in pixels
+    pub rms: f64,
+    /// Confidence (0 to 1) that the solution is not a chance match
+    pub confidence: f64,
+    /// Number of candidate orientations tried
+    pub candidates: usize,
+}
+
+//ip PlateSolution
+impl PlateSolution {
+    //mi is_better_than
+    /// True if this solution matches more stars than another, or as many
+    /// with a smaller residual
+    fn is_better_than(&self, other: &Self) -> bool {
+        self.matches.len() > other.matches.len()
+            || (self.matches.len() == other.matches.len() && self.rms < other.rms)
+    }
+}
+
+//a Solving
+//fp solve
+/// Solve for the orientation and focal length of a camera given the
+/// image positions of sources (brightest first), returning the best
+/// solution found if any
+///
+/// This is an error if a search for catalog triangles fails
+pub fn solve<S: StarSearch>(
+    search: &S,
+    sources: &[[f64; 2]],
+    parameters: &SolveParameters,
+) -> Result<Option<PlateSolution>, String> {
+    let triangles = source_triangles(sources, parameters);
+    let (f0, f1) = (
+        parameters.focal_length[0].min(parameters.focal_length[1]),
+        parameters.focal_length[0].max(parameters.focal_length[1]),
+    );
+
+    let mut best: Option<PlateSolution> = None;
+    let mut candidates = 0;
+    for triangle in &triangles {
+        // The angles scale (roughly) inversely with the focal length, so
+        // the search tolerance is widened to cover half a step either
+        // way; the step is limited so that the widening is limited
+        let lens = parameters.lens.with_focal_length(f0);
+        let v = triangle.map(|i| lens.vector_of_xy(sources[i]));
+        let largest = angle(&v[0], &v[1])
+            .max(angle(&v[1], &v[2]))
+            .max(angle(&v[2], &v[0]));
+        let step = (1. + SEARCH_WIDENING * parameters.tolerance / largest)
+            .powi(2)
+            .min(MAX_FOCAL_STEP);
+        let steps = ((f1 / f0).ln() / step.ln())
+            .ceil()
+            .clamp(1., MAX_FOCAL_STEPS as f64) as usize;
+        let step_ratio = (f1 / f0).powf(1. / steps as f64);
+        for step in 0..steps {
+            let f = f0 * step_ratio.powf(step as f64 + 0.5);
+            let lens = parameters.lens.with_focal_length(f);
+            let v = triangle.map(|i| lens.vector_of_xy(sources[i]));
+            let angles = [
+                angle(&v[0], &v[1]),
+                angle(&v[1], &v[2]),
+                angle(&v[2], &v[0]),
+            ];
+            let largest = angles.iter().fold(0., |a: f64, b| a.max(*b));
+            let search_tolerance = parameters.tolerance + largest * (step_ratio.sqrt() - 1.);
+            for stars in search.triangles(angles, search_tolerance, parameters.max_candidates)? {
+                let Some((stars, scale)) =
+                    matching_order(search, &stars, &angles, parameters.tolerance)
+                else {
+                    continue;
+                };
+                candidates += 1;
+                let lens = parameters.lens.with_focal_length(f / scale);
+                let pairs: Vec<_> = (0..3)
+                    .map(|i| {
+                        (
+                            lens.vector_of_xy(sources[triangle[i]]),
+                            search.vector(stars[i]),
+                            1.,
+                        )
+                    })
+                    .collect();
+                let orientation = rotation::fit_rotation(&pairs);
+                if !has_quad(search, sources, triangle, &orientation, &lens, parameters) {
+                    continue;
+                }
+                let Some(solution) = refine(search, sources, parameters, orientation, lens) else {
+                    continue;
+                };
+                if best.as_ref().is_none_or(|b| solution.is_better_than(b)) {
+                    best = Some(solution);
+                }
+            }
+            if let Some(b) = &mut best {
+                b.candidates = candidates;
+                b.confidence = confidence(search, sources, parameters, b);
+                if b.confidence >= parameters.min_confidence {
+                    return Ok(best);
+                }
+            }
+        }
+    }
+    Ok(best)
+}
+
+//fp solve_near
+/// Match the sources to catalog stars for an approximate orientation
+/// (such as from a match of a few stars) and focal length in pixels,
+/// refining both, and returning the solution if enough stars match
+pub fn solve_near<S: StarSearch>(
+    search: &S,
+    sources: &[[f64; 2]],
+    parameters: &SolveParameters,
+    orientation: &Mat3,
+    focal_length: f64,
+) -> Option<PlateSolution> {
+    let lens = parameters.lens.with_focal_length(focal_length);
+    let mut solution = refine(search, sources, parameters, *orientation, lens)?;
+    solution.candidates = 1;
+    solution.confidence = confidence(search, sources, parameters, &solution);
+    Some(solution)
+}
+
+//fp match_stars
+/// Match the sources to catalog stars for an orientation and lens,
+/// brightest source first, with each star matched at most once
+pub fn match_stars<S: StarSearch>(
+    search: &S,
+    sources: &[[f64; 2]],
+    orientation: &Mat3,
+    lens: &Lens,
+    match_radius: f64,
+) -> Vec<StarMatch> {
+    let to_camera = rotation::transpose(orientation);
+    // Allow for the scale of a fisheye lens away from its center
+    let radius = 2. * match_radius / lens.focal_length;
+    let mut used = HashSet::new();
+    let mut matches = vec![];
+    for (source, xy) in sources.iter().enumerate() {
+        let v = rotation::apply(orientation, &lens.vector_of_xy(*xy));
+        let mut closest: Option<StarMatch> = None;
+        for star in search.stars_around(&v, radius) {
+            if used.contains(&star) {
+                continue;
+            }
+            let Some(sxy) = lens.xy_of_vector(&rotation::apply(&to_camera, &search.vector(star)))
+            else {
+                continue;
+            };
+            let residual = [sxy[0] - xy[0], sxy[1] - xy[1]];
+            let d = residual[0].hypot(residual[1]);
+            if d <= match_radius && closest.is_none_or(|c| d < c.residual[0].hypot(c.residual[1])) {
+                closest = Some(StarMatch {
+                    source,
+                    star,
+                    id: search.id(star),
+                    residual,
+                });
+            }
+        }
+        if let Some(c) = closest {
+            used.insert(c.star);
+            matches.push(c);
+        }
+    }
+    matches
+}
+
+//fp rms_of_matches
+/// The root mean square residual of some matches
+pub fn rms_of_matches(matches: &[StarMatch]) -> f64 {
+    if matches.is_empty() {
+        return 0.;
+    }
+    let sum: f64 = matches
+        .iter()
+        .map(|m| m.residual[0].powi(2) + m.residual[1].powi(2))
+        .sum();
+    (sum / matches.len() as f64).sqrt()
+}
+
+//fi source_triangles
+/// The triangles of the brightest sources to search for, brightest
+/// first, excluding those with a side too short or too long
+fn source_triangles(sources: &[[f64; 2]], parameters: &SolveParameters) -> Vec<[usize; 3]> {
+    let n = sources.len().min(parameters.triangle_sources);
+    let diagonal = parameters.size[0].hypot(parameters.size[1]);
+    let sides = (MIN_TRIANGLE_SIDE * diagonal)..=(MAX_TRIANGLE_SIDE * diagonal);
+    let side =
+        |a: usize, b: usize| (sources[a][0] - sources[b][0]).hypot(sources[a][1] - sources[b][1]);
+    let mut triangles = vec![];
+    for i in 0..n {
+        for j in (i + 1)..n {
+            for k in (j + 1)..n {
+                if [side(i, j), side(j, k), side(k, i)]
+                    .iter()
+                    .all(|s| sides.contains(s))
+                {
+                    triangles.push([i, j, k]);
+                }
+            }
+        }
+    }
+    triangles.sort_by_key(|t| t[0] + t[1] + t[2]);
+    triangles.truncate(parameters.max_triangles);
+    triangles
+}
+
+//fi matching_order
+/// Reorder the stars of a catalog triangle so that their sides match
+/// the shape of a source triangle, if any order does so within the
+/// tolerance, returning them with the scale of their angles to those of
+/// the source triangle
+fn matching_order<S: StarSearch>(
+    search: &S,
+    stars: &[usize; 3],
+    angles: &[f64; 3],
+    tolerance: f64,
+) -> Option<([usize; 3], f64)> {
+    const ORDERS: [[usize; 3]; 6] = [
+        [0, 1, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [0, 2, 1],
+        [2, 1, 0],
+        [1, 0, 2],
+    ];
+    let v = stars.map(|s| search.vector(s));
+    let total: f64 = angles.iter().sum();
+    ORDERS
+        .iter()
+        .map(|o| {
+            let sides: [f64; 3] = std::array::from_fn(|i| angle(&v[o[i]], &v[o[(i + 1) % 3]]));
+            let scale = sides.iter().sum::<f64>() / total;
+            let error = (0..3)
+                .map(|i| (sides[i] - angles[i] * scale).abs())
+                .fold(0., f64::max);
+            (error, o.map(|i| stars[i]), scale)
+        })
+        .filter(|(error, _, _)| *error <= tolerance)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, s, scale)| (s, scale))
+}
+
+//fi has_quad
+/// True if one of the brightest sources outside a triangle has a
+/// catalog star for an orientation and lens (with twice the usual
+/// match radius, as neither has been refined)
+fn has_quad<S: StarSearch>(
+    search: &S,
+    sources: &[[f64; 2]],
+    triangle: &[usize; 3],
+    orientation: &Mat3,
+    lens: &Lens,
+    parameters: &SolveParameters,
+) -> bool {
+    let to_camera = rotation::transpose(orientation);
+    let radius = 2. * parameters.match_radius;
+    (0..sources.len())
+        .filter(|i| !triangle.contains(i))
+        .take(QUAD_SOURCES)
+        .any(|i| {
+            let xy = sources[i];
+            let v = rotation::apply(orientation, &lens.vector_of_xy(xy));
+            search
+                .stars_around(&v, 2. * radius / lens.focal_length)
+                .into_iter()
+                .filter_map(|star| {
+                    lens.xy_of_vector(&rotation::apply(&to_camera, &search.vector(star)))
+                })
+                .any(|sxy| (sxy[0] - xy[0]).hypot(sxy[1] - xy[1]) <= radius)
+        })
+}
+
+//fi refine
+/// Refine an orientation and lens focal length from the stars matched
+/// to the sources, returning a solution if enough stars match
+fn refine<S: StarSearch>(
+    search: &S,
+    sources: &[[f64; 2]],
+    parameters: &SolveParameters,
+    mut orientation: Mat3,
+    mut lens: Lens,
+) -> Option<PlateSolution> {
+    let mut matches = match_stars(
+        search,
+        sources,
+        &orientation,
+        &lens,
+        parameters.match_radius,
+    );
+    for _ in 0..REFINE_ITERATIONS {
+        if matches.len() < MIN_MATCHES {
+            return None;
+        }
+        lens.focal_length = fit_focal_length(search, sources, &matches, &orientation, &lens);
+        let pairs: Vec<_> = matches
+            .iter()
+            .map(|m| {
+                (
+                    lens.vector_of_xy(sources[m.source]),
+                    search.vector(m.star),
+                    1.,
+                )
+            })
+            .collect();
+        orientation = rotation::fit_rotation(&pairs);
+        matches = match_stars(
+            search,
+            sources,
+            &orientation,
+            &lens,
+            parameters.match_radius,
+        );
+    }
+    if matches.len() < MIN_MATCHES {
+        return None;
+    }
+    Some(PlateSolution {
+        orientation,
+        lens,
+        rms: rms_of_matches(&matches),
+        matches,
+        confidence: 0.,
+        candidates: 0,
+    })
+}
+
+//fi fit_focal_length
+/// The focal length that best fits the distances of the matched sources
+/// from the image center to the angles of their stars from the optical
+/// axis
+fn fit_focal_length<S: StarSearch>(
+    search: &S,
+    sources: &[[f64; 2]],
+    matches: &[StarMatch],
+    orientation: &Mat3,
+    lens: &Lens,
+) -> f64 {
+    let to_camera = rotation::transpose(orientation);
+    let (mut rg, mut gg) = (0., 0.);
+    for m in matches {
+        let c = rotation::apply(&to_camera, &search.vector(m.star));
+        let g = lens
+            .projection
+            .radius_of_angle(c[1].hypot(c[2]).atan2(c[0]));
+        let xy = sources[m.source];
+        rg += lens.radius_of_xy(xy) * g;
+        gg += g * g;
+    }
+    if gg > 0. && rg > 0. {
+        rg / gg
+    } else {
+        lens.focal_length
+    }
+}
+
+//fi confidence
+/// The confidence of a solution: one less the chance of the matches
+/// beyond the triangle being accidental, for all the candidates tried
+fn confidence<S: StarSearch>(
+    search: &S,
+    sources: &[[f64; 2]],
+    parameters: &SolveParameters,
+    solution: &PlateSolution,
+) -> f64 {
+    let n = sources.len().saturating_sub(3);
+    let m = solution.matches.len().saturating_sub(3);
+    if m == 0 {
+        return 0.;
+    }
+    let r = parameters.match_radius / solution.lens.focal_length;
+    let p = (1. - (-search.density() * std::f64::consts::PI * r * r).exp()).clamp(1e-12, 1.);
+    let chance = binomial_tail(n, m, p) * solution.candidates.max(1) as f64;
+    (1. - chance).clamp(0., 1.)
+}
+
+//fi binomial_tail
+/// The probability of at least 'k' successes in 'n' trials each of
+/// probability 'p'
+fn binomial_tail(n: usize, k: usize, p: f64) -> f64 {
+    if k > n {
+        return 0.;
+    }
+    if p >= 1. {
+        return 1.;
+    }
+    let odds = p / (1. - p);
+    let mut term = (1. - p).powi(n as i32);
+    let mut tail = 0.;
+    for j in 0..=n {
+        if j >= k {
+            tail += term;
+        }
+        term *= (n - j) as f64 / (j + 1) as f64 * odds;
+    }
+    tail.min(1.)
+}
+
+//fi angle
+/// The angle in radians between two unit vectors
+fn angle(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    rotation::length(&rotation::cross(a, b)).atan2(rotation::dot(a, b))
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lens::LensProjection;
+
+    /// Width and height of the synthetic image in pixels
+    const SIZE: [f64; 2] = [1200., 800.];
+
+    /// Focal length in pixels of the synthetic image
+    const FOCAL_LENGTH: f64 = 1000.;
+
+    /// A catalog of stars searched by brute force, with triangle
+    /// searches failing if it is not complete
+    struct Stars {
+        vectors: Vec<[f64; 3]>,
+        density: f64,
+        complete: bool,
+    }
+
+    impl StarSearch for Stars {
+        fn triangles(
+            &self,
+            angles: [f64; 3],
+            tolerance: f64,
+            max_triangles: usize,
+        ) -> Result<Vec<[usize; 3]>, String> {
+            if !self.complete {
+                return Err("Search did not finish".into());
+            }
+            let n = self.vectors.len();
+            let mut triangles = vec![];
+            for i in 0..n {
+                for j in (i + 1)..n {
+                    for k in (j + 1)..n {
+                        let v = [i, j, k].map(|s| self.vector(s));
+                        let mut sides = [
+                            angle(&v[0], &v[1]),
+                            angle(&v[1], &v[2]),
+                            angle(&v[2], &v[0]),
+                        ];
+                        let mut angles = angles;
+                        sides.sort_by(f64::total_cmp);
+                        angles.sort_by(f64::total_cmp);
+                        if (0..3).all(|s| (sides[s] - angles[s]).abs() <= tolerance) {
+                            triangles.push([i, j, k]);
+                        }
+                    }
+                }
+            }
+            triangles.truncate(max_triangles);
+            Ok(triangles)
+        }
+
+        fn stars_around(&self, v: &[f64; 3], within: f64) -> Vec<usize> {
+            (0..self.vectors.len())
+                .filter(|s| angle(v, &self.vectors[*s]) <= within)
+                .collect()
+        }
+
+        fn vector(&self, star: usize) -> [f64; 3] {
+            self.vectors[star]
+        }
+
+        fn id(&self, star: usize) -> usize {
+            1000 + star
+        }
+
+        fn density(&self) -> f64 {
+            self.density
+        }
+    }
+
+    /// A linear congruential generator of values from 0 to 1
+    fn uniform(seed: &mut u64) -> f64 {
+        *seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (*seed >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    fn orientation() -> Mat3 {
+        rotation::mul(
+            &rotation::rot_z(1.0),
+            &rotation::mul(&rotation::rot_y(-0.5), &rotation::rot_x(0.2)),
+        )
+    }
+
+    fn lens() -> Lens {
+        Lens::new(
+            LensProjection::Rectilinear,
+            FOCAL_LENGTH,
+            [SIZE[0] / 2., SIZE[1] / 2.],
+        )
+    }
+
+    /// The angle in degrees of the rotation between two orientations
+    fn rotation_error(a: &Mat3, b: &Mat3) -> f64 {
+        let m = rotation::mul(&rotation::transpose(a), b);
+        ((m[0][0] + m[1][1] + m[2][2] - 1.) / 2.)
+            .clamp(-1., 1.)
+            .acos()
+            .to_degrees()
+    }
+
+    /// Thirty stars in the image and thirty around it, with the sources
+    /// of the stars in the image (with up to 0.3 pixels of error) and
+    /// three spurious sources; returns the catalog, the sources, and the
+    /// star of each source
+    fn sky() -> (Stars, Vec<[f64; 2]>, Vec<Option<usize>>) {
+        let mut seed = 2024;
+        let lens = lens();
+        let orientation = orientation();
+        let to_camera = rotation::transpose(&orientation);
+        let in_image =
+            |xy: [f64; 2]| (0.0..SIZE[0]).contains(&xy[0]) && (0.0..SIZE[1]).contains(&xy[1]);
+        let mut vectors = vec![];
+        let mut sources = vec![];
+        let mut stars = vec![];
+        while vectors.len() < 30 {
+            let xy = [
+                20. + uniform(&mut seed) * (SIZE[0] - 40.),
+                20. + uniform(&mut seed) * (SIZE[1] - 40.),
+            ];
+            if [5, 12, 20].contains(&sources.len()) {
+                sources.push(xy);
+                stars.push(None);
+                continue;
+            }
+            stars.push(Some(vectors.len()));
+            vectors.push(rotation::apply(&orientation, &lens.vector_of_xy(xy)));
+            let error = [uniform(&mut seed) - 0.5, uniform(&mut seed) - 0.5];
+            sources.push([xy[0] + 0.6 * error[0], xy[1] + 0.6 * error[1]]);
+        }
+        while vectors.len() < 60 {
+            let xy = [
+                (uniform(&mut seed) * 2. - 0.5) * SIZE[0],
+                (uniform(&mut seed) * 2. - 0.5) * SIZE[1],
+            ];
+            if !in_image(xy) {
+                vectors.push(rotation::apply(&orientation, &lens.vector_of_xy(xy)));
+            }
+        }
+        let density = 30. / (SIZE[0] * SIZE[1] / FOCAL_LENGTH / FOCAL_LENGTH);
+        assert!(vectors[30..].iter().all(|v| lens
+            .xy_of_vector(&rotation::apply(&to_camera, v))
+            .is_none_or(|xy| !in_image(xy))));
+        let search = Stars {
+            vectors,
+            density,
+            complete: true,
+        };
+        (search, sources, stars)
+    }
+
+    /// Check a solution against the sky it was found in
+    fn check_solution(solution: &PlateSolution, stars: &[Option<usize>]) {
+        let error = rotation_error(&solution.orientation, &orientation());
+        assert!(error < 0.01, "{error}");
+        let f = solution.lens.focal_length;
+        assert!((f / FOCAL_LENGTH - 1.).abs() < 1e-3, "{f}");
+        assert_eq!(solution.matches.len(), 30);
+        for m in &solution.matches {
+            assert_eq!(stars[m.source], Some(m.star));
+            assert_eq!(m.id, 1000 + m.star);
+            assert!(m.residual[0].hypot(m.residual[1]) < 1., "{m:?}");
+        }
+        assert!(solution.rms < 0.5, "{}", solution.rms);
+        assert!(solution.confidence > 0.999, "{}", solution.confidence);
+    }
+
+    #[test]
+    fn blind() {
+        let (search, sources, stars) = sky();
+        let parameters = SolveParameters::new(lens(), SIZE, [600., 1500.]);
+        let solution = solve(&search, &sources, &parameters).unwrap().unwrap();
+        check_solution(&solution, &stars);
+        assert!(solution.candidates >= 1);
+
+        // A search that cannot be completed stops the solve
+        let search = Stars {
+            complete: false,
+            ..search
+        };
+        assert!(solve(&search, &sources, &parameters).is_err());
+    }
+
+    #[test]
+    fn near() {
+        let (search, sources, stars) = sky();
+        let parameters = SolveParameters::new(lens(), SIZE, [600., 1500.]);
+        let nearly = rotation::mul(&orientation(), &rotation::rot_y(0.2_f64.to_radians()));
+        let solution = solve_near(
+            &search,
+            &sources,
+            &parameters,
+            &nearly,
+            1.002 * FOCAL_LENGTH,
+        )
+        .unwrap();
+        check_solution(&solution, &stars);
+        assert_eq!(solution.candidates, 1);
+
+        // Too far from the orientation, too few stars match
+        let wrong = rotation::mul(&orientation(), &rotation::rot_y(5_f64.to_radians()));
+        assert!(solve_near(&search, &sources, &parameters, &wrong, FOCAL_LENGTH).is_none());
+    }
+
+    #[test]
+    fn triangle_order() {
+        let v = [[1., 0., 0.], [0., 1., 0.], [0.6, 0.8, 0.]];
+        let search = Stars {
+            vectors: v.to_vec(),
+            density: 1.,
+            complete: true,
+        };
+        // Sides of 90, 36.87 and 53.13 degrees, matched (at 1.1 times
+        // the scale) in another order
+        let [a, b, c] = [90_f64, 36.87, 53.13].map(|d| d.to_radians() / 1.1);
+        let tolerance = 0.01_f64.to_radians();
+        let (stars, scale) = matching_order(&search, &[0, 1, 2], &[b, c, a], tolerance).unwrap();
+        assert!((scale - 1.1).abs() < 1e-4, "{scale}");
+        assert!(
+            (angle(&search.vector(stars[0]), &search.vector(stars[1])) - b * scale).abs()
+                < tolerance
+        );
+        assert!(
+            (angle(&search.vector(stars[2]), &search.vector(stars[0])) - a * scale).abs()
+                < tolerance
+        );
+        // An equilateral triangle matches no order
+        let d = 60_f64.to_radians();
+        assert!(matching_order(&search, &[0, 1, 2], &[d, d, d], 0.01).is_none());
+    }
+
+    #[test]
+    fn binomial() {
+        assert!((binomial_tail(5, 0, 0.3) - 1.).abs() < 1e-12);
+        assert_eq!(binomial_tail(5, 6, 0.3), 0.);
+        assert_eq!(binomial_tail(5, 2, 1.), 1.);
+        assert!((binomial_tail(4, 2, 0.5) - 11. / 16.).abs() < 1e-12);
+        assert!((binomial_tail(10, 10, 0.1) - 1e-10).abs() < 1e-20);
+        assert!((binomial_tail(3, 1, 0.2) - (1. - 0.8_f64.powi(3))).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,161 @@
+//a Documentation
+//! Precession and nutation of the celestial pole
+//!
+//! Precession uses the IAU 2006 (Capitaine et al 2003) equatorial
+//! precession angles; nutation uses the largest 31 terms of the IAU
+//! 1980 series, which is accurate to a few milliarcseconds - far more
+//! than needed to match photographs
+//!
+//! Times are Julian dates in Terrestrial Time; the difference from UTC
+//! (about a minute) is not significant here
+
+//a Imports
+use crate::rotation::{self, Mat3};
+use crate::time::JD_J2000;
+
+//a Constants
+/// Radians per arcsecond
+const RAD_PER_ARCSEC: f64 = std::f64::consts::PI / (180.0 * 3600.0);
+
+/// Days per Julian century
+const DAYS_PER_CENTURY: f64 = 36525.0;
+
+/// Nutation series terms: multipliers of D, M, M', F and Omega, then
+/// the sine coefficient for longitude and its rate, and the cosine
+/// coefficient for obliquity and its rate; units of 0.0001 arcseconds
+/// (per century for the rates)
+#[rustfmt::skip]
+const NUTATION_TERMS: [([i8; 5], f64, f64, f64, f64); 31] = [
+    ([ 0,  0,  0,  0,  1], -171996.0, -174.2, 92025.0,  8.9),
+    ([-2,  0,  0,  2,  2],  -13187.0,   -1.6,  5736.0, -3.1),
+    ([ 0,  0,  0,  2,  2],   -2274.0,   -0.2,   977.0, -0.5),
+    ([ 0,  0,  0,  0,  2],    2062.0,    0.2,  -895.0,  0.5),
+    ([ 0,  1,  0,  0,  0],    1426.0,   -3.4,    54.0, -0.1),
+    ([ 0,  0,  1,  0,  0],     712.0,    0.1,    -7.0,  0.0),
+    ([-2,  1,  0,  2,  2],    -517.0,    1.2,   224.0, -0.6),
+    ([ 0,  0,  0,  2,  1],    -386.0,   -0.4,   200.0,  0.0),
+    ([ 0,  0,  1,  2,  2],    -301.0,    0.0,   129.0, -0.1),
+    ([-2, -1,  0,  2,  2],     217.0,   -0.5,   -95.0,  0.3),
+    ([-2,  0,  1,  0,  0],    -158.0,    0.0,     0.0,  0.0),
+    ([-2,  0,  0,  2,  1],     129.0,    0.1,   -70.0,  0.0),
+    ([ 0,  0, -1,  2,  2],     123.0,    0.0,   -53.0,  0.0),
+    ([ 2,  0,  0,  0,  0],      63.0,    0.0,     0.0,  0.0),
+    ([ 0,  0,  1,  0,  1],      63.0,    0.1,   -33.0,  0.0),
+    ([ 2,  0, -1,  2,  2],     -59.0,    0.0,    26.0,  0.0),
+    ([ 0,  0, -1,  0,  1],     -58.0,   -0.1,    32.0,  0.0),
+    ([ 0,  0,  1,  2,  1],     -51.0,    0.0,    27.0,  0.0),
+    ([-2,  0,  2,  0,  0],      48.0,    0.0,     0.0,  0.0),
+    ([ 0,  0, -2,  2,  1],      46.0,    0.0,   -24.0,  0.0),
+    ([ 2,  0,  0,  2,  2],     -38.0,    0.0,    16.0,  0.0),
+    ([ 0,  0,  2,  2,  2],     -31.0,    0.0,    13.0,  0.0),
+    ([ 0,  0,  2,  0,  0],      29.0,    0.0,     0.0,  0.0),
+    ([-2,  0,  1,  2,  2],      29.0,    0.0,   -12.0,  0.0),
+    ([ 0,  0,  0,  2,  0],      26.0,    0.0,     0.0,  0.0),
+    ([-2,  0,  0,  2,  0],     -22.0,    0.0,     0.0,  0.0),
+    ([ 0,  0, -1,  2,  1],      21.0,    0.0,   -10.0,  0.0),
+    ([ 0,  2,  0,  0,  0],      17.0,   -0.1,     0.0,  0.0),
+    ([ 2,  0, -1,  0,  1],      16.0,    0.0,    -8.0,  0.0),
+    ([-2,  2,  0,  2,  2],     -16.0,    0.1,     7.0,  0.0),
+    ([ 0,  1,  0,  0,  1],     -15.0,    0.0,     9.0,  0.0),
+];
+
+//a Functions
+//fp centuries_since_j2000
+/// Julian centuries since J2000.0 of a Julian date
+pub fn centuries_since_j2000(julian_date: f64) -> f64 {
+    (julian_date - JD_J2000) / DAYS_PER_CENTURY
+}
+
+//fp mean_obliquity
+/// The mean obliquity of the ecliptic (IAU 2006) in radians
+pub fn mean_obliquity(julian_date: f64) -> f64 {
+    let t = centuries_since_j2000(julian_date);
+    let eps =
+        84381.406 + t * (-46.836769 + t * (-0.0001831 + t * (0.00200340 + t * (-0.000000576))));
+    eps * RAD_PER_ARCSEC
+}
+
+//fp nutation
+/// The nutation in longitude and in obliquity, in radians
+pub fn nutation(julian_date: f64) -> (f64, f64) {
+    let t = centuries_since_j2000(julian_date);
+    let t2 = t * t;
+    let t3 = t2 * t;
+    // Delaunay arguments in degrees
+    let d = 297.85036 + 445267.111480 * t - 0.0019142 * t2 + t3 / 189474.0;
+    let m = 357.52772 + 35999.050340 * t - 0.0001603 * t2 - t3 / 300000.0;
+    let mp = 134.96298 + 477198.867398 * t + 0.0086972 * t2 + t3 / 56250.0;
+    let f = 93.27191 + 483202.017538 * t - 0.0036825 * t2 + t3 / 327270.0;
+    let om = 125.04452 - 1934.136261 * t + 0.0020708 * t2 + t3 / 450000.0;
+    let args = [d, m, mp, f, om].map(f64::to_radians);
+
+    let mut dpsi = 0.;
+    let mut deps = 0.;
+    for (mult, s, st, c, ct) in NUTATION_TERMS.iter() {
+        let arg: f64 = mult
+            .iter()
+            .zip(args.iter())
+            .map(|(m, a)| (*m as f64) * a)
+            .sum();
+        dpsi += (s + st * t) * arg.sin();
+        deps += (c + ct * t) * arg.cos();
+    }
+    (
+        dpsi * 0.0001 * RAD_PER_ARCSEC,
+        deps * 0.0001 * RAD_PER_ARCSEC,
+    )
+}
+
+//fp precession_matrix
+/// The matrix taking J2000 mean equatorial vectors to the mean equator
+/// and equinox of the date
+pub fn precession_matrix(julian_date: f64) -> Mat3 {
+    let t = centuries_since_j2000(julian_date);
+    let zeta = 2.650545
+        + t * (2306.083227
+            + t * (0.2988499 + t * (0.01801828 + t * (-0.000005971 + t * (-0.0000003173)))));
+    let z = -2.650545
+        + t * (2306.077181
+            + t * (1.0927348 + t * (0.01826837 + t * (-0.000028596 + t * (-0.0000002904)))));
+    let theta = t
+        * (2004.191903
+            + t * (-0.4294934 + t * (-0.04182264 + t * (-0.000007089 + t * (-0.0000001274)))));
+    let zeta = zeta * RAD_PER_ARCSEC;
+    let z = z * RAD_PER_ARCSEC;
+    let theta = theta * RAD_PER_ARCSEC;
+    rotation::mul(
+        &rotation::rot_z(-z),
+        &rotation::mul(&rotation::rot_y(theta), &rotation::rot_z(-zeta)),
+    )
+}
+
+//fp nutation_matrix
+/// The matrix taking mean equatorial vectors of the date to the true
+/// equator and equinox of the date
+pub fn nutation_matrix(julian_date: f64) -> Mat3 {
+    let eps = mean_obliquity(julian_date);
+    let (dpsi, deps) = nutation(julian_date);
+    rotation::mul(
+        &rotation::rot_x(-(eps + deps)),
+        &rotation::mul(&rotation::rot_z(-dpsi), &rotation::rot_x(eps)),
+    )
+}
+
+//fp precession_nutation_matrix
+/// The matrix taking J2000 mean equatorial vectors to the true equator
+/// and equinox of the date
+pub fn precession_nutation_matrix(julian_date: f64) -> Mat3 {
+    rotation::mul(
+        &nutation_matrix(julian_date),
+        &precession_matrix(julian_date),
+    )
+}
+
+//fp equation_of_the_equinoxes
+/// The equation of the equinoxes (the nutation in right ascension) in
+/// radians, the difference between apparent and mean sidereal time
+pub fn equation_of_the_equinoxes(julian_date: f64) -> f64 {
+    let eps = mean_obliquity(julian_date);
+    let (dpsi, _) = nutation(julian_date);
+    dpsi * eps.cos()
+}
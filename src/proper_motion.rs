@@ -0,0 +1,155 @@
+//a Documentation
+//! Proper motion of stars, and propagation of their positions between
+//! epochs
+//!
+//! The propagation treats the star as moving in a straight line at
+//! constant velocity through space; the tangential velocity comes from
+//! the proper motion, and the radial velocity (if known, with a
+//! parallax) gives the change in distance, which affects the apparent
+//! proper motion over long intervals (perspective acceleration)
+
+//a Imports
+use star_catalog::Star;
+
+pub use crate::time::JD_J2000;
+
+//a Constants
+/// Julian date of the Hipparcos catalog epoch J1991.25
+pub const JD_HIPPARCOS: f64 = 2448349.0625;
+
+/// Days in a Julian year
+pub const DAYS_PER_JULIAN_YEAR: f64 = 365.25;
+
+/// Radians per milliarcsecond
+const RAD_PER_MAS: f64 = std::f64::consts::PI / (180.0 * 3600.0 * 1000.0);
+
+/// Astronomical units per year for one km/s
+const AU_PER_YEAR_PER_KM_S: f64 = 365.25 * 86400.0 / 149_597_870.7;
+
+//a ProperMotion
+//tp ProperMotion
+/// The space motion of a star
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProperMotion {
+    /// Proper motion in right ascension, including the cos(declination)
+    /// factor, in milliarcseconds per year
+    pub pm_ra: f64,
+    /// Proper motion in declination, in milliarcseconds per year
+    pub pm_de: f64,
+    /// Parallax in milliarcseconds; zero if unknown
+    pub parallax: f64,
+    /// Radial velocity in km/s, positive receding; zero if unknown
+    pub rv: f64,
+}
+
+//ip ProperMotion
+impl ProperMotion {
+    //cp new
+    pub fn new(pm_ra: f64, pm_de: f64, parallax: f64, rv: f64) -> Self {
+        Self {
+            pm_ra,
+            pm_de,
+            parallax,
+            rv,
+        }
+    }
+
+    //mp propagate
+    /// Propagate a catalog position (radians) by a number of Julian
+    /// years, returning the new unit vector
+    pub fn propagate(&self, ra: f64, de: f64, years: f64) -> [f64; 3] {
+        let (sr, cr) = ra.sin_cos();
+        let (sd, cd) = de.sin_cos();
+        let p = [cd * cr, cd * sr, sd];
+        let east = [-sr, cr, 0.];
+        let north = [-sd * cr, -sd * sr, cd];
+
+        // Velocity in units of the star's distance per year
+        let mu_a = self.pm_ra * RAD_PER_MAS;
+        let mu_d = self.pm_de * RAD_PER_MAS;
+        let zeta = self.rv * AU_PER_YEAR_PER_KM_S * self.parallax * RAD_PER_MAS;
+
+        let v: [f64; 3] = std::array::from_fn(|i| {
+            p[i] + years * (mu_a * east[i] + mu_d * north[i] + zeta * p[i])
+        });
+        let l = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        [v[0] / l, v[1] / l, v[2] / l]
+    }
+
+    //mp propagate_ra_de
+    /// Propagate a catalog position (radians) by a number of Julian
+    /// years, returning the new right ascension (0 to 2*PI) and
+    /// declination
+    pub fn propagate_ra_de(&self, ra: f64, de: f64, years: f64) -> (f64, f64) {
+        let v = self.propagate(ra, de, years);
+        let ra = v[1].atan2(v[0]).rem_euclid(std::f64::consts::TAU);
+        let de = v[2].clamp(-1., 1.).asin();
+        (ra, de)
+    }
+
+    //mp propagate_star
+    /// Create a copy of a star moved from one epoch to another (Julian
+    /// dates)
+    pub fn propagate_star(&self, star: &Star, from_jd: f64, to_jd: f64) -> Star {
+        let years = (to_jd - from_jd) / DAYS_PER_JULIAN_YEAR;
+        let (ra, de) = self.propagate_ra_de(star.ra(), star.de(), years);
+        Star::new(
+            star.id(),
+            ra,
+            de,
+            star.distance(),
+            star.magnitude(),
+            star.bv(),
+        )
+    }
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rotation;
+
+    /// Barnard's Star (HIP 87937) at the Hipparcos epoch, from the
+    /// Hipparcos catalog
+    fn barnards_star() -> (f64, f64, ProperMotion) {
+        let ra = 269.45402305_f64.to_radians();
+        let de = 4.66828815_f64.to_radians();
+        (ra, de, ProperMotion::new(-797.84, 10326.93, 549.01, -110.6))
+    }
+
+    #[test]
+    fn barnards_star_at_j2000() {
+        let (ra, de, pm) = barnards_star();
+        let years = (JD_J2000 - JD_HIPPARCOS) / DAYS_PER_JULIAN_YEAR;
+        let (ra, de) = pm.propagate_ra_de(ra, de, years);
+        // 17h57m48.498s +04 41' 36.21" at J2000 (SIMBAD)
+        let ra_j2000 = ((17. + 57. / 60. + 48.498 / 3600.) * 15.0_f64).to_radians();
+        let de_j2000 = (4. + 41. / 60. + 36.21 / 3600.0_f64).to_radians();
+        let arcsec = 1.0_f64 / 3600.0_f64.to_degrees();
+        assert!((ra - ra_j2000).abs() * de.cos() < 0.05 * arcsec);
+        assert!((de - de_j2000).abs() < 0.05 * arcsec);
+    }
+
+    #[test]
+    fn barnards_star_acceleration() {
+        // The perspective (secular) acceleration of Barnard's Star is
+        // about 1.3 milliarcseconds per year per year
+        let (ra, de, pm) = barnards_star();
+        let arcsec = 1.0_f64 / 3600.0_f64.to_degrees();
+        let proper_motion = |years: f64| {
+            let v0 = pm.propagate(ra, de, years - 0.5);
+            let v1 = pm.propagate(ra, de, years + 0.5);
+            rotation::length(&rotation::cross(&v0, &v1)) / arcsec * 1000.
+        };
+        let acceleration = (proper_motion(100.) - proper_motion(0.)) / 100.;
+        assert!((1.2..1.4).contains(&acceleration), "{acceleration}");
+    }
+
+    #[test]
+    fn no_motion() {
+        let pm = ProperMotion::default();
+        let (ra, de) = pm.propagate_ra_de(1.0, -0.5, 100.);
+        assert!((ra - 1.0).abs() < 1e-15 && (de + 0.5).abs() < 1e-15);
+    }
+}
@@ -0,0 +1,214 @@
+//a Documentation
+//! Atmospheric refraction
+//!
+//! Refraction raises objects near the horizon by about half a degree.
+//! The apparent altitude of an object is found from its true altitude
+//! with Saemundsson's formula, and the true altitude from the apparent
+//! altitude with Bennett's formula; these are inverses of each other to
+//! within about 0.1 arcminutes, for standard conditions of 1010 mbar
+//! and 10C, scaled for other pressures and temperatures
+//!
+//! Observer frame vectors are (north, west, up), as used for the
+//! observer frame of the sky view
+
+//a Imports
+use crate::rotation;
+
+//a Constants
+/// Standard pressure in millibars for the refraction formulae
+pub const STANDARD_PRESSURE: f64 = 1010.0;
+
+/// Standard temperature in Celsius for the refraction formulae
+pub const STANDARD_TEMPERATURE: f64 = 10.0;
+
+/// Absolute zero in Celsius, as used by the temperature scaling
+const ABSOLUTE_ZERO: f64 = -273.0;
+
+/// True altitude in degrees below which the refraction is held at its
+/// value for that altitude, as the formulae diverge further below the
+/// horizon
+const MIN_ALTITUDE_DEG: f64 = -1.0;
+
+//a Refraction
+//tp Refraction
+/// The atmospheric conditions for refraction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Refraction {
+    /// Pressure in millibars
+    pub pressure: f64,
+    /// Temperature in Celsius
+    pub temperature: f64,
+}
+
+//ip Default for Refraction
+impl std::default::Default for Refraction {
+    fn default() -> Self {
+        Self::new(STANDARD_PRESSURE, STANDARD_TEMPERATURE)
+    }
+}
+
+//ip Refraction
+impl Refraction {
+    //cp new
+    pub fn new(pressure: f64, temperature: f64) -> Self {
+        Self {
+            pressure,
+            temperature,
+        }
+    }
+
+    //cp of_conditions
+    /// Create a [Refraction] for a pressure in millibars and a
+    /// temperature in Celsius, checking that they are physical
+    pub fn of_conditions(pressure: f64, temperature: f64) -> Result<Self, String> {
+        if !pressure.is_finite() || pressure < 0. {
+            return Err(format!("Bad atmospheric pressure {pressure} mbar"));
+        }
+        if !temperature.is_finite() || temperature <= ABSOLUTE_ZERO {
+            return Err(format!("Bad atmospheric temperature {temperature}C"));
+        }
+        Ok(Self::new(pressure, temperature))
+    }
+
+    //mi scale
+    /// The scaling of the standard refraction for the conditions
+    fn scale(&self) -> f64 {
+        (self.pressure / STANDARD_PRESSURE) * (283.0 / (273.0 + self.temperature))
+    }
+
+    //mp refraction_of_true
+    /// The refraction (radians) to add to a true altitude (radians) to
+    /// give the apparent altitude (Saemundsson)
+    pub fn refraction_of_true(&self, altitude: f64) -> f64 {
+        let h = altitude.to_degrees().max(MIN_ALTITUDE_DEG);
+        (saemundsson(h) / 60.0 * self.scale()).max(0.).to_radians()
+    }
+
+    //mp refraction_of_apparent
+    /// The refraction (radians) to subtract from an apparent altitude
+    /// (radians) to give the true altitude (Bennett)
+    pub fn refraction_of_apparent(&self, altitude: f64) -> f64 {
+        // Hold the refraction below the (standard) apparent altitude of
+        // the lowest true altitude, so that this stays close to the
+        // inverse of refraction_of_true
+        let min_apparent = MIN_ALTITUDE_DEG + saemundsson(MIN_ALTITUDE_DEG) / 60.0;
+        let h = altitude.to_degrees().max(min_apparent);
+        (bennett(h) / 60.0 * self.scale()).max(0.).to_radians()
+    }
+
+    //mp apparent_altitude
+    /// The apparent altitude (radians) of an object at a true altitude
+    pub fn apparent_altitude(&self, altitude: f64) -> f64 {
+        altitude + self.refraction_of_true(altitude)
+    }
+
+    //mp true_altitude
+    /// The true altitude (radians) of an object at an apparent altitude
+    pub fn true_altitude(&self, altitude: f64) -> f64 {
+        altitude - self.refraction_of_apparent(altitude)
+    }
+
+    //mp refract
+    /// Map a true observer-frame (north, west, up) direction to the
+    /// apparent direction, preserving the azimuth and length
+    pub fn refract(&self, v: &[f64; 3]) -> [f64; 3] {
+        set_altitude(v, |alt| self.apparent_altitude(alt))
+    }
+
+    //mp unrefract
+    /// Map an apparent observer-frame (north, west, up) direction to the
+    /// true direction, preserving the azimuth and length
+    pub fn unrefract(&self, v: &[f64; 3]) -> [f64; 3] {
+        set_altitude(v, |alt| self.true_altitude(alt))
+    }
+}
+
+//fi saemundsson
+/// Standard refraction in arcminutes at a true altitude in degrees
+fn saemundsson(h: f64) -> f64 {
+    1.02 / (h + 10.3 / (h + 5.11)).to_radians().tan()
+}
+
+//fi bennett
+/// Standard refraction in arcminutes at an apparent altitude in degrees
+fn bennett(h: f64) -> f64 {
+    1.0 / (h + 7.31 / (h + 4.4)).to_radians().tan()
+}
+
+//fi set_altitude
+/// Change the altitude of an observer-frame vector
+fn set_altitude<F: Fn(f64) -> f64>(v: &[f64; 3], f: F) -> [f64; 3] {
+    let l = rotation::length(v);
+    if l == 0. {
+        return *v;
+    }
+    let h = (v[0] * v[0] + v[1] * v[1]).sqrt();
+    let alt = v[2].atan2(h);
+    let az = v[1].atan2(v[0]);
+    let alt = f(alt).clamp(-std::f64::consts::FRAC_PI_2, std::f64::consts::FRAC_PI_2);
+    let (sa, ca) = alt.sin_cos();
+    [l * ca * az.cos(), l * ca * az.sin(), l * sa]
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check the largest error in arcminutes of true altitudes from
+    /// apparent altitudes from true altitudes, above and below the
+    /// horizon
+    fn check_round_trip(refraction: &Refraction, max_above: f64, max_below: f64) {
+        for i in -20..=180 {
+            let altitude = (i as f64 * 0.5).to_radians();
+            let apparent = refraction.apparent_altitude(altitude);
+            assert!(apparent >= altitude);
+            let error = (refraction.true_altitude(apparent) - altitude).to_degrees() * 60.;
+            let max_error = if i >= 0 { max_above } else { max_below };
+            assert!(error.abs() < max_error, "{i} {error}");
+        }
+    }
+
+    #[test]
+    fn conditions() {
+        assert_eq!(
+            Refraction::of_conditions(STANDARD_PRESSURE, STANDARD_TEMPERATURE),
+            Ok(Refraction::default())
+        );
+        assert!(Refraction::of_conditions(0., -40.).is_ok());
+        for (p, t) in [
+            (-1., 10.),
+            (f64::NAN, 10.),
+            (f64::INFINITY, 10.),
+            (1010., -273.),
+            (1010., -300.),
+            (1010., f64::NAN),
+        ] {
+            assert!(Refraction::of_conditions(p, t).is_err(), "{p} {t}");
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        // Bennett's and Saemundsson's formulae agree to about 0.1
+        // arcminutes above the horizon in standard conditions
+        check_round_trip(&Refraction::default(), 0.1, 0.7);
+        check_round_trip(&Refraction::new(800., -20.), 0.5, 0.8);
+        check_round_trip(&Refraction::new(1040., -10.), 0.7, 0.9);
+    }
+
+    #[test]
+    fn standard_refraction() {
+        // About 34 arcminutes at the horizon, and 1 at 45 degrees
+        let refraction = Refraction::default();
+        let r = refraction.refraction_of_apparent(0.).to_degrees() * 60.;
+        assert!((r - 34.5).abs() < 0.5, "{r}");
+        let r = refraction
+            .refraction_of_apparent(45_f64.to_radians())
+            .to_degrees()
+            * 60.;
+        assert!((r - 1.0).abs() < 0.05, "{r}");
+        let r = refraction.refraction_of_true(90_f64.to_radians());
+        assert!(r.abs() < 1e-6, "{r}");
+    }
+}
@@ -0,0 +1,196 @@
+//a Documentation
+//! Rise, transit and set times of objects at fixed right ascension and
+//! declination
+//!
+//! The events are the next ones after a start time, within one
+//! sidereal day; right ascension and declination are relative to the
+//! true equator and equinox of date (as the sidereal time is). Objects
+//! that move (such as the Sun or Moon) can be handled by repeating the
+//! calculation with their position at the time of each event
+
+//a Imports
+use crate::time::Time;
+
+//a Constants
+/// Sidereal rotation rate of the Earth in radians per second of UT1
+pub const SIDEREAL_RATE: f64 = std::f64::consts::TAU * 1.002_737_909_35 / 86400.0;
+
+//a RiseSetKind
+//tp RiseSetKind
+/// Whether an object rises and sets, or is always above or below the
+/// horizon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiseSetKind {
+    /// The object rises and sets
+    RisesAndSets,
+    /// The object is always above the horizon
+    Circumpolar,
+    /// The object is always below the horizon
+    NeverRises,
+}
+
+//ip RiseSetKind
+impl RiseSetKind {
+    //ap as_str
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::RisesAndSets => "rises_and_sets",
+            Self::Circumpolar => "circumpolar",
+            Self::NeverRises => "never_rises",
+        }
+    }
+}
+
+//a RiseSet
+//tp RiseSet
+/// Rise, transit and set times (Unix times in seconds)
+#[derive(Debug, Clone, Copy)]
+pub struct RiseSet {
+    /// Whether the object rises and sets
+    pub kind: RiseSetKind,
+    /// The next rise, if the object rises and sets
+    pub rise: Option<f64>,
+    /// The next upper transit (culmination)
+    pub upper_transit: f64,
+    /// The next lower transit
+    pub lower_transit: f64,
+    /// The next set, if the object rises and sets
+    pub set: Option<f64>,
+    /// The altitude of the object at upper transit in radians
+    pub transit_altitude: f64,
+}
+
+//ip RiseSet
+impl RiseSet {
+    //cp of_ra_de
+    /// Find the next rise, transits and set after a time for an object
+    /// at a right ascension and declination (radians, of date), for a
+    /// latitude and east longitude (radians)
+    ///
+    /// The object rises and sets when its true altitude is 'h0'
+    /// radians; this should include any refraction and the semidiameter
+    /// of the object
+    pub fn of_ra_de(ra: f64, de: f64, latitude: f64, longitude: f64, time: &Time, h0: f64) -> Self {
+        let lst = time.local_sidereal_time(longitude);
+        let start = time.unix_time();
+        let day = std::f64::consts::TAU / SIDEREAL_RATE;
+
+        let hour_angle = (lst - ra).rem_euclid(std::f64::consts::TAU);
+        let to_upper = (std::f64::consts::TAU - hour_angle).rem_euclid(std::f64::consts::TAU);
+        let upper_transit = start + to_upper / SIDEREAL_RATE;
+        let lower_transit = next_after(upper_transit - day / 2., start, day);
+        let transit_altitude = std::f64::consts::FRAC_PI_2 - (latitude - de).abs();
+
+        let cos_h0 = (h0.sin() - latitude.sin() * de.sin()) / (latitude.cos() * de.cos());
+        let (kind, rise, set) = {
+            if cos_h0.is_nan() || cos_h0 < -1. {
+                (RiseSetKind::Circumpolar, None, None)
+            } else if cos_h0 > 1. {
+                (RiseSetKind::NeverRises, None, None)
+            } else {
+                let dt = cos_h0.acos() / SIDEREAL_RATE;
+                let rise = next_after(upper_transit - dt, start, day);
+                let set = next_after(upper_transit + dt, start, day);
+                (RiseSetKind::RisesAndSets, Some(rise), Some(set))
+            }
+        };
+        Self {
+            kind,
+            rise,
+            upper_transit,
+            lower_transit,
+            set,
+            transit_altitude,
+        }
+    }
+}
+
+//fi next_after
+/// Move a time by whole periods to be the first at or after a start
+fn next_after(t: f64, start: f64, period: f64) -> f64 {
+    t + ((start - t) / period).ceil() * period
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::days_from_civil;
+
+    const SECONDS_PER_DAY: f64 = 86400.0;
+
+    fn unix_time(year: i32, month: u32, day: u32) -> f64 {
+        days_from_civil(year, month, day) as f64 * SECONDS_PER_DAY
+    }
+
+    #[test]
+    fn next_after_start() {
+        assert_eq!(next_after(5., 10., 3.), 11.);
+        assert_eq!(next_after(10., 10., 3.), 10.);
+        assert_eq!(next_after(20., 10., 3.), 11.);
+        assert_eq!(next_after(-100., 10., 3.), 11.);
+    }
+
+    #[test]
+    fn circumpolar() {
+        // Vega from 60N is at least 8.8 degrees up
+        let time = Time::of_unix_time(unix_time(2024, 6, 1));
+        let (ra, de) = (279.2347_f64.to_radians(), 38.7837_f64.to_radians());
+        let rs = RiseSet::of_ra_de(ra, de, 60_f64.to_radians(), 0., &time, 0.);
+        assert_eq!(rs.kind, RiseSetKind::Circumpolar);
+        assert!(rs.rise.is_none() && rs.set.is_none());
+        assert!((rs.transit_altitude.to_degrees() - 68.7837).abs() < 1e-9);
+        let start = time.unix_time();
+        let day = std::f64::consts::TAU / SIDEREAL_RATE;
+        assert!(rs.upper_transit >= start && rs.upper_transit < start + day);
+        assert!(rs.lower_transit >= start && rs.lower_transit < start + day);
+        let apart = (rs.upper_transit - rs.lower_transit).abs();
+        assert!((apart - day / 2.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn never_rises() {
+        // Canopus from 51.5N culminates 14.2 degrees below the horizon
+        let time = Time::of_unix_time(unix_time(2024, 1, 1));
+        let (ra, de) = (95.9880_f64.to_radians(), (-52.6957_f64).to_radians());
+        let rs = RiseSet::of_ra_de(ra, de, 51.5_f64.to_radians(), 0., &time, 0.);
+        assert_eq!(rs.kind, RiseSetKind::NeverRises);
+        assert!(rs.rise.is_none() && rs.set.is_none());
+        assert!((rs.transit_altitude.to_degrees() + 14.1957).abs() < 1e-9);
+    }
+
+    #[test]
+    fn venus_at_boston() {
+        // Meeus example 15.a: Venus from Boston on 1988 March 20, with
+        // its apparent places at 0h TD on March 19, 20 and 21
+        let ras: [f64; 3] = [40.68021, 41.73129, 42.78204];
+        let des: [f64; 3] = [18.04761, 18.44092, 18.82742];
+        let interpolate = |y: &[f64; 3], n: f64| {
+            let (a, b) = (y[1] - y[0], y[2] - y[1]);
+            (y[1] + n / 2. * (a + b + n * (b - a))).to_radians()
+        };
+        let (latitude, longitude) = (42.3333_f64.to_radians(), (-71.0833_f64).to_radians());
+        let h0 = (-0.5667_f64).to_radians();
+        let start = unix_time(1988, 3, 20);
+        let time = Time::of_unix_time(start);
+        let delta_t = (time.jd_tt() - time.jd_ut1()) * SECONDS_PER_DAY;
+
+        // Repeat each event with the position of Venus at its time
+        let event = |f: &dyn Fn(&RiseSet) -> f64| {
+            let mut t = start;
+            for _ in 0..4 {
+                let n = (t - start + delta_t) / SECONDS_PER_DAY;
+                let (ra, de) = (interpolate(&ras, n), interpolate(&des, n));
+                t = f(&RiseSet::of_ra_de(ra, de, latitude, longitude, &time, h0));
+            }
+            (t - start) / SECONDS_PER_DAY
+        };
+        let rise = event(&|rs| rs.rise.unwrap());
+        let transit = event(&|rs| rs.upper_transit);
+        let set = event(&|rs| rs.set.unwrap());
+        // Meeus gives m1 = 0.51766, m0 = 0.81980 and m2 = 0.12130
+        for (m, expected) in [(rise, 0.51766), (transit, 0.81980), (set, 0.12130)] {
+            assert!((m - expected).abs() < 1e-4, "{m} {expected}");
+        }
+    }
+}
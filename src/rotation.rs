@@ -0,0 +1,312 @@
+//a Documentation
+//! 3x3 rotation matrices and vector helpers on plain f64 arrays
+//!
+//! The axis rotations follow the usual astronomical (SOFA) convention
+//! of rotating the *frame* by a positive angle, so that rot_z(a)
+//! applied to a vector with longitude L gives one with longitude L-a
+
+//a Imports
+use geo_nd_wasm::WasmQuatf64;
+
+//a Types
+//tp Mat3
+/// A 3x3 matrix stored as rows
+pub type Mat3 = [[f64; 3]; 3];
+
+/// The identity matrix
+pub const IDENTITY: Mat3 = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+
+//a Matrix functions
+//fp rot_x
+/// Rotate the frame about the X axis
+pub fn rot_x(a: f64) -> Mat3 {
+    let (s, c) = a.sin_cos();
+    [[1., 0., 0.], [0., c, s], [0., -s, c]]
+}
+
+//fp rot_y
+/// Rotate the frame about the Y axis
+pub fn rot_y(a: f64) -> Mat3 {
+    let (s, c) = a.sin_cos();
+    [[c, 0., -s], [0., 1., 0.], [s, 0., c]]
+}
+
+//fp rot_z
+/// Rotate the frame about the Z axis
+pub fn rot_z(a: f64) -> Mat3 {
+    let (s, c) = a.sin_cos();
+    [[c, s, 0.], [-s, c, 0.], [0., 0., 1.]]
+}
+
+//fp mul
+/// Multiply two matrices, so that the result applies 'b' then 'a'
+pub fn mul(a: &Mat3, b: &Mat3) -> Mat3 {
+    std::array::from_fn(|i| {
+        std::array::from_fn(|j| a[i][0] * b[0][j] + a[i][1] * b[1][j] + a[i][2] * b[2][j])
+    })
+}
+
+//fp transpose
+/// Transpose a matrix; for a rotation this is its inverse
+pub fn transpose(m: &Mat3) -> Mat3 {
+    std::array::from_fn(|i| std::array::from_fn(|j| m[j][i]))
+}
+
+//fp apply
+/// Apply a matrix to a vector
+pub fn apply(m: &Mat3, v: &[f64; 3]) -> [f64; 3] {
+    [dot(&m[0], v), dot(&m[1], v), dot(&m[2], v)]
+}
+
+//fp rijk_of_matrix
+/// Get the unit quaternion (r, i, j, k) that rotates vectors as the
+/// matrix does
+pub fn rijk_of_matrix(m: &Mat3) -> (f64, f64, f64, f64) {
+    let trace = m[0][0] + m[1][1] + m[2][2];
+    let (r, i, j, k) = {
+        if trace > 0. {
+            let s = (trace + 1.0).sqrt() * 2.;
+            (
+                0.25 * s,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.;
+            (
+                (m[2][1] - m[1][2]) / s,
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.;
+            (
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.;
+            (
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+            )
+        }
+    };
+    // Keep the real part non-negative for consistency
+    if r < 0. {
+        (-r, -i, -j, -k)
+    } else {
+        (r, i, j, k)
+    }
+}
+
+//fp matrix_of_rijk
+/// Get the rotation matrix for a unit quaternion (r, i, j, k)
+pub fn matrix_of_rijk(q: (f64, f64, f64, f64)) -> Mat3 {
+    let (r, i, j, k) = q;
+    [
+        [
+            1. - 2. * (j * j + k * k),
+            2. * (i * j - k * r),
+            2. * (i * k + j * r),
+        ],
+        [
+            2. * (i * j + k * r),
+            1. - 2. * (i * i + k * k),
+            2. * (j * k - i * r),
+        ],
+        [
+            2. * (i * k - j * r),
+            2. * (j * k + i * r),
+            1. - 2. * (i * i + j * j),
+        ],
+    ]
+}
+
+//fp fit_rotation
+/// Find the rotation that best maps each of a set of unit vectors to
+/// its pair, weighted, in the least squares sense
+///
+/// This is Horn's method: the quaternion of the rotation is the
+/// eigenvector of the largest eigenvalue of a symmetric 4x4 matrix
+/// formed from the correlation of the pairs
+pub fn fit_rotation(pairs: &[([f64; 3], [f64; 3], f64)]) -> Mat3 {
+    let mut s = [[0.; 3]; 3];
+    for (a, b, w) in pairs {
+        for i in 0..3 {
+            for j in 0..3 {
+                s[i][j] += w * a[i] * b[j];
+            }
+        }
+    }
+    let n = [
+        [
+            s[0][0] + s[1][1] + s[2][2],
+            s[1][2] - s[2][1],
+            s[2][0] - s[0][2],
+            s[0][1] - s[1][0],
+        ],
+        [
+            s[1][2] - s[2][1],
+            s[0][0] - s[1][1] - s[2][2],
+            s[0][1] + s[1][0],
+            s[2][0] + s[0][2],
+        ],
+        [
+            s[2][0] - s[0][2],
+            s[0][1] + s[1][0],
+            -s[0][0] + s[1][1] - s[2][2],
+            s[1][2] + s[2][1],
+        ],
+        [
+            s[0][1] - s[1][0],
+            s[2][0] + s[0][2],
+            s[1][2] + s[2][1],
+            -s[0][0] - s[1][1] + s[2][2],
+        ],
+    ];
+    let (values, vectors) = symmetric_eigen4(n);
+    let best = (0..4).fold(0, |b, i| if values[i] > values[b] { i } else { b });
+    let q = [
+        vectors[0][best],
+        vectors[1][best],
+        vectors[2][best],
+        vectors[3][best],
+    ];
+    let l = q.iter().map(|x| x * x).sum::<f64>().sqrt();
+    matrix_of_rijk((q[0] / l, q[1] / l, q[2] / l, q[3] / l))
+}
+
+//fi symmetric_eigen4
+/// The eigenvalues and eigenvectors (as columns) of a symmetric 4x4
+/// matrix, by cyclic Jacobi rotations
+fn symmetric_eigen4(mut a: [[f64; 4]; 4]) -> ([f64; 4], [[f64; 4]; 4]) {
+    let mut v = [[0.; 4]; 4];
+    for (i, row) in v.iter_mut().enumerate() {
+        row[i] = 1.;
+    }
+    for _ in 0..50 {
+        let off: f64 = (0..4)
+            .flat_map(|p| ((p + 1)..4).map(move |q| (p, q)))
+            .map(|(p, q)| a[p][q] * a[p][q])
+            .sum();
+        if off < 1e-30 {
+            break;
+        }
+        for p in 0..4 {
+            for q in (p + 1)..4 {
+                if a[p][q].abs() < 1e-300 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2. * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+                let t = if theta == 0. { 1. } else { t };
+                let c = 1. / (t * t + 1.).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (ap, aq) = (a[p], a[q]);
+                for k in 0..4 {
+                    a[p][k] = c * ap[k] - s * aq[k];
+                    a[q][k] = s * ap[k] + c * aq[k];
+                }
+                for row in v.iter_mut() {
+                    let (vp, vq) = (row[p], row[q]);
+                    row[p] = c * vp - s * vq;
+                    row[q] = s * vp + c * vq;
+                }
+            }
+        }
+    }
+    ([a[0][0], a[1][1], a[2][2], a[3][3]], v)
+}
+
+//fp wasm_quat_of_matrix
+/// Create a [WasmQuatf64] that rotates vectors as the matrix does
+pub fn wasm_quat_of_matrix(m: &Mat3) -> WasmQuatf64 {
+    use geo_nd_wasm::geo_nd::Quaternion;
+    let (r, i, j, k) = rijk_of_matrix(m);
+    crate::Quatf64::of_rijk(r, i, j, k).into()
+}
+
+//fp matrix_of_wasm_quat
+/// Get the rotation matrix that rotates vectors as a [WasmQuatf64] does
+pub fn matrix_of_wasm_quat(q: &WasmQuatf64) -> Mat3 {
+    use geo_nd_wasm::geo_nd::Quaternion;
+    let q: &crate::Quatf64 = q;
+    let (r, i, j, k) = q.as_rijk();
+    let l = (r * r + i * i + j * j + k * k).sqrt();
+    matrix_of_rijk((r / l, i / l, j / l, k / l))
+}
+
+//a Vector functions
+//fp dot
+pub fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+//fp cross
+pub fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+//fp length
+pub fn length(a: &[f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+//fp normalize
+/// Normalize a vector; a zero vector is returned unchanged
+pub fn normalize(a: &[f64; 3]) -> [f64; 3] {
+    let l = length(a);
+    if l == 0. {
+        *a
+    } else {
+        [a[0] / l, a[1] / l, a[2] / l]
+    }
+}
+
+//fp scale
+pub fn scale(a: &[f64; 3], s: f64) -> [f64; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+//fp add
+pub fn add(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+//fp sub
+pub fn sub(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+//fp vec_of_ra_de
+/// Get the unit vector for a right ascension and declination in radians
+pub fn vec_of_ra_de(ra: f64, de: f64) -> [f64; 3] {
+    let (sr, cr) = ra.sin_cos();
+    let (sd, cd) = de.sin_cos();
+    [cd * cr, cd * sr, sd]
+}
+
+//fp ra_de_of_vec
+/// Get the right ascension (0 to 2*PI) and declination in radians of a
+/// (not necessarily unit) vector
+pub fn ra_de_of_vec(v: &[f64; 3]) -> (f64, f64) {
+    let ra = v[1].atan2(v[0]).rem_euclid(std::f64::consts::TAU);
+    let de = v[2].atan2((v[0] * v[0] + v[1] * v[1]).sqrt());
+    (ra, de)
+}
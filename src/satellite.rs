@@ -0,0 +1,409 @@
+//a Documentation
+//! Earth satellites from two-line element sets: positions for an
+//! observer and passes over the observer
+//!
+//! The SGP4 propagator gives positions in the TEME frame of the element
+//! set; these are rotated by the equation of the equinoxes to the true
+//! equator and equinox of date, and then to J2000 unless the observer
+//! uses the frame of date. Light time (a few milliseconds) and polar
+//! motion are ignored
+//!
+//! A satellite is sunlit unless it is within the cylinder of the
+//! Earth's shadow on the far side of the Earth from the Sun
+//!
+//! Passes are found by sampling the satellite's altitude and bisecting
+//! to find where it crosses the minimum elevation, with the highest
+//! point found by a golden section search
+
+//a Imports
+use crate::observer::Observer;
+use crate::precession;
+use crate::rotation;
+use crate::sgp4::{Sgp4, EARTH_RADIUS_KM};
+use crate::sun;
+use crate::time::{self, Time, SECONDS_PER_DAY};
+use crate::tle::Tle;
+
+//a Constants
+/// Interval in seconds between samples of the altitude when finding
+/// passes; short enough not to miss a low pass of a low satellite
+const SAMPLE_INTERVAL: f64 = 30.0;
+
+/// Number of bisections of a sample interval to find a pass start or
+/// end, to better than 0.01 seconds
+const BISECTIONS: usize = 12;
+
+/// Number of golden section steps to find the highest point of a pass
+const GOLDEN_STEPS: usize = 30;
+
+/// Longest search for passes, in seconds; element sets are only good
+/// for a few days either side of their epoch
+const MAX_PASS_SEARCH: f64 = 31. * SECONDS_PER_DAY;
+
+//a SatellitePosition
+//tp SatellitePosition
+/// The position of a satellite as seen by an observer at a time
+#[derive(Debug, Clone)]
+pub struct SatellitePosition {
+    /// Unix time in seconds
+    pub unix_time: f64,
+    /// Position relative to the center of the Earth in km in the ECEF
+    /// frame of the observer
+    pub geocentric: [f64; 3],
+    /// Position relative to the observer in km in the ECEF frame
+    pub topocentric: [f64; 3],
+    /// Distance from the observer in km
+    pub range: f64,
+    /// Altitude in radians, refracted if the observer has refraction
+    pub altitude: f64,
+    /// Azimuth in radians from north through east
+    pub azimuth: f64,
+    /// Height above the (spherical) Earth in km
+    pub height: f64,
+    /// True if the satellite is in sunlight
+    pub sunlit: bool,
+}
+
+//a SatellitePass
+//tp SatellitePass
+/// A pass of a satellite above a minimum elevation
+///
+/// If the satellite is already above the minimum elevation at the
+/// start of the search, or still above it at the end, then the pass
+/// is clipped to the search
+#[derive(Debug, Clone)]
+pub struct SatellitePass {
+    /// Position at the start of the pass
+    pub start: SatellitePosition,
+    /// Position at the highest point of the pass
+    pub max: SatellitePosition,
+    /// Position at the end of the pass
+    pub end: SatellitePosition,
+}
+
+//ip SatellitePass
+impl SatellitePass {
+    //ap duration
+    /// Duration of the pass in seconds
+    pub fn duration(&self) -> f64 {
+        self.end.unix_time - self.start.unix_time
+    }
+
+    //ap sunlit
+    /// True if the satellite is in sunlight at any of the start, highest
+    /// point or end of the pass
+    pub fn sunlit(&self) -> bool {
+        self.start.sunlit || self.max.sunlit || self.end.sunlit
+    }
+}
+
+//a Satellite
+//tp Satellite
+/// A satellite with its element set and initialized propagator
+#[derive(Debug, Clone)]
+pub struct Satellite {
+    tle: Tle,
+    sgp4: Sgp4,
+}
+
+//ip Satellite
+impl Satellite {
+    //cp new
+    pub fn new(tle: Tle) -> Result<Self, String> {
+        let sgp4 = Sgp4::new(&tle).map_err(|e| format!("{}: {e}", tle.name))?;
+        Ok(Self { tle, sgp4 })
+    }
+
+    //ap tle
+    pub fn tle(&self) -> &Tle {
+        &self.tle
+    }
+
+    //ap name
+    pub fn name(&self) -> &str {
+        &self.tle.name
+    }
+
+    //ap period
+    /// The period of the orbit in seconds
+    pub fn period(&self) -> f64 {
+        std::f64::consts::TAU / self.tle.mean_motion * 60.
+    }
+
+    //mp teme_state
+    /// The position (km) and velocity (km/s) in the TEME frame at a
+    /// Unix time
+    pub fn teme_state(&self, unix_time: f64) -> Result<([f64; 3], [f64; 3]), String> {
+        self.sgp4
+            .propagate_jd(time::jd_of_unix_time(unix_time))
+            .map_err(|e| format!("{}: {e}", self.tle.name))
+    }
+
+    //mp position_of_date
+    /// The position (km) relative to the center of the Earth in the true
+    /// equator and equinox of date at a time
+    pub fn position_of_date(&self, time: &Time) -> Result<[f64; 3], String> {
+        let (r, _) = self.teme_state(time.unix_time())?;
+        let eqeq = precession::equation_of_the_equinoxes(time.jd_tt());
+        Ok(rotation::apply(&rotation::rot_z(-eqeq), &r))
+    }
+
+    //mp position
+    /// The position of the satellite for an observer at the observer's
+    /// time
+    pub fn position(&self, observer: &Observer) -> Result<SatellitePosition, String> {
+        let time = observer.time();
+        let of_date = self.position_of_date(time)?;
+        let sun = rotation::normalize(&sun::sun_apparent_vector_of_date(time.jd_tt()));
+        let sunlit = is_sunlit(&of_date, &sun);
+        let geocentric = {
+            if observer.of_date() {
+                of_date
+            } else {
+                let pn = precession::precession_nutation_matrix(time.jd_tt());
+                rotation::apply(&rotation::transpose(&pn), &of_date)
+            }
+        };
+        let topocentric = rotation::sub(
+            &geocentric,
+            &rotation::scale(&observer.geocentric_position(), 0.001),
+        );
+        let range = rotation::length(&topocentric);
+        let (altitude, azimuth) =
+            observer.alt_az_of_vector(&rotation::scale(&topocentric, 1. / range));
+        Ok(SatellitePosition {
+            unix_time: time.unix_time(),
+            geocentric,
+            topocentric,
+            range,
+            altitude,
+            azimuth,
+            height: rotation::length(&geocentric) - EARTH_RADIUS_KM,
+            sunlit,
+        })
+    }
+
+    //mp position_at
+    /// The position of the satellite for an observer at a Unix time
+    pub fn position_at(
+        &self,
+        observer: &Observer,
+        unix_time: f64,
+    ) -> Result<SatellitePosition, String> {
+        let mut observer = observer.clone();
+        let time = observer
+            .time()
+            .add_seconds(unix_time - observer.time().unix_time());
+        observer.set_time(time);
+        self.position(&observer)
+    }
+
+    //mp passes
+    /// Find the passes of the satellite above a minimum elevation
+    /// (radians) between two Unix times, at most 31 days apart
+    pub fn passes(
+        &self,
+        observer: &Observer,
+        start: f64,
+        end: f64,
+        min_elevation: f64,
+    ) -> Result<Vec<SatellitePass>, String> {
+        if !start.is_finite() || !end.is_finite() || end < start {
+            return Err(format!("Bad search for passes from {start} to {end}"));
+        }
+        if end - start > MAX_PASS_SEARCH {
+            return Err(format!(
+                "Search for passes of {} days is longer than the maximum of {} days",
+                (end - start) / SECONDS_PER_DAY,
+                MAX_PASS_SEARCH / SECONDS_PER_DAY
+            ));
+        }
+        let altitude = |t: f64| {
+            self.position_at(observer, t)
+                .map(|p| p.altitude)
+                .unwrap_or(-std::f64::consts::FRAC_PI_2)
+        };
+        let n = ((end - start) / SAMPLE_INTERVAL).ceil().max(1.) as usize;
+        let times: Vec<f64> = (0..=n)
+            .map(|i| start + (end - start) * (i as f64) / (n as f64))
+            .collect();
+        let samples: Vec<f64> = times.iter().map(|t| altitude(*t)).collect();
+
+        let mut passes = vec![];
+        let mut i = 0;
+        while i < times.len() {
+            if samples[i] < min_elevation {
+                i += 1;
+                continue;
+            }
+            // A pass starts at or before sample 'i'; find its last sample
+            let first = i;
+            while i + 1 < times.len() && samples[i + 1] >= min_elevation {
+                i += 1;
+            }
+            let last = i;
+            i += 1;
+
+            let pass_start = {
+                if first == 0 {
+                    times[0]
+                } else {
+                    bisect(times[first - 1], times[first], min_elevation, &altitude)
+                }
+            };
+            let pass_end = {
+                if last + 1 == times.len() {
+                    times[last]
+                } else {
+                    bisect(times[last + 1], times[last], min_elevation, &altitude)
+                }
+            };
+            let best = (first..=last)
+                .max_by(|a, b| samples[*a].total_cmp(&samples[*b]))
+                .unwrap();
+            let t0 = times[best.saturating_sub(1)].max(pass_start);
+            let t1 = times[(best + 1).min(times.len() - 1)].min(pass_end);
+            let pass_max = golden_max(t0, t1, &altitude);
+
+            let positions = (
+                self.position_at(observer, pass_start),
+                self.position_at(observer, pass_max),
+                self.position_at(observer, pass_end),
+            );
+            if let (Ok(start), Ok(max), Ok(end)) = positions {
+                passes.push(SatellitePass { start, max, end });
+            }
+        }
+        Ok(passes)
+    }
+}
+
+//a Functions
+//fp is_sunlit
+/// Determine if a geocentric position (km) is in sunlight given the
+/// unit vector toward the Sun, using a cylindrical shadow
+pub fn is_sunlit(position: &[f64; 3], sun: &[f64; 3]) -> bool {
+    let along = rotation::dot(position, sun);
+    if along >= 0. {
+        return true;
+    }
+    let across = rotation::sub(position, &rotation::scale(sun, along));
+    rotation::length(&across) > EARTH_RADIUS_KM
+}
+
+//fi bisect
+/// Bisect between a time below an altitude and a time above it to find
+/// the crossing
+fn bisect<F: Fn(f64) -> f64>(mut below: f64, mut above: f64, h: f64, altitude: &F) -> f64 {
+    for _ in 0..BISECTIONS {
+        let t = (below + above) / 2.;
+        if altitude(t) < h {
+            below = t;
+        } else {
+            above = t;
+        }
+    }
+    (below + above) / 2.
+}
+
+//fi golden_max
+/// Find the time of the highest altitude between two times by golden
+/// section search
+fn golden_max<F: Fn(f64) -> f64>(mut t0: f64, mut t1: f64, altitude: &F) -> f64 {
+    let r = (5.0_f64.sqrt() - 1.) / 2.;
+    let mut a = t1 - r * (t1 - t0);
+    let mut b = t0 + r * (t1 - t0);
+    let mut fa = altitude(a);
+    let mut fb = altitude(b);
+    for _ in 0..GOLDEN_STEPS {
+        if fa > fb {
+            t1 = b;
+            b = a;
+            fb = fa;
+            a = t1 - r * (t1 - t0);
+            fa = altitude(a);
+        } else {
+            t0 = a;
+            a = b;
+            fa = fb;
+            b = t0 + r * (t1 - t0);
+            fb = altitude(b);
+        }
+    }
+    (t0 + t1) / 2.
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISS_1: &str = "1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927";
+    const ISS_2: &str = "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537";
+
+    /// The ISS from London over the day after the epoch of its elements
+    fn iss_from_london() -> (Satellite, Observer) {
+        let tle = Tle::parse_lines(Some("ISS"), 1, ISS_1, ISS_2).unwrap();
+        let satellite = Satellite::new(tle).unwrap();
+        let time = Time::of_unix_time(satellite.tle().epoch);
+        let observer = Observer::new(51.5_f64.to_radians(), 0., 0., time);
+        (satellite, observer)
+    }
+
+    #[test]
+    fn shadow() {
+        let sun = [1., 0., 0.];
+        let r = EARTH_RADIUS_KM;
+        assert!(is_sunlit(&[r + 400., 0., 0.], &sun));
+        assert!(is_sunlit(&[0., r + 400., 0.], &sun));
+        assert!(is_sunlit(&[-r - 400., 0., r + 1.], &sun));
+        assert!(!is_sunlit(&[-r - 400., 0., 0.], &sun));
+        assert!(!is_sunlit(&[-r - 400., 0., r - 1.], &sun));
+        assert!(!is_sunlit(&[-40000., 1000., -1000.], &sun));
+    }
+
+    #[test]
+    fn iss_passes() {
+        let (satellite, observer) = iss_from_london();
+        let start = satellite.tle().epoch;
+        let min_elevation = 10_f64.to_radians();
+        let passes = satellite
+            .passes(&observer, start, start + SECONDS_PER_DAY, min_elevation)
+            .unwrap();
+        // Several passes a day, each of a few minutes, as the ISS
+        // reaches 51.6 degrees north and the orbits drift west
+        assert!((3..=6).contains(&passes.len()), "{}", passes.len());
+        for (i, pass) in passes.iter().enumerate() {
+            assert!(pass.start.unix_time > start);
+            assert!(pass.duration() > 0. && pass.duration() < 12. * 60.);
+            assert!(pass.start.unix_time < pass.max.unix_time);
+            assert!(pass.max.unix_time < pass.end.unix_time);
+            assert!((pass.start.altitude - min_elevation).abs() < 1e-3);
+            assert!((pass.end.altitude - min_elevation).abs() < 1e-3);
+            assert!(pass.max.altitude > min_elevation);
+            for dt in [-10., 10.] {
+                let p = satellite
+                    .position_at(&observer, pass.max.unix_time + dt)
+                    .unwrap();
+                assert!(p.altitude < pass.max.altitude);
+            }
+            assert!(pass.max.height > 300. && pass.max.height < 400.);
+            assert!(pass.max.range < pass.start.range);
+            if i > 0 {
+                let gap = pass.start.unix_time - passes[i - 1].start.unix_time;
+                assert!(gap > 0.8 * satellite.period(), "{gap}");
+            }
+        }
+    }
+
+    #[test]
+    fn pass_search() {
+        let (satellite, observer) = iss_from_london();
+        let start = satellite.tle().epoch;
+        assert!(satellite.passes(&observer, start, start - 1., 0.).is_err());
+        assert!(satellite.passes(&observer, start, f64::NAN, 0.).is_err());
+        let end = start + 32. * SECONDS_PER_DAY;
+        assert!(satellite.passes(&observer, start, end, 0.).is_err());
+        assert!(satellite.passes(&observer, start, start, 0.).is_ok());
+    }
+}
@@ -0,0 +1,1160 @@
+//a Documentation
+//! SGP4/SDP4 propagation of NORAD two-line element sets
+//!
+//! This follows the revised SGP4 of Vallado, Crawford, Hujsak and
+//! Kelso, 'Revisiting Spacetrack Report #3' (AIAA 2006-6753), with the
+//! WGS72 constants used to produce element sets. Orbits with a period
+//! of 225 minutes or more use the deep space (SDP4) lunar and solar
+//! perturbations and, for 12 hour and geosynchronous orbits, the
+//! resonance terms
+//!
+//! Positions and velocities are in km and km/s in the TEME (true
+//! equator, mean equinox) frame of the element set; times are minutes
+//! since the element set epoch
+
+//a Imports
+use std::f64::consts::{PI, TAU};
+
+use crate::tle::Tle;
+
+//a Constants
+/// WGS72 radius of the Earth in km
+pub const EARTH_RADIUS_KM: f64 = 6378.135;
+
+/// WGS72 gravitational parameter of the Earth in km^3/s^2
+const MU: f64 = 398600.8;
+
+/// WGS72 zonal harmonics
+const J2: f64 = 0.001082616;
+const J3: f64 = -0.00000253881;
+const J4: f64 = -0.00000165597;
+const J3OJ2: f64 = J3 / J2;
+
+const X2O3: f64 = 2.0 / 3.0;
+
+/// Earth rotation rate in radians per minute
+const RPTIM: f64 = 4.375_269_088_011_3e-3;
+
+/// Julian date of 1949 December 31 0h, from which SGP4 epochs are
+/// counted
+const JD_1950: f64 = 2433281.5;
+
+//a Sgp4Error
+//tp Sgp4Error
+/// Errors in propagation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sgp4Error {
+    /// The mean eccentricity is out of range
+    Eccentricity,
+    /// The mean motion is not positive
+    MeanMotion,
+    /// The perturbed eccentricity is out of range
+    PerturbedEccentricity,
+    /// The semi-latus rectum is negative
+    SemiLatusRectum,
+    /// The satellite has decayed below the surface of the Earth
+    Decayed,
+}
+
+//ip Display for Sgp4Error
+impl std::fmt::Display for Sgp4Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            Self::Eccentricity => "mean eccentricity out of range",
+            Self::MeanMotion => "mean motion is not positive",
+            Self::PerturbedEccentricity => "perturbed eccentricity out of range",
+            Self::SemiLatusRectum => "semi-latus rectum is negative",
+            Self::Decayed => "satellite has decayed",
+        };
+        f.write_str(s)
+    }
+}
+
+//a Functions
+//fp xke
+/// sqrt(GM) in Earth radii^1.5 per minute
+fn xke() -> f64 {
+    60.0 / (EARTH_RADIUS_KM.powi(3) / MU).sqrt()
+}
+
+//fp gstime
+/// Greenwich mean sidereal time (IAU 1982) in radians for a UT1 Julian
+/// date, as used with TEME
+pub fn gstime(jd_ut1: f64) -> f64 {
+    let tut1 = (jd_ut1 - 2451545.0) / 36525.0;
+    let temp = -6.2e-6 * tut1 * tut1 * tut1
+        + 0.093104 * tut1 * tut1
+        + (876600.0 * 3600.0 + 8640184.812866) * tut1
+        + 67310.54841;
+    (temp.to_radians() / 240.0).rem_euclid(TAU)
+}
+
+//a DeepSpace
+//tp DeepSpace
+/// Lunar and solar perturbation and resonance terms for deep space
+/// orbits
+#[derive(Debug, Clone, Default)]
+struct DeepSpace {
+    // Lunar-solar periodic terms
+    e3: f64,
+    ee2: f64,
+    se2: f64,
+    se3: f64,
+    sgh2: f64,
+    sgh3: f64,
+    sgh4: f64,
+    sh2: f64,
+    sh3: f64,
+    si2: f64,
+    si3: f64,
+    sl2: f64,
+    sl3: f64,
+    sl4: f64,
+    xgh2: f64,
+    xgh3: f64,
+    xgh4: f64,
+    xh2: f64,
+    xh3: f64,
+    xi2: f64,
+    xi3: f64,
+    xl2: f64,
+    xl3: f64,
+    xl4: f64,
+    zmol: f64,
+    zmos: f64,
+    // Secular rates
+    dedt: f64,
+    didt: f64,
+    dmdt: f64,
+    dnodt: f64,
+    domdt: f64,
+    // Resonance: 0 none, 1 synchronous, 2 half day
+    irez: u32,
+    d2201: f64,
+    d2211: f64,
+    d3210: f64,
+    d3222: f64,
+    d4410: f64,
+    d4422: f64,
+    d5220: f64,
+    d5232: f64,
+    d5421: f64,
+    d5433: f64,
+    del1: f64,
+    del2: f64,
+    del3: f64,
+    xfact: f64,
+    xlamo: f64,
+    gsto: f64,
+}
+
+//tp LunarSolar
+/// Intermediate lunar and solar terms from the deep space common
+/// calculation, used to initialize the deep space terms
+#[derive(Debug, Clone, Default)]
+struct LunarSolar {
+    s: [f64; 8],
+    ss: [f64; 8],
+    z: [f64; 4],
+    sz: [f64; 4],
+    z1x: [[f64; 4]; 4],
+    sz1x: [[f64; 4]; 4],
+    sinim: f64,
+    cosim: f64,
+    emsq: f64,
+}
+
+//ip DeepSpace
+impl DeepSpace {
+    //cp dscom
+    /// Calculate the lunar and solar terms (Vallado's dscom) for the
+    /// epoch (days since 1950) and mean elements
+    fn dscom(
+        epoch: f64,
+        ep: f64,
+        argpp: f64,
+        inclp: f64,
+        nodep: f64,
+        np: f64,
+    ) -> (Self, LunarSolar) {
+        const ZES: f64 = 0.01675;
+        const ZEL: f64 = 0.05490;
+        const C1SS: f64 = 2.9864797e-6;
+        const C1L: f64 = 4.7968065e-7;
+        const ZSINIS: f64 = 0.39785416;
+        const ZCOSIS: f64 = 0.91744867;
+        const ZCOSGS: f64 = 0.1945905;
+        const ZSINGS: f64 = -0.98088458;
+
+        let nm = np;
+        let em = ep;
+        let (snodm, cnodm) = nodep.sin_cos();
+        let (sinomm, cosomm) = argpp.sin_cos();
+        let (sinim, cosim) = inclp.sin_cos();
+        let emsq = em * em;
+        let betasq = 1.0 - emsq;
+        let rtemsq = betasq.sqrt();
+
+        let day = epoch + 18261.5;
+        let xnodce = (4.5236020 - 9.2422029e-4 * day).rem_euclid(TAU);
+        let (stem, ctem) = xnodce.sin_cos();
+        let zcosil = 0.91375164 - 0.03568096 * ctem;
+        let zsinil = (1.0 - zcosil * zcosil).sqrt();
+        let zsinhl = 0.089683511 * stem / zsinil;
+        let zcoshl = (1.0 - zsinhl * zsinhl).sqrt();
+        let gam = 5.8351514 + 0.0019443680 * day;
+        let zx = 0.39785416 * stem / zsinil;
+        let zy = zcoshl * ctem + 0.91744867 * zsinhl * stem;
+        let zx = gam + zx.atan2(zy) - xnodce;
+        let (zsingl, zcosgl) = zx.sin_cos();
+
+        let mut ls = LunarSolar {
+            sinim,
+            cosim,
+            emsq,
+            ..Default::default()
+        };
+        let mut zcosg = ZCOSGS;
+        let mut zsing = ZSINGS;
+        let mut zcosi = ZCOSIS;
+        let mut zsini = ZSINIS;
+        let mut zcosh = cnodm;
+        let mut zsinh = snodm;
+        let mut cc = C1SS;
+        let xnoi = 1.0 / nm;
+
+        for lsflg in 0..2 {
+            let a1 = zcosg * zcosh + zsing * zcosi * zsinh;
+            let a3 = -zsing * zcosh + zcosg * zcosi * zsinh;
+            let a7 = -zcosg * zsinh + zsing * zcosi * zcosh;
+            let a8 = zsing * zsini;
+            let a9 = zsing * zsinh + zcosg * zcosi * zcosh;
+            let a10 = zcosg * zsini;
+            let a2 = cosim * a7 + sinim * a8;
+            let a4 = cosim * a9 + sinim * a10;
+            let a5 = -sinim * a7 + cosim * a8;
+            let a6 = -sinim * a9 + cosim * a10;
+
+            let x1 = a1 * cosomm + a2 * sinomm;
+            let x2 = a3 * cosomm + a4 * sinomm;
+            let x3 = -a1 * sinomm + a2 * cosomm;
+            let x4 = -a3 * sinomm + a4 * cosomm;
+            let x5 = a5 * sinomm;
+            let x6 = a6 * sinomm;
+            let x7 = a5 * cosomm;
+            let x8 = a6 * cosomm;
+
+            let z31 = 12.0 * x1 * x1 - 3.0 * x3 * x3;
+            let z32 = 24.0 * x1 * x2 - 6.0 * x3 * x4;
+            let z33 = 12.0 * x2 * x2 - 3.0 * x4 * x4;
+            let mut z1 = 3.0 * (a1 * a1 + a2 * a2) + z31 * emsq;
+            let mut z2 = 6.0 * (a1 * a3 + a2 * a4) + z32 * emsq;
+            let mut z3 = 3.0 * (a3 * a3 + a4 * a4) + z33 * emsq;
+            let z11 = -6.0 * a1 * a5 + emsq * (-24.0 * x1 * x7 - 6.0 * x3 * x5);
+            let z12 = -6.0 * (a1 * a6 + a3 * a5)
+                + emsq * (-24.0 * (x2 * x7 + x1 * x8) - 6.0 * (x3 * x6 + x4 * x5));
+            let z13 = -6.0 * a3 * a6 + emsq * (-24.0 * x2 * x8 - 6.0 * x4 * x6);
+            let z21 = 6.0 * a2 * a5 + emsq * (24.0 * x1 * x5 - 6.0 * x3 * x7);
+            let z22 = 6.0 * (a4 * a5 + a2 * a6)
+                + emsq * (24.0 * (x2 * x5 + x1 * x6) - 6.0 * (x4 * x7 + x3 * x8));
+            let z23 = 6.0 * a4 * a6 + emsq * (24.0 * x2 * x6 - 6.0 * x4 * x8);
+            z1 = z1 + z1 + betasq * z31;
+            z2 = z2 + z2 + betasq * z32;
+            z3 = z3 + z3 + betasq * z33;
+            let s3 = cc * xnoi;
+            let s2 = -0.5 * s3 / rtemsq;
+            let s4 = s3 * rtemsq;
+            let s1 = -15.0 * em * s4;
+            let s5 = x1 * x3 + x2 * x4;
+            let s6 = x2 * x3 + x1 * x4;
+            let s7 = x2 * x4 - x1 * x3;
+
+            let s = [0., s1, s2, s3, s4, s5, s6, s7];
+            let z = [0., z1, z2, z3];
+            let z1x = [
+                [0.; 4],
+                [0., z11, z12, z13],
+                [0., z21, z22, z23],
+                [0., z31, z32, z33],
+            ];
+            if lsflg == 0 {
+                // The solar terms; then set up for the lunar terms
+                ls.ss = s;
+                ls.sz = z;
+                ls.sz1x = z1x;
+                zcosg = zcosgl;
+                zsing = zsingl;
+                zcosi = zcosil;
+                zsini = zsinil;
+                zcosh = zcoshl * cnodm + zsinhl * snodm;
+                zsinh = snodm * zcoshl - cnodm * zsinhl;
+                cc = C1L;
+            } else {
+                ls.s = s;
+                ls.z = z;
+                ls.z1x = z1x;
+            }
+        }
+
+        let (ss, sz, sz1x) = (&ls.ss, &ls.sz, &ls.sz1x);
+        let (s, z, z1x) = (&ls.s, &ls.z, &ls.z1x);
+        let ds = DeepSpace {
+            zmol: (4.7199672 + 0.22997150 * day - gam).rem_euclid(TAU),
+            zmos: (6.2565837 + 0.017201977 * day).rem_euclid(TAU),
+            // Solar terms
+            se2: 2.0 * ss[1] * ss[6],
+            se3: 2.0 * ss[1] * ss[7],
+            si2: 2.0 * ss[2] * sz1x[1][2],
+            si3: 2.0 * ss[2] * (sz1x[1][3] - sz1x[1][1]),
+            sl2: -2.0 * ss[3] * sz[2],
+            sl3: -2.0 * ss[3] * (sz[3] - sz[1]),
+            sl4: -2.0 * ss[3] * (-21.0 - 9.0 * emsq) * ZES,
+            sgh2: 2.0 * ss[4] * sz1x[3][2],
+            sgh3: 2.0 * ss[4] * (sz1x[3][3] - sz1x[3][1]),
+            sgh4: -18.0 * ss[4] * ZES,
+            sh2: -2.0 * ss[2] * sz1x[2][2],
+            sh3: -2.0 * ss[2] * (sz1x[2][3] - sz1x[2][1]),
+            // Lunar terms
+            ee2: 2.0 * s[1] * s[6],
+            e3: 2.0 * s[1] * s[7],
+            xi2: 2.0 * s[2] * z1x[1][2],
+            xi3: 2.0 * s[2] * (z1x[1][3] - z1x[1][1]),
+            xl2: -2.0 * s[3] * z[2],
+            xl3: -2.0 * s[3] * (z[3] - z[1]),
+            xl4: -2.0 * s[3] * (-21.0 - 9.0 * emsq) * ZEL,
+            xgh2: 2.0 * s[4] * z1x[3][2],
+            xgh3: 2.0 * s[4] * (z1x[3][3] - z1x[3][1]),
+            xgh4: -18.0 * s[4] * ZEL,
+            xh2: -2.0 * s[2] * z1x[2][2],
+            xh3: -2.0 * s[2] * (z1x[2][3] - z1x[2][1]),
+            ..Default::default()
+        };
+        (ds, ls)
+    }
+
+    //mp dsinit
+    /// Initialize the secular and resonance terms (Vallado's dsinit)
+    #[allow(clippy::too_many_arguments)]
+    fn dsinit(
+        &mut self,
+        ls: &LunarSolar,
+        gsto: f64,
+        ecco: f64,
+        inclo: f64,
+        argpo: f64,
+        nodeo: f64,
+        mo: f64,
+        no: f64,
+        mdot: f64,
+        nodedot: f64,
+        xpidot: f64,
+    ) {
+        const Q22: f64 = 1.7891679e-6;
+        const Q31: f64 = 2.1460748e-6;
+        const Q33: f64 = 2.2123015e-7;
+        const ROOT22: f64 = 1.7891679e-6;
+        const ROOT44: f64 = 7.3636953e-9;
+        const ROOT54: f64 = 2.1765803e-9;
+        const ROOT32: f64 = 3.7393792e-7;
+        const ROOT52: f64 = 1.1428639e-7;
+        const ZNL: f64 = 1.5835218e-4;
+        const ZNS: f64 = 1.19459e-5;
+
+        let (ss, sz, sz1x) = (&ls.ss, &ls.sz, &ls.sz1x);
+        let (s, z, z1x) = (&ls.s, &ls.z, &ls.z1x);
+        let (sinim, cosim, emsq) = (ls.sinim, ls.cosim, ls.emsq);
+        let nm = no;
+        let em = ecco;
+
+        self.gsto = gsto;
+        self.irez = 0;
+        if nm < 0.0052359877 && nm > 0.0034906585 {
+            self.irez = 1;
+        }
+        if (8.26e-3..=9.24e-3).contains(&nm) && em >= 0.5 {
+            self.irez = 2;
+        }
+
+        // Solar terms
+        let ses = ss[1] * ZNS * ss[5];
+        let sis = ss[2] * ZNS * (sz1x[1][1] + sz1x[1][3]);
+        let sls = -ZNS * ss[3] * (sz[1] + sz[3] - 14.0 - 6.0 * emsq);
+        let sghs = ss[4] * ZNS * (sz1x[3][1] + sz1x[3][3] - 6.0);
+        let mut shs = -ZNS * ss[2] * (sz1x[2][1] + sz1x[2][3]);
+        let near_equatorial = !(5.2359877e-2..=PI - 5.2359877e-2).contains(&inclo);
+        if near_equatorial {
+            shs = 0.0;
+        }
+        if sinim != 0.0 {
+            shs /= sinim;
+        }
+        let sgs = sghs - cosim * shs;
+
+        // Lunar terms
+        self.dedt = ses + s[1] * ZNL * s[5];
+        self.didt = sis + s[2] * ZNL * (z1x[1][1] + z1x[1][3]);
+        self.dmdt = sls - ZNL * s[3] * (z[1] + z[3] - 14.0 - 6.0 * emsq);
+        let sghl = s[4] * ZNL * (z1x[3][1] + z1x[3][3] - 6.0);
+        let mut shll = -ZNL * s[2] * (z1x[2][1] + z1x[2][3]);
+        if near_equatorial {
+            shll = 0.0;
+        }
+        self.domdt = sgs + sghl;
+        self.dnodt = shs;
+        if sinim != 0.0 {
+            self.domdt -= cosim / sinim * shll;
+            self.dnodt += shll / sinim;
+        }
+
+        // Resonance terms
+        let theta = gsto.rem_euclid(TAU);
+        if self.irez == 0 {
+            return;
+        }
+        let aonv = (nm / xke()).powf(X2O3);
+        if self.irez == 2 {
+            // Geopotential resonance for 12 hour orbits
+            let cosisq = cosim * cosim;
+            let em = ecco;
+            let emsq = ecco * ecco;
+            let eoc = em * emsq;
+            let g201 = -0.306 - (em - 0.64) * 0.440;
+            let (g211, g310, g322, g410, g422, g520);
+            if em <= 0.65 {
+                g211 = 3.616 - 13.2470 * em + 16.2900 * emsq;
+                g310 = -19.302 + 117.3900 * em - 228.4190 * emsq + 156.5910 * eoc;
+                g322 = -18.9068 + 109.7927 * em - 214.6334 * emsq + 146.5816 * eoc;
+                g410 = -41.122 + 242.6940 * em - 471.0940 * emsq + 313.9530 * eoc;
+                g422 = -146.407 + 841.8800 * em - 1629.014 * emsq + 1083.4350 * eoc;
+                g520 = -532.114 + 3017.977 * em - 5740.032 * emsq + 3708.2760 * eoc;
+            } else {
+                g211 = -72.099 + 331.819 * em - 508.738 * emsq + 266.724 * eoc;
+                g310 = -346.844 + 1582.851 * em - 2415.925 * emsq + 1246.113 * eoc;
+                g322 = -342.585 + 1554.908 * em - 2366.899 * emsq + 1215.972 * eoc;
+                g410 = -1052.797 + 4758.686 * em - 7193.992 * emsq + 3651.957 * eoc;
+                g422 = -3581.690 + 16178.110 * em - 24462.770 * emsq + 12422.520 * eoc;
+                g520 = if em > 0.715 {
+                    -5149.66 + 29936.92 * em - 54087.36 * emsq + 31324.56 * eoc
+                } else {
+                    1464.74 - 4664.75 * em + 3763.64 * emsq
+                };
+            }
+            let (g533, g521, g532);
+            if em < 0.7 {
+                g533 = -919.22770 + 4988.6100 * em - 9064.7700 * emsq + 5542.21 * eoc;
+                g521 = -822.71072 + 4568.6173 * em - 8491.4146 * emsq + 5337.524 * eoc;
+                g532 = -853.66600 + 4690.2500 * em - 8624.7700 * emsq + 5341.4 * eoc;
+            } else {
+                g533 = -37995.780 + 161616.52 * em - 229838.20 * emsq + 109377.94 * eoc;
+                g521 = -51752.104 + 218913.95 * em - 309468.16 * emsq + 146349.42 * eoc;
+                g532 = -40023.880 + 170470.89 * em - 242699.48 * emsq + 115605.82 * eoc;
+            }
+
+            let sini2 = sinim * sinim;
+            let f220 = 0.75 * (1.0 + 2.0 * cosim + cosisq);
+            let f221 = 1.5 * sini2;
+            let f321 = 1.875 * sinim * (1.0 - 2.0 * cosim - 3.0 * cosisq);
+            let f322 = -1.875 * sinim * (1.0 + 2.0 * cosim - 3.0 * cosisq);
+            let f441 = 35.0 * sini2 * f220;
+            let f442 = 39.3750 * sini2 * sini2;
+            let f522 = 9.84375
+                * sinim
+                * (sini2 * (1.0 - 2.0 * cosim - 5.0 * cosisq)
+                    + 0.33333333 * (-2.0 + 4.0 * cosim + 6.0 * cosisq));
+            let f523 = sinim
+                * (4.92187512 * sini2 * (-2.0 - 4.0 * cosim + 10.0 * cosisq)
+                    + 6.56250012 * (1.0 + 2.0 * cosim - 3.0 * cosisq));
+            let f542 = 29.53125
+                * sinim
+                * (2.0 - 8.0 * cosim + cosisq * (-12.0 + 8.0 * cosim + 10.0 * cosisq));
+            let f543 = 29.53125
+                * sinim
+                * (-2.0 - 8.0 * cosim + cosisq * (12.0 + 8.0 * cosim - 10.0 * cosisq));
+
+            let xno2 = nm * nm;
+            let ainv2 = aonv * aonv;
+            let mut temp1 = 3.0 * xno2 * ainv2;
+            let mut temp = temp1 * ROOT22;
+            self.d2201 = temp * f220 * g201;
+            self.d2211 = temp * f221 * g211;
+            temp1 *= aonv;
+            temp = temp1 * ROOT32;
+            self.d3210 = temp * f321 * g310;
+            self.d3222 = temp * f322 * g322;
+            temp1 *= aonv;
+            temp = 2.0 * temp1 * ROOT44;
+            self.d4410 = temp * f441 * g410;
+            self.d4422 = temp * f442 * g422;
+            temp1 *= aonv;
+            temp = temp1 * ROOT52;
+            self.d5220 = temp * f522 * g520;
+            self.d5232 = temp * f523 * g532;
+            temp = 2.0 * temp1 * ROOT54;
+            self.d5421 = temp * f542 * g521;
+            self.d5433 = temp * f543 * g533;
+            self.xlamo = (mo + nodeo + nodeo - theta - theta).rem_euclid(TAU);
+            self.xfact = mdot + self.dmdt + 2.0 * (nodedot + self.dnodt - RPTIM) - no;
+        } else {
+            // Synchronous resonance terms
+            let g200 = 1.0 + emsq * (-2.5 + 0.8125 * emsq);
+            let g310 = 1.0 + 2.0 * emsq;
+            let g300 = 1.0 + emsq * (-6.0 + 6.60937 * emsq);
+            let f220 = 0.75 * (1.0 + cosim) * (1.0 + cosim);
+            let f311 = 0.9375 * sinim * sinim * (1.0 + 3.0 * cosim) - 0.75 * (1.0 + cosim);
+            let f330 = 1.0 + cosim;
+            let f330 = 1.875 * f330 * f330 * f330;
+            let del1 = 3.0 * nm * nm * aonv * aonv;
+            self.del2 = 2.0 * del1 * f220 * g200 * Q22;
+            self.del3 = 3.0 * del1 * f330 * g300 * Q33 * aonv;
+            self.del1 = del1 * f311 * g310 * Q31 * aonv;
+            self.xlamo = (mo + nodeo + argpo - theta).rem_euclid(TAU);
+            self.xfact = mdot + xpidot - RPTIM + self.dmdt + self.domdt + self.dnodt - no;
+        }
+    }
+
+    //mp dspace
+    /// Apply the secular and resonance effects at a time since epoch
+    /// (Vallado's dspace), integrating the resonance from the epoch;
+    /// returns (em, argpm, inclm, mm, nodem, nm)
+    #[allow(clippy::too_many_arguments)]
+    fn dspace(
+        &self,
+        t: f64,
+        no: f64,
+        argpo: f64,
+        argpdot: f64,
+        em: f64,
+        argpm: f64,
+        inclm: f64,
+        mm: f64,
+        nodem: f64,
+    ) -> (f64, f64, f64, f64, f64, f64) {
+        const FASX2: f64 = 0.13130908;
+        const FASX4: f64 = 2.8843198;
+        const FASX6: f64 = 0.37448087;
+        const G22: f64 = 5.7686396;
+        const G32: f64 = 0.95240898;
+        const G44: f64 = 1.8014998;
+        const G52: f64 = 1.0508330;
+        const G54: f64 = 4.4108898;
+        const STEPP: f64 = 720.0;
+        const STEP2: f64 = 259200.0;
+
+        let theta = (self.gsto + t * RPTIM).rem_euclid(TAU);
+        let em = em + self.dedt * t;
+        let inclm = inclm + self.didt * t;
+        let argpm = argpm + self.domdt * t;
+        let nodem = nodem + self.dnodt * t;
+        let mut mm = mm + self.dmdt * t;
+        let mut nm = no;
+
+        if self.irez != 0 {
+            let delt = if t > 0.0 { STEPP } else { -STEPP };
+            let mut atime = 0.0;
+            let mut xni = no;
+            let mut xli = self.xlamo;
+            let (mut xndt, mut xldot, mut xnddt);
+            loop {
+                if self.irez != 2 {
+                    // Near-synchronous resonance terms
+                    xndt = self.del1 * (xli - FASX2).sin()
+                        + self.del2 * (2.0 * (xli - FASX4)).sin()
+                        + self.del3 * (3.0 * (xli - FASX6)).sin();
+                    xldot = xni + self.xfact;
+                    xnddt = self.del1 * (xli - FASX2).cos()
+                        + 2.0 * self.del2 * (2.0 * (xli - FASX4)).cos()
+                        + 3.0 * self.del3 * (3.0 * (xli - FASX6)).cos();
+                    xnddt *= xldot;
+                } else {
+                    // Near-half-day resonance terms
+                    let xomi = argpo + argpdot * atime;
+                    let x2omi = xomi + xomi;
+                    let x2li = xli + xli;
+                    xndt = self.d2201 * (x2omi + xli - G22).sin()
+                        + self.d2211 * (xli - G22).sin()
+                        + self.d3210 * (xomi + xli - G32).sin()
+                        + self.d3222 * (-xomi + xli - G32).sin()
+                        + self.d4410 * (x2omi + x2li - G44).sin()
+                        + self.d4422 * (x2li - G44).sin()
+                        + self.d5220 * (xomi + xli - G52).sin()
+                        + self.d5232 * (-xomi + xli - G52).sin()
+                        + self.d5421 * (xomi + x2li - G54).sin()
+                        + self.d5433 * (-xomi + x2li - G54).sin();
+                    xldot = xni + self.xfact;
+                    xnddt = self.d2201 * (x2omi + xli - G22).cos()
+                        + self.d2211 * (xli - G22).cos()
+                        + self.d3210 * (xomi + xli - G32).cos()
+                        + self.d3222 * (-xomi + xli - G32).cos()
+                        + self.d5220 * (xomi + xli - G52).cos()
+                        + self.d5232 * (-xomi + xli - G52).cos()
+                        + 2.0
+                            * (self.d4410 * (x2omi + x2li - G44).cos()
+                                + self.d4422 * (x2li - G44).cos()
+                                + self.d5421 * (xomi + x2li - G54).cos()
+                                + self.d5433 * (-xomi + x2li - G54).cos());
+                    xnddt *= xldot;
+                }
+                if (t - atime).abs() < STEPP {
+                    break;
+                }
+                xli += xldot * delt + xndt * STEP2;
+                xni += xndt * delt + xnddt * STEP2;
+                atime += delt;
+            }
+            let ft = t - atime;
+            nm = xni + xndt * ft + xnddt * ft * ft * 0.5;
+            let xl = xli + xldot * ft + xndt * ft * ft * 0.5;
+            if self.irez != 1 {
+                mm = xl - 2.0 * nodem + 2.0 * theta;
+            } else {
+                mm = xl - nodem - argpm + theta;
+            }
+        }
+        (em, argpm, inclm, mm, nodem, nm)
+    }
+
+    //mp dpper
+    /// Apply the lunar-solar periodics at a time since epoch (Vallado's
+    /// dpper); returns (ep, inclp, nodep, argpp, mp)
+    fn dpper(
+        &self,
+        t: f64,
+        ep: f64,
+        inclp: f64,
+        nodep: f64,
+        argpp: f64,
+        mp: f64,
+    ) -> (f64, f64, f64, f64, f64) {
+        const ZNS: f64 = 1.19459e-5;
+        const ZES: f64 = 0.01675;
+        const ZNL: f64 = 1.5835218e-4;
+        const ZEL: f64 = 0.05490;
+
+        // The periodics; as in the reference implementation these are
+        // not taken relative to their values at the epoch
+        let periodics = |zm: f64, ze: f64| {
+            let zf = zm + 2.0 * ze * zm.sin();
+            let sinzf = zf.sin();
+            (0.5 * sinzf * sinzf - 0.25, -0.5 * sinzf * zf.cos(), sinzf)
+        };
+        let (f2, f3, sinzf) = periodics(self.zmos + ZNS * t, ZES);
+        let ses = self.se2 * f2 + self.se3 * f3;
+        let sis = self.si2 * f2 + self.si3 * f3;
+        let sls = self.sl2 * f2 + self.sl3 * f3 + self.sl4 * sinzf;
+        let sghs = self.sgh2 * f2 + self.sgh3 * f3 + self.sgh4 * sinzf;
+        let shs = self.sh2 * f2 + self.sh3 * f3;
+
+        let (f2, f3, sinzf) = periodics(self.zmol + ZNL * t, ZEL);
+        let sel = self.ee2 * f2 + self.e3 * f3;
+        let sil = self.xi2 * f2 + self.xi3 * f3;
+        let sll = self.xl2 * f2 + self.xl3 * f3 + self.xl4 * sinzf;
+        let sghl = self.xgh2 * f2 + self.xgh3 * f3 + self.xgh4 * sinzf;
+        let shll = self.xh2 * f2 + self.xh3 * f3;
+
+        let pe = ses + sel;
+        let pinc = sis + sil;
+        let pl = sls + sll;
+        let mut pgh = sghs + sghl;
+        let mut ph = shs + shll;
+
+        let inclp = inclp + pinc;
+        let ep = ep + pe;
+        let (sinip, cosip) = inclp.sin_cos();
+        if inclp >= 0.2 {
+            // Apply the periodics directly
+            ph /= sinip;
+            pgh -= cosip * ph;
+            (ep, inclp, nodep + ph, argpp + pgh, mp + pl)
+        } else {
+            // Apply the periodics with the Lyddane modification
+            let (sinop, cosop) = nodep.sin_cos();
+            let alfdp = sinip * sinop + ph * cosop + pinc * cosip * sinop;
+            let betdp = sinip * cosop - ph * sinop + pinc * cosip * cosop;
+            let xls = mp + argpp + cosip * nodep + pl + pgh - pinc * nodep * sinip;
+            let xnoh = nodep;
+            let mut nodep = alfdp.atan2(betdp);
+            if (xnoh - nodep).abs() > PI {
+                if nodep < xnoh {
+                    nodep += TAU;
+                } else {
+                    nodep -= TAU;
+                }
+            }
+            let mp = mp + pl;
+            let argpp = xls - mp - cosip * nodep;
+            (ep, inclp, nodep, argpp, mp)
+        }
+    }
+}
+
+//a Sgp4
+//tp Sgp4
+/// An initialized SGP4/SDP4 propagator for an element set
+#[derive(Debug, Clone)]
+pub struct Sgp4 {
+    /// Epoch as a UTC Julian date
+    jd_epoch: f64,
+    bstar: f64,
+    ecco: f64,
+    inclo: f64,
+    nodeo: f64,
+    argpo: f64,
+    mo: f64,
+    /// Mean motion (un-Kozai'd) in radians per minute
+    no: f64,
+    isimp: bool,
+    aycof: f64,
+    con41: f64,
+    cc1: f64,
+    cc4: f64,
+    cc5: f64,
+    d2: f64,
+    d3: f64,
+    d4: f64,
+    delmo: f64,
+    eta: f64,
+    argpdot: f64,
+    omgcof: f64,
+    sinmao: f64,
+    t2cof: f64,
+    t3cof: f64,
+    t4cof: f64,
+    t5cof: f64,
+    x1mth2: f64,
+    x7thm1: f64,
+    mdot: f64,
+    nodedot: f64,
+    xlcof: f64,
+    xmcof: f64,
+    nodecf: f64,
+    deep_space: Option<DeepSpace>,
+}
+
+//ip Sgp4
+impl Sgp4 {
+    //cp new
+    /// Initialize the propagator for an element set (Vallado's
+    /// sgp4init)
+    pub fn new(tle: &Tle) -> Result<Self, Sgp4Error> {
+        let xke = xke();
+        let ecco = tle.eccentricity;
+        let inclo = tle.inclination;
+        let nodeo = tle.ascending_node;
+        let argpo = tle.arg_perigee;
+        let mo = tle.mean_anomaly;
+        let bstar = tle.bstar;
+        let jd_epoch = crate::time::jd_of_unix_time(tle.epoch);
+        if !(0.0..1.0).contains(&ecco) {
+            return Err(Sgp4Error::Eccentricity);
+        }
+
+        // Recover the original mean motion and semi-major axis (initl)
+        let eccsq = ecco * ecco;
+        let omeosq = 1.0 - eccsq;
+        let rteosq = omeosq.sqrt();
+        let (sinio, cosio) = inclo.sin_cos();
+        let cosio2 = cosio * cosio;
+        let ak = (xke / tle.mean_motion).powf(X2O3);
+        let d1 = 0.75 * J2 * (3.0 * cosio2 - 1.0) / (rteosq * omeosq);
+        let del = d1 / (ak * ak);
+        let adel = ak * (1.0 - del * del - del * (1.0 / 3.0 + 134.0 * del * del / 81.0));
+        let del = d1 / (adel * adel);
+        let no = tle.mean_motion / (1.0 + del);
+        let ao = (xke / no).powf(X2O3);
+        let po = ao * omeosq;
+        let con42 = 1.0 - 5.0 * cosio2;
+        let con41 = -con42 - cosio2 - cosio2;
+        let posq = po * po;
+        let rp = ao * (1.0 - ecco);
+        let gsto = gstime(jd_epoch);
+
+        let ss = 78.0 / EARTH_RADIUS_KM + 1.0;
+        let qzms2t = ((120.0 - 78.0) / EARTH_RADIUS_KM).powi(4);
+        let mut isimp = rp < 220.0 / EARTH_RADIUS_KM + 1.0;
+        let mut sfour = ss;
+        let mut qzms24 = qzms2t;
+        let perige = (rp - 1.0) * EARTH_RADIUS_KM;
+        if perige < 156.0 {
+            sfour = if perige < 98.0 { 20.0 } else { perige - 78.0 };
+            qzms24 = ((120.0 - sfour) / EARTH_RADIUS_KM).powi(4);
+            sfour = sfour / EARTH_RADIUS_KM + 1.0;
+        }
+        let pinvsq = 1.0 / posq;
+        let tsi = 1.0 / (ao - sfour);
+        let eta = ao * ecco * tsi;
+        let etasq = eta * eta;
+        let eeta = ecco * eta;
+        let psisq = (1.0 - etasq).abs();
+        let coef = qzms24 * tsi.powi(4);
+        let coef1 = coef / psisq.powf(3.5);
+        let cc2 = coef1
+            * no
+            * (ao * (1.0 + 1.5 * etasq + eeta * (4.0 + etasq))
+                + 0.375 * J2 * tsi / psisq * con41 * (8.0 + 3.0 * etasq * (8.0 + etasq)));
+        let cc1 = bstar * cc2;
+        let cc3 = {
+            if ecco > 1.0e-4 {
+                -2.0 * coef * tsi * J3OJ2 * no * sinio / ecco
+            } else {
+                0.0
+            }
+        };
+        let x1mth2 = 1.0 - cosio2;
+        let cc4 = 2.0
+            * no
+            * coef1
+            * ao
+            * omeosq
+            * (eta * (2.0 + 0.5 * etasq) + ecco * (0.5 + 2.0 * etasq)
+                - J2 * tsi / (ao * psisq)
+                    * (-3.0 * con41 * (1.0 - 2.0 * eeta + etasq * (1.5 - 0.5 * eeta))
+                        + 0.75
+                            * x1mth2
+                            * (2.0 * etasq - eeta * (1.0 + etasq))
+                            * (2.0 * argpo).cos()));
+        let cc5 = 2.0 * coef1 * ao * omeosq * (1.0 + 2.75 * (etasq + eeta) + eeta * etasq);
+        let cosio4 = cosio2 * cosio2;
+        let temp1 = 1.5 * J2 * pinvsq * no;
+        let temp2 = 0.5 * temp1 * J2 * pinvsq;
+        let temp3 = -0.46875 * J4 * pinvsq * pinvsq * no;
+        let mdot = no
+            + 0.5 * temp1 * rteosq * con41
+            + 0.0625 * temp2 * rteosq * (13.0 - 78.0 * cosio2 + 137.0 * cosio4);
+        let argpdot = -0.5 * temp1 * con42
+            + 0.0625 * temp2 * (7.0 - 114.0 * cosio2 + 395.0 * cosio4)
+            + temp3 * (3.0 - 36.0 * cosio2 + 49.0 * cosio4);
+        let xhdot1 = -temp1 * cosio;
+        let nodedot = xhdot1
+            + (0.5 * temp2 * (4.0 - 19.0 * cosio2) + 2.0 * temp3 * (3.0 - 7.0 * cosio2)) * cosio;
+        let xpidot = argpdot + nodedot;
+        let omgcof = bstar * cc3 * argpo.cos();
+        let xmcof = {
+            if ecco > 1.0e-4 {
+                -X2O3 * coef * bstar / eeta
+            } else {
+                0.0
+            }
+        };
+        let nodecf = 3.5 * omeosq * xhdot1 * cc1;
+        let t2cof = 1.5 * cc1;
+        let xlcof = -0.25 * J3OJ2 * sinio * (3.0 + 5.0 * cosio) / (1.0 + cosio).max(1.5e-12);
+        let aycof = -0.5 * J3OJ2 * sinio;
+        let delmo = (1.0 + eta * mo.cos()).powi(3);
+        let sinmao = mo.sin();
+        let x7thm1 = 7.0 * cosio2 - 1.0;
+
+        let deep_space = {
+            if TAU / no >= 225.0 {
+                isimp = true;
+                let epoch = jd_epoch - JD_1950;
+                let (mut ds, ls) = DeepSpace::dscom(epoch, ecco, argpo, inclo, nodeo, no);
+                ds.dsinit(
+                    &ls, gsto, ecco, inclo, argpo, nodeo, mo, no, mdot, nodedot, xpidot,
+                );
+                Some(ds)
+            } else {
+                None
+            }
+        };
+
+        let (mut d2, mut d3, mut d4) = (0.0, 0.0, 0.0);
+        let (mut t3cof, mut t4cof, mut t5cof) = (0.0, 0.0, 0.0);
+        if !isimp {
+            let cc1sq = cc1 * cc1;
+            d2 = 4.0 * ao * tsi * cc1sq;
+            let temp = d2 * tsi * cc1 / 3.0;
+            d3 = (17.0 * ao + sfour) * temp;
+            d4 = 0.5 * temp * ao * tsi * (221.0 * ao + 31.0 * sfour) * cc1;
+            t3cof = d2 + 2.0 * cc1sq;
+            t4cof = 0.25 * (3.0 * d3 + cc1 * (12.0 * d2 + 10.0 * cc1sq));
+            t5cof = 0.2
+                * (3.0 * d4 + 12.0 * cc1 * d3 + 6.0 * d2 * d2 + 15.0 * cc1sq * (2.0 * d2 + cc1sq));
+        }
+
+        Ok(Self {
+            jd_epoch,
+            bstar,
+            ecco,
+            inclo,
+            nodeo,
+            argpo,
+            mo,
+            no,
+            isimp,
+            aycof,
+            con41,
+            cc1,
+            cc4,
+            cc5,
+            d2,
+            d3,
+            d4,
+            delmo,
+            eta,
+            argpdot,
+            omgcof,
+            sinmao,
+            t2cof,
+            t3cof,
+            t4cof,
+            t5cof,
+            x1mth2,
+            x7thm1,
+            mdot,
+            nodedot,
+            xlcof,
+            xmcof,
+            nodecf,
+            deep_space,
+        })
+    }
+
+    //mp propagate
+    /// Find the TEME position (km) and velocity (km/s) at a time in
+    /// minutes since the epoch
+    pub fn propagate(&self, tsince: f64) -> Result<([f64; 3], [f64; 3]), Sgp4Error> {
+        let xke = xke();
+        let t = tsince;
+
+        // Secular gravity and atmospheric drag
+        let xmdf = self.mo + self.mdot * t;
+        let argpdf = self.argpo + self.argpdot * t;
+        let nodedf = self.nodeo + self.nodedot * t;
+        let mut argpm = argpdf;
+        let mut mm = xmdf;
+        let t2 = t * t;
+        let mut nodem = nodedf + self.nodecf * t2;
+        let mut tempa = 1.0 - self.cc1 * t;
+        let mut tempe = self.bstar * self.cc4 * t;
+        let mut templ = self.t2cof * t2;
+        if !self.isimp {
+            let delomg = self.omgcof * t;
+            let delmtemp = 1.0 + self.eta * xmdf.cos();
+            let delm = self.xmcof * (delmtemp * delmtemp * delmtemp - self.delmo);
+            let temp = delomg + delm;
+            mm = xmdf + temp;
+            argpm = argpdf - temp;
+            let t3 = t2 * t;
+            let t4 = t3 * t;
+            tempa = tempa - self.d2 * t2 - self.d3 * t3 - self.d4 * t4;
+            tempe += self.bstar * self.cc5 * (mm.sin() - self.sinmao);
+            templ += self.t3cof * t3 + t4 * (self.t4cof + t * self.t5cof);
+        }
+
+        let mut nm = self.no;
+        let mut em = self.ecco;
+        let mut inclm = self.inclo;
+        if let Some(ds) = &self.deep_space {
+            (em, argpm, inclm, mm, nodem, nm) = ds.dspace(
+                t,
+                self.no,
+                self.argpo,
+                self.argpdot,
+                em,
+                argpm,
+                inclm,
+                mm,
+                nodem,
+            );
+        }
+        if nm <= 0.0 {
+            return Err(Sgp4Error::MeanMotion);
+        }
+        let am = (xke / nm).powf(X2O3) * tempa * tempa;
+        let nm = xke / am.powf(1.5);
+        em -= tempe;
+        if !(-0.001..1.0).contains(&em) {
+            return Err(Sgp4Error::Eccentricity);
+        }
+        let em = em.max(1.0e-6);
+        mm += self.no * templ;
+        let xlm = mm + argpm + nodem;
+        let nodem = nodem.rem_euclid(TAU);
+        let argpm = argpm.rem_euclid(TAU);
+        let xlm = xlm.rem_euclid(TAU);
+        let mm = (xlm - argpm - nodem).rem_euclid(TAU);
+
+        // Lunar-solar periodics
+        let (mut ep, mut xincp, mut nodep, mut argpp, mut mp) = (em, inclm, nodem, argpm, mm);
+        let (mut aycof, mut xlcof) = (self.aycof, self.xlcof);
+        let (mut con41, mut x1mth2, mut x7thm1) = (self.con41, self.x1mth2, self.x7thm1);
+        if let Some(ds) = &self.deep_space {
+            (ep, xincp, nodep, argpp, mp) = ds.dpper(t, ep, xincp, nodep, argpp, mp);
+            if xincp < 0.0 {
+                xincp = -xincp;
+                nodep += PI;
+                argpp -= PI;
+            }
+            if !(0.0..=1.0).contains(&ep) {
+                return Err(Sgp4Error::PerturbedEccentricity);
+            }
+            let (sinip, cosip) = xincp.sin_cos();
+            aycof = -0.5 * J3OJ2 * sinip;
+            xlcof = -0.25 * J3OJ2 * sinip * (3.0 + 5.0 * cosip) / (1.0 + cosip).max(1.5e-12);
+            let cosisq = cosip * cosip;
+            con41 = 3.0 * cosisq - 1.0;
+            x1mth2 = 1.0 - cosisq;
+            x7thm1 = 7.0 * cosisq - 1.0;
+        }
+        let (sinip, cosip) = xincp.sin_cos();
+
+        // Long period periodics
+        let axnl = ep * argpp.cos();
+        let temp = 1.0 / (am * (1.0 - ep * ep));
+        let aynl = ep * argpp.sin() + temp * aycof;
+        let xl = mp + argpp + nodep + temp * xlcof * axnl;
+
+        // Solve Kepler's equation
+        let u = (xl - nodep).rem_euclid(TAU);
+        let mut eo1 = u;
+        let (mut sineo1, mut coseo1) = (0.0, 0.0);
+        for _ in 0..10 {
+            (sineo1, coseo1) = eo1.sin_cos();
+            let mut tem5 = 1.0 - coseo1 * axnl - sineo1 * aynl;
+            tem5 = (u - aynl * coseo1 + axnl * sineo1 - eo1) / tem5;
+            tem5 = tem5.clamp(-0.95, 0.95);
+            eo1 += tem5;
+            if tem5.abs() < 1.0e-12 {
+                break;
+            }
+        }
+
+        // Short period preliminary quantities
+        let ecose = axnl * coseo1 + aynl * sineo1;
+        let esine = axnl * sineo1 - aynl * coseo1;
+        let el2 = axnl * axnl + aynl * aynl;
+        let pl = am * (1.0 - el2);
+        if pl < 0.0 {
+            return Err(Sgp4Error::SemiLatusRectum);
+        }
+        let rl = am * (1.0 - ecose);
+        let rdotl = am.sqrt() * esine / rl;
+        let rvdotl = pl.sqrt() / rl;
+        let betal = (1.0 - el2).sqrt();
+        let temp = esine / (1.0 + betal);
+        let sinu = am / rl * (sineo1 - aynl - axnl * temp);
+        let cosu = am / rl * (coseo1 - axnl + aynl * temp);
+        let su = sinu.atan2(cosu);
+        let sin2u = (cosu + cosu) * sinu;
+        let cos2u = 1.0 - 2.0 * sinu * sinu;
+        let temp = 1.0 / pl;
+        let temp1 = 0.5 * J2 * temp;
+        let temp2 = temp1 * temp;
+
+        // Update for short period periodics
+        let mrt = rl * (1.0 - 1.5 * temp2 * betal * con41) + 0.5 * temp1 * x1mth2 * cos2u;
+        let su = su - 0.25 * temp2 * x7thm1 * sin2u;
+        let xnode = nodep + 1.5 * temp2 * cosip * sin2u;
+        let xinc = xincp + 1.5 * temp2 * cosip * sinip * cos2u;
+        let mvt = rdotl - nm * temp1 * x1mth2 * sin2u / xke;
+        let rvdot = rvdotl + nm * temp1 * (x1mth2 * cos2u + 1.5 * con41) / xke;
+
+        // Orientation vectors
+        let (sinsu, cossu) = su.sin_cos();
+        let (snod, cnod) = xnode.sin_cos();
+        let (sini, cosi) = xinc.sin_cos();
+        let xmx = -snod * cosi;
+        let xmy = cnod * cosi;
+        let ux = xmx * sinsu + cnod * cossu;
+        let uy = xmy * sinsu + snod * cossu;
+        let uz = sini * sinsu;
+        let vx = xmx * cossu - cnod * sinsu;
+        let vy = xmy * cossu - snod * sinsu;
+        let vz = sini * cossu;
+
+        if mrt < 1.0 {
+            return Err(Sgp4Error::Decayed);
+        }
+        let vkmpersec = EARTH_RADIUS_KM * xke / 60.0;
+        let r = [
+            mrt * ux * EARTH_RADIUS_KM,
+            mrt * uy * EARTH_RADIUS_KM,
+            mrt * uz * EARTH_RADIUS_KM,
+        ];
+        let v = [
+            (mvt * ux + rvdot * vx) * vkmpersec,
+            (mvt * uy + rvdot * vy) * vkmpersec,
+            (mvt * uz + rvdot * vz) * vkmpersec,
+        ];
+        Ok((r, v))
+    }
+
+    //mp propagate_jd
+    /// Find the TEME position (km) and velocity (km/s) at a UTC Julian
+    /// date
+    pub fn propagate_jd(&self, jd_utc: f64) -> Result<([f64; 3], [f64; 3]), Sgp4Error> {
+        self.propagate((jd_utc - self.jd_epoch) * 1440.0)
+    }
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check the propagation of an element set against positions (km)
+    /// and velocities (km/s) at times in minutes
+    fn check(line1: &str, line2: &str, expected: &[(f64, [f64; 3], [f64; 3])]) {
+        let tle = Tle::parse_lines(None, 1, line1, line2).unwrap();
+        let sgp4 = Sgp4::new(&tle).unwrap();
+        for (t, r_e, v_e) in expected {
+            let (r, v) = sgp4.propagate(*t).unwrap();
+            for i in 0..3 {
+                assert!((r[i] - r_e[i]).abs() < 1e-6, "{t} {r:?}");
+                assert!((v[i] - v_e[i]).abs() < 1e-8, "{t} {v:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn near_earth() {
+        // Vallado SGP4-VER case 00005, with its reference output
+        check(
+            "1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753",
+            "2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667",
+            &[
+                (
+                    0.,
+                    [7022.46529266, -1400.08296755, 0.03995155],
+                    [1.893841015, 6.405893759, 4.534807250],
+                ),
+                (
+                    360.,
+                    [-7154.03120202, -3783.17682504, -3536.19412294],
+                    [4.741887409, -4.151817765, -2.093935425],
+                ),
+                (
+                    720.,
+                    [-7134.59340119, 6531.68641334, 3260.27186483],
+                    [-4.113793027, -2.911922039, -2.557327851],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn deep_space() {
+        // Vallado SGP4-VER case 11801 (the Spacetrack Report #3 SDP4
+        // case), with its reference output
+        let line1 = "1 11801U          80230.29629788  .01431103  00000-0  14311-1      13";
+        let line2 = "2 11801  46.7916 230.4354 7318036  47.4722  10.4117  2.28537848    13";
+        let tle = Tle::parse_lines(None, 1, line1, line2).unwrap();
+        assert!(Sgp4::new(&tle).unwrap().deep_space.is_some());
+        check(
+            line1,
+            line2,
+            &[
+                (
+                    0.,
+                    [7473.37102491, 428.94748312, 5828.74846783],
+                    [5.107155391, 6.444680305, -0.186133297],
+                ),
+                (
+                    360.,
+                    [-3305.22148694, 32410.84323331, -24697.16974954],
+                    [-1.301137319, -1.151315600, -0.283335823],
+                ),
+            ],
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//a Imports
+use wasm_bindgen::prelude::*;
+
+use star_catalog::Star;
+
+//a Encoding
+//fp encode_star
+/// Encode a star as three u32 for the sky shader
+///
+/// The first word holds the larger of |x| and |y| (the 'u' component)
+/// as a 24-bit fraction, with sign bits for u, v and w in bits 24-26,
+/// and bits 27 and 28 indicating that u is x and v is z respectively
+///
+/// The second word holds the 'v' component (the larger of |z| and
+/// |y|) as a 24-bit fraction, and the magnitude (times 4, clamped to
+/// 0-63) in bits 24 upwards
+///
+/// The third word holds the RA in 14 bits, the declination in 12 bits,
+/// and a 4-bit color class derived from the temperature
+pub fn encode_star(star: &Star) -> [u32; 3] {
+    let vxyz = star.vector();
+    let (x, y, z) = (vxyz[0], vxyz[1], vxyz[2]);
+    let (abs_x, x_neg) = (x.abs(), x < 0.);
+    let (abs_y, y_neg) = (y.abs(), y < 0.);
+    let (abs_z, z_neg) = (z.abs(), z < 0.);
+
+    let x_is_u = abs_x >= abs_y || abs_x >= abs_z;
+    let z_is_v = abs_z > abs_x || abs_z >= abs_y;
+    let u_abs = if x_is_u { abs_x } else { abs_y };
+    let v_abs = if z_is_v { abs_z } else { abs_y };
+    let u_neg = if x_is_u { x_neg } else { y_neg };
+    let v_neg = if z_is_v { z_neg } else { y_neg };
+    let w_neg = {
+        if x_is_u {
+            if z_is_v {
+                y_neg
+            } else {
+                z_neg
+            }
+        } else {
+            x_neg
+        }
+    };
+
+    let mut u = ((u_abs * 16777216.0).round() as u32).min(0xffffff);
+    let mut v = ((v_abs * 16777216.0).round() as u32).min(0xffffff);
+    if u_neg {
+        u |= 0x01000000;
+    }
+    if v_neg {
+        u |= 0x02000000;
+    }
+    if w_neg {
+        u |= 0x04000000;
+    }
+    if x_is_u {
+        u |= 0x08000000;
+    }
+    if z_is_v {
+        u |= 0x10000000;
+    }
+
+    // Bound the magnitude to 0 to 12 in quarter magnitudes
+    let m = (star.magnitude() * 4.0).round().clamp(0., 63.) as u32;
+    v |= m << 24;
+
+    let ra = ((star.ra() * 16384.0 / std::f64::consts::TAU + 8192.0).round() as u32) & 16383;
+    let de = ((star.de() * 4096.0 / std::f64::consts::PI + 2048.0).round() as u32) & 4095;
+
+    // The color class is (temperature - 2300k) / 7700k in 4 bits; see
+    // the sky shader for the mapping back to RGB
+    let color = (((star.temp() - 2300.0) / 7700.0 * 15.9).floor()).clamp(0., 15.) as u32;
+    let map = ra | (de << 14) | (color << 26);
+    [u, v, map]
+}
+
+//a WasmStarBuffer
+//tp WasmStarBuffer
+/// A buffer of encoded stars, held in WASM memory so that it can be
+/// handed to WebGL without copying
+#[wasm_bindgen]
+pub struct WasmStarBuffer {
+    data: Vec<u32>,
+    words_per_star: usize,
+}
+
+//ip WasmStarBuffer
+impl WasmStarBuffer {
+    //cp new
+    pub fn new(data: Vec<u32>, words_per_star: usize) -> Self {
+        Self {
+            data,
+            words_per_star,
+        }
+    }
+}
+
+//ip WasmStarBuffer
+#[wasm_bindgen]
+impl WasmStarBuffer {
+    //ap buffer
+    /// The address of the data in WASM memory; use with the 'length'
+    /// to create a Uint32Array view of the memory
+    #[wasm_bindgen(getter)]
+    pub fn buffer(&self) -> *const u32 {
+        self.data.as_ptr()
+    }
+
+    //ap length
+    /// The number of u32 words in the buffer
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.data.len()
+    }
+
+    //ap count
+    /// The number of stars encoded in the buffer
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.data
+            .len()
+            .checked_div(self.words_per_star)
+            .unwrap_or(0)
+    }
+
+    //mp data
+    /// Copy the buffer out to a new Uint32Array
+    pub fn data(&self) -> Vec<u32> {
+        self.data.clone()
+    }
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The color class as packed by the TypeScript star field
+    fn color_class(star: &Star) -> u32 {
+        (((star.temp() - 2300.0) / 7700.0 * 15.9).floor()).clamp(0., 15.) as u32
+    }
+
+    #[test]
+    fn positive_octant() {
+        let star = Star::new(1, 30_f64.to_radians(), 45_f64.to_radians(), 10., 1.5, 0.6);
+        let [u, v, map] = encode_star(&star);
+        // |x| = 0.612372 is u, |z| = 0.707107 is v, all positive
+        assert_eq!(u, 0x9cc471 | 0x08000000 | 0x10000000);
+        assert_eq!(v, 0xb504f3 | (6 << 24));
+        assert_eq!(map, 9557 | (3072 << 14) | (color_class(&star) << 26));
+    }
+
+    #[test]
+    fn negative_octant() {
+        let star = Star::new(
+            2,
+            200_f64.to_radians(),
+            (-60_f64).to_radians(),
+            10.,
+            -1.46,
+            0.,
+        );
+        let [u, v, map] = encode_star(&star);
+        // |x| = 0.469846 is u, |z| = 0.866025 is v, and x, y and z are
+        // all negative; the magnitude clamps to zero
+        assert_eq!(u, 0x7847d9 | 0x07000000 | 0x08000000 | 0x10000000);
+        assert_eq!(v, 0xddb3d7);
+        assert_eq!(map, 910 | (683 << 14) | (color_class(&star) << 26));
+
+        let faint = Star::new(
+            3,
+            200_f64.to_radians(),
+            (-60_f64).to_radians(),
+            10.,
+            20.,
+            0.,
+        );
+        assert_eq!(encode_star(&faint)[1] >> 24, 63);
+    }
+}
@@ -0,0 +1,290 @@
+//a Imports
+use wasm_bindgen::prelude::*;
+
+use star_catalog::Star;
+
+use crate::star_buffer;
+
+//a StarAttribute
+//tp StarAttribute
+/// An attribute of a star that can be placed in an interleaved vertex
+/// buffer
+///
+/// Every attribute occupies a multiple of four bytes, so that all of
+/// the attributes of a vertex are word aligned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StarAttribute {
+    /// Unit vector as three f32
+    VectorF32,
+    /// Unit vector as three f16, padded to 8 bytes
+    VectorF16,
+    /// Magnitude as an f32
+    Magnitude,
+    /// Color from the temperature as three f32
+    Rgb,
+    /// Color from the temperature as four u8 (alpha of 255)
+    RgbU8,
+    /// Blue-violet color index as an f32
+    BlueViolet,
+    /// Blue-violet color index as an f16, padded to 4 bytes
+    BlueVioletF16,
+    /// Right ascension and declination as two f32 (radians)
+    RaDe,
+    /// Distance in light years as an f32
+    Distance,
+    /// Temperature in Kelvin as an f32
+    Temperature,
+    /// Catalog id as a u32
+    Id,
+    /// The three u32 encoding used by the sky shader
+    SkyEncoded,
+}
+
+//ip StarAttribute
+impl StarAttribute {
+    //cp of_name
+    /// Get the attribute with the given name, if there is one
+    pub fn of_name(name: &str) -> Option<Self> {
+        use StarAttribute::*;
+        match name {
+            "xyz" | "xyz_f32" => Some(VectorF32),
+            "xyz_f16" => Some(VectorF16),
+            "magnitude" => Some(Magnitude),
+            "rgb" | "rgb_f32" => Some(Rgb),
+            "rgb_u8" => Some(RgbU8),
+            "bv" | "bv_f32" => Some(BlueViolet),
+            "bv_f16" => Some(BlueVioletF16),
+            "ra_de" => Some(RaDe),
+            "distance" => Some(Distance),
+            "temperature" => Some(Temperature),
+            "id" => Some(Id),
+            "sky" => Some(SkyEncoded),
+            _ => None,
+        }
+    }
+
+    //ap components
+    /// The number of components of the attribute, as used for a WebGL
+    /// vertex attribute pointer
+    pub fn components(self) -> usize {
+        use StarAttribute::*;
+        match self {
+            VectorF32 | VectorF16 | Rgb | SkyEncoded => 3,
+            RgbU8 => 4,
+            RaDe => 2,
+            _ => 1,
+        }
+    }
+
+    //ap size
+    /// The number of bytes the attribute occupies in a vertex
+    pub fn size(self) -> usize {
+        use StarAttribute::*;
+        match self {
+            VectorF32 | Rgb | SkyEncoded => 12,
+            VectorF16 | RaDe => 8,
+            _ => 4,
+        }
+    }
+
+    //ap gl_type
+    /// The WebGL type name of the components of the attribute
+    pub fn gl_type(self) -> &'static str {
+        use StarAttribute::*;
+        match self {
+            VectorF16 | BlueVioletF16 => "HALF_FLOAT",
+            RgbU8 => "UNSIGNED_BYTE",
+            Id | SkyEncoded => "UNSIGNED_INT",
+            _ => "FLOAT",
+        }
+    }
+
+    //mp write
+    /// Append the attribute for a star to a byte buffer
+    pub fn write(self, star: &Star, out: &mut Vec<u8>) {
+        use StarAttribute::*;
+        let v = star.vector();
+        match self {
+            VectorF32 => {
+                for c in [v[0], v[1], v[2]] {
+                    out.extend((c as f32).to_le_bytes());
+                }
+            }
+            VectorF16 => {
+                for c in [v[0], v[1], v[2], 0.] {
+                    out.extend(f16_bits_of_f32(c as f32).to_le_bytes());
+                }
+            }
+            Magnitude => out.extend(star.magnitude().to_le_bytes()),
+            Rgb => {
+                let (r, g, b) = Star::temp_to_rgb(star.temp());
+                for c in [r, g, b] {
+                    out.extend(c.to_le_bytes());
+                }
+            }
+            RgbU8 => {
+                let (r, g, b) = Star::temp_to_rgb(star.temp());
+                for c in [r, g, b] {
+                    out.push((c.clamp(0., 1.) * 255.0).round() as u8);
+                }
+                out.push(255);
+            }
+            BlueViolet => out.extend(star.bv().to_le_bytes()),
+            BlueVioletF16 => {
+                out.extend(f16_bits_of_f32(star.bv()).to_le_bytes());
+                out.extend([0, 0]);
+            }
+            RaDe => {
+                out.extend((star.ra() as f32).to_le_bytes());
+                out.extend((star.de() as f32).to_le_bytes());
+            }
+            Distance => out.extend(star.distance().to_le_bytes()),
+            Temperature => out.extend(star.temp().to_le_bytes()),
+            Id => out.extend((star.id() as u32).to_le_bytes()),
+            SkyEncoded => {
+                for w in star_buffer::encode_star(star) {
+                    out.extend(w.to_le_bytes());
+                }
+            }
+        }
+    }
+}
+
+//fp f16_bits_of_f32
+/// Convert an f32 to IEEE half-precision bits, rounding to nearest and
+/// saturating to infinity; subnormal results are flushed to zero
+pub fn f16_bits_of_f32(f: f32) -> u16 {
+    let bits = f.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7fffff;
+    if exp == 0xff {
+        // Infinity or NaN
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exp = exp - 127 + 15;
+    if exp >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exp <= 0 {
+        return sign;
+    }
+    let half = ((exp as u32) << 10) | (mantissa >> 13);
+    // Round to nearest, which may carry into the exponent
+    let round = (mantissa >> 12) & 1;
+    let half = (half + round).min(0x7c00);
+    sign | (half as u16)
+}
+
+//a WasmStarLayout
+//tp WasmStarLayout
+/// A description of an interleaved vertex buffer layout of star
+/// attributes
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct WasmStarLayout {
+    attributes: Vec<StarAttribute>,
+}
+
+//ip WasmStarLayout
+#[wasm_bindgen]
+impl WasmStarLayout {
+    //cp new
+    /// Create a new [WasmStarLayout] from a list of attribute names
+    ///
+    /// The names are: xyz (or xyz_f32), xyz_f16, magnitude, rgb (or
+    /// rgb_f32), rgb_u8, bv (or bv_f32), bv_f16, ra_de, distance,
+    /// temperature, id and sky
+    #[wasm_bindgen(constructor)]
+    pub fn new(names: Vec<String>) -> Result<WasmStarLayout, JsValue> {
+        let mut attributes = vec![];
+        for n in names.iter() {
+            let Some(a) = StarAttribute::of_name(n) else {
+                return Err(format!("Unknown star attribute '{n}'").into());
+            };
+            attributes.push(a);
+        }
+        Ok(Self { attributes })
+    }
+
+    //ap stride
+    /// The number of bytes per star
+    #[wasm_bindgen(getter)]
+    pub fn stride(&self) -> usize {
+        self.attributes.iter().map(|a| a.size()).sum()
+    }
+
+    //ap num_attributes
+    #[wasm_bindgen(getter)]
+    pub fn num_attributes(&self) -> usize {
+        self.attributes.len()
+    }
+
+    //mp offset
+    /// The byte offset of the n'th attribute within a vertex
+    pub fn offset(&self, n: usize) -> Option<usize> {
+        if n >= self.attributes.len() {
+            return None;
+        }
+        Some(self.attributes[0..n].iter().map(|a| a.size()).sum())
+    }
+
+    //mp components
+    /// The number of components of the n'th attribute
+    pub fn components(&self, n: usize) -> Option<usize> {
+        self.attributes.get(n).map(|a| a.components())
+    }
+
+    //mp gl_type
+    /// The WebGL type name (such as "FLOAT") of the n'th attribute
+    pub fn gl_type(&self, n: usize) -> Option<String> {
+        self.attributes.get(n).map(|a| a.gl_type().into())
+    }
+}
+
+//ip WasmStarLayout
+impl WasmStarLayout {
+    //mp write_star
+    /// Append the vertex for a star to a byte buffer
+    pub fn write_star(&self, star: &Star, out: &mut Vec<u8>) {
+        for a in self.attributes.iter() {
+            a.write(star, out);
+        }
+    }
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f16_exact() {
+        assert_eq!(f16_bits_of_f32(0.), 0x0000);
+        assert_eq!(f16_bits_of_f32(1.), 0x3c00);
+        assert_eq!(f16_bits_of_f32(0.5), 0x3800);
+        assert_eq!(f16_bits_of_f32(-2.), 0xc000);
+        assert_eq!(f16_bits_of_f32(65504.), 0x7bff);
+    }
+
+    #[test]
+    fn f16_special() {
+        assert_eq!(f16_bits_of_f32(1e6), 0x7c00);
+        assert_eq!(f16_bits_of_f32(-1e6), 0xfc00);
+        assert_eq!(f16_bits_of_f32(f32::INFINITY), 0x7c00);
+        assert_eq!(f16_bits_of_f32(f32::NAN) & 0x7e00, 0x7e00);
+        assert_eq!(f16_bits_of_f32(1e-6), 0x0000);
+        assert_eq!(f16_bits_of_f32(-1e-6), 0x8000);
+    }
+
+    #[test]
+    fn f16_rounding() {
+        // 1 + 2^-11 + 2^-12 rounds up to the next half above 1
+        assert_eq!(f16_bits_of_f32(1.000_732_4), 0x3c01);
+        // Just below 2 rounds up, carrying into the exponent
+        assert_eq!(f16_bits_of_f32(1.9998), 0x4000);
+        // Just above the largest half rounds up to infinity
+        assert_eq!(f16_bits_of_f32(65520.), 0x7c00);
+    }
+}
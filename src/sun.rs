@@ -0,0 +1,95 @@
+//a Documentation
+//! Low-precision position of the Sun
+//!
+//! This uses the simplified solar theory from Meeus, 'Astronomical
+//! Algorithms' chapter 25, which is accurate to about 0.01 degrees over
+//! several centuries around J2000
+
+//a Imports
+use crate::precession;
+use crate::rotation;
+
+//a Functions
+//fp sun_ecliptic
+/// The Sun's geometric ecliptic longitude (radians, mean equinox of
+/// date) and distance (AU) from the Earth at a Julian date
+pub fn sun_ecliptic(julian_date: f64) -> (f64, f64) {
+    let t = precession::centuries_since_j2000(julian_date);
+    let l0 = 280.46646 + t * (36000.76983 + t * 0.0003032);
+    let m = (357.52911 + t * (35999.05029 - t * 0.0001537)).to_radians();
+    let e = 0.016708634 - t * (0.000042037 + t * 0.0000001267);
+    let c = (1.914602 - t * (0.004817 + t * 0.000014)) * m.sin()
+        + (0.019993 - t * 0.000101) * (2. * m).sin()
+        + 0.000289 * (3. * m).sin();
+    let longitude = (l0 + c).to_radians();
+    let nu = m + c.to_radians();
+    let distance = 1.000001018 * (1. - e * e) / (1. + e * nu.cos());
+    (longitude.rem_euclid(std::f64::consts::TAU), distance)
+}
+
+//fp sun_vector_of_date
+/// The geometric geocentric vector to the Sun (AU), relative to the
+/// mean equator and equinox of date
+pub fn sun_vector_of_date(julian_date: f64) -> [f64; 3] {
+    let (lon, r) = sun_ecliptic(julian_date);
+    let ecl = [r * lon.cos(), r * lon.sin(), 0.];
+    let eps = precession::mean_obliquity(julian_date);
+    rotation::apply(&rotation::rot_x(-eps), &ecl)
+}
+
+//fp earth_velocity_of_date
+/// The velocity of the Earth around the Sun in AU per day, relative to
+/// the mean equator and equinox of date
+pub fn earth_velocity_of_date(julian_date: f64) -> [f64; 3] {
+    let dt = 0.05;
+    let before = sun_vector_of_date(julian_date - dt);
+    let after = sun_vector_of_date(julian_date + dt);
+    // The Earth moves opposite to the apparent motion of the Sun
+    rotation::scale(&rotation::sub(&before, &after), 0.5 / dt)
+}
+
+//fp sun_apparent_vector_of_date
+/// The apparent geocentric vector to the Sun (AU), including nutation
+/// and aberration, relative to the true equator and equinox of date
+pub fn sun_apparent_vector_of_date(julian_date: f64) -> [f64; 3] {
+    let (lon, r) = sun_ecliptic(julian_date);
+    let (dpsi, deps) = precession::nutation(julian_date);
+    // Annual aberration of the Sun is 20.4898 arcseconds at 1AU
+    let lon = lon + dpsi - (20.4898 / 3600.0_f64).to_radians() / r;
+    let eps = precession::mean_obliquity(julian_date) + deps;
+    let ecl = [r * lon.cos(), r * lon.sin(), 0.];
+    rotation::apply(&rotation::rot_x(-eps), &ecl)
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meeus_25a() {
+        // Meeus example 25.a: 1992 October 13 0h TD
+        let jd = 2448908.5;
+        let (lon, r) = sun_ecliptic(jd);
+        assert!(
+            (lon.to_degrees() - 199.90988).abs() < 1e-4,
+            "{}",
+            lon.to_degrees()
+        );
+        assert!((r - 0.99766).abs() < 1e-5, "{r}");
+
+        // The apparent right ascension and declination are 198.38083
+        // and -7.78507 degrees
+        let (ra, de) = rotation::ra_de_of_vec(&sun_apparent_vector_of_date(jd));
+        assert!(
+            (ra.to_degrees() - 198.38083).abs() < 1e-3,
+            "{}",
+            ra.to_degrees()
+        );
+        assert!(
+            (de.to_degrees() + 7.78507).abs() < 1e-3,
+            "{}",
+            de.to_degrees()
+        );
+    }
+}
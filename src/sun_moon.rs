@@ -0,0 +1,202 @@
+//a Documentation
+//! Apparent positions of the Sun and Moon for a time, geocentric or
+//! for an observer on the Earth, and the phase of the Moon
+//!
+//! Positions are held in km relative to the true equator and equinox
+//! of date, and are provided in the 'ECEF' frame of an observer - the
+//! J2000 catalog frame, or the true equator and equinox of date
+//!
+//! Topocentric positions differ from geocentric ones by the parallax
+//! of the observer's position on the Earth, which is up to a degree
+//! for the Moon and about 9 arcseconds for the Sun
+
+//a Imports
+use crate::moon;
+use crate::observer::Observer;
+use crate::precession;
+use crate::rotation::{self, Mat3};
+use crate::sun;
+use crate::time::Time;
+
+//a Constants
+/// Kilometres per astronomical unit
+pub const KM_PER_AU: f64 = 149_597_870.7;
+
+/// Mean radius of the Moon in km
+pub const MOON_RADIUS_KM: f64 = 1737.4;
+
+/// Radius of the Sun in km
+pub const SUN_RADIUS_KM: f64 = 696_000.0;
+
+//a SunMoon
+//tp SunMoon
+/// The Sun and Moon at a time, as seen from the center of the Earth or
+/// from an observer
+#[derive(Debug, Clone)]
+pub struct SunMoon {
+    /// Time of the positions
+    time: Time,
+    /// True if the ECEF frame is the true equator and equinox of date,
+    /// false for the J2000 frame
+    of_date: bool,
+    /// Matrix mapping true equator and equinox of date to ECEF
+    of_date_to_ecef: Mat3,
+    /// Apparent geocentric position of the Sun in km, ECEF
+    sun: [f64; 3],
+    /// Apparent geocentric position of the Moon in km, ECEF
+    moon: [f64; 3],
+    /// Position of the observer relative to the center of the Earth in
+    /// km, ECEF; zero for geocentric positions
+    observer: [f64; 3],
+}
+
+//ip SunMoon
+impl SunMoon {
+    //cp new
+    /// Create the geocentric Sun and Moon at a time, in the J2000 frame
+    /// or the true equator and equinox of date
+    pub fn new(time: Time, of_date: bool) -> Self {
+        let jd_tt = time.jd_tt();
+        let of_date_to_ecef = {
+            if of_date {
+                rotation::IDENTITY
+            } else {
+                rotation::transpose(&precession::precession_nutation_matrix(jd_tt))
+            }
+        };
+        let sun = rotation::scale(&sun::sun_apparent_vector_of_date(jd_tt), KM_PER_AU);
+        let sun = rotation::apply(&of_date_to_ecef, &sun);
+        let moon = rotation::apply(&of_date_to_ecef, &moon::moon_vector_of_date(jd_tt));
+        Self {
+            time,
+            of_date,
+            of_date_to_ecef,
+            sun,
+            moon,
+            observer: [0.; 3],
+        }
+    }
+
+    //cp of_observer
+    /// Create the Sun and Moon as seen by an observer at the observer's
+    /// time, in the observer's ECEF frame
+    pub fn of_observer(observer: &Observer) -> Self {
+        let mut s = Self::new(*observer.time(), observer.of_date());
+        s.observer = rotation::scale(&observer.geocentric_position(), 0.001);
+        s
+    }
+
+    //ap time
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+
+    //ap of_date
+    pub fn of_date(&self) -> bool {
+        self.of_date
+    }
+
+    //ap of_date_to_ecef
+    /// The matrix mapping the true equator and equinox of date to the
+    /// ECEF frame of the positions
+    pub fn of_date_to_ecef(&self) -> &Mat3 {
+        &self.of_date_to_ecef
+    }
+
+    //ap sun
+    /// The geocentric position of the Sun in km
+    pub fn sun(&self) -> &[f64; 3] {
+        &self.sun
+    }
+
+    //ap moon
+    /// The geocentric position of the Moon in km
+    pub fn moon(&self) -> &[f64; 3] {
+        &self.moon
+    }
+
+    //mp topocentric_sun
+    /// The position of the Sun in km relative to the observer
+    pub fn topocentric_sun(&self) -> [f64; 3] {
+        rotation::sub(&self.sun, &self.observer)
+    }
+
+    //mp topocentric_moon
+    /// The position of the Moon in km relative to the observer
+    pub fn topocentric_moon(&self) -> [f64; 3] {
+        rotation::sub(&self.moon, &self.observer)
+    }
+
+    //mp sun_semi_diameter
+    /// The angular radius of the Sun as seen by the observer in radians
+    pub fn sun_semi_diameter(&self) -> f64 {
+        (SUN_RADIUS_KM / rotation::length(&self.topocentric_sun())).asin()
+    }
+
+    //mp moon_semi_diameter
+    /// The angular radius of the Moon as seen by the observer in radians
+    pub fn moon_semi_diameter(&self) -> f64 {
+        (MOON_RADIUS_KM / rotation::length(&self.topocentric_moon())).asin()
+    }
+
+    //mp moon_elongation
+    /// The geocentric angle between the Sun and Moon in radians
+    pub fn moon_elongation(&self) -> f64 {
+        let c = rotation::dot(&self.sun, &self.moon)
+            / (rotation::length(&self.sun) * rotation::length(&self.moon));
+        c.clamp(-1., 1.).acos()
+    }
+
+    //mp moon_phase_angle
+    /// The phase angle of the Moon in radians - the angle at the Moon
+    /// between the Sun and the Earth; zero at full Moon
+    pub fn moon_phase_angle(&self) -> f64 {
+        let psi = self.moon_elongation();
+        let r = rotation::length(&self.sun);
+        let d = rotation::length(&self.moon);
+        (r * psi.sin()).atan2(d - r * psi.cos())
+    }
+
+    //mp moon_illuminated_fraction
+    /// The fraction of the Moon's disc that is illuminated, 0 to 1
+    pub fn moon_illuminated_fraction(&self) -> f64 {
+        (1. + self.moon_phase_angle().cos()) / 2.
+    }
+
+    //mp moon_phase
+    /// The phase of the Moon as a fraction of the lunation, 0 to 1: zero
+    /// at new Moon, 0.25 at first quarter, 0.5 at full Moon and 0.75 at
+    /// last quarter
+    ///
+    /// This is the difference in ecliptic longitude of the Moon and Sun
+    pub fn moon_phase(&self) -> f64 {
+        let jd_tt = self.time.jd_tt();
+        let (sun_lon, _) = sun::sun_ecliptic(jd_tt);
+        let (moon_lon, _, _) = moon::moon_ecliptic(jd_tt);
+        (moon_lon - sun_lon).rem_euclid(std::f64::consts::TAU) / std::f64::consts::TAU
+    }
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn illuminated_fraction() {
+        // Meeus example 48.a: 1992 April 12 0h TD (TT - UTC was 58.184
+        // seconds), when the phase angle is 69.0756 degrees and the
+        // illuminated fraction 0.6786
+        let time = Time::of_julian_date(2448724.5 - 58.184 / 86400.);
+        assert!((time.jd_tt() - 2448724.5).abs() < 1e-9);
+        for of_date in [false, true] {
+            let sun_moon = SunMoon::new(time, of_date);
+            let i = sun_moon.moon_phase_angle().to_degrees();
+            let k = sun_moon.moon_illuminated_fraction();
+            assert!((i - 69.0756).abs() < 0.005, "{i}");
+            assert!((k - 0.6786).abs() < 2e-4, "{k}");
+            // Between first quarter and full
+            assert!((0.25..0.5).contains(&sun_moon.moon_phase()));
+        }
+    }
+}
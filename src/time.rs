@@ -0,0 +1,301 @@
+//a Documentation
+//! Time scales: Unix time, Julian dates, UTC, TAI, TT and UT1, and
+//! sidereal time
+//!
+//! Unix time is taken as UTC seconds since 1970-01-01 without leap
+//! seconds, as in JavaScript; a Unix time therefore maps directly to a
+//! UTC Julian date. TT is found from UTC using a table of leap seconds
+//! (TAI-UTC), and UT1 from UTC using a caller-supplied DUT1 (UT1-UTC,
+//! always less than 0.9 seconds)
+//!
+//! Sidereal time uses the IAU 2006 relation of Greenwich mean sidereal
+//! time to the Earth rotation angle
+
+//a Imports
+use crate::precession;
+
+//a Constants
+/// Julian date of the Unix epoch, 1970-01-01T00:00:00
+pub const JD_UNIX_EPOCH: f64 = 2440587.5;
+
+/// Julian date of J2000.0
+pub const JD_J2000: f64 = 2451545.0;
+
+/// Seconds per day
+pub const SECONDS_PER_DAY: f64 = 86400.0;
+
+/// TT - TAI in seconds
+pub const TT_MINUS_TAI: f64 = 32.184;
+
+/// Radians per arcsecond
+const RAD_PER_ARCSEC: f64 = std::f64::consts::PI / (180.0 * 3600.0);
+
+/// Leap seconds as the (year, month) at whose start TAI-UTC took a new
+/// value; before 1972 TAI-UTC is taken as 10 seconds
+const LEAP_SECONDS: [(i32, u32, f64); 28] = [
+    (1972, 1, 10.),
+    (1972, 7, 11.),
+    (1973, 1, 12.),
+    (1974, 1, 13.),
+    (1975, 1, 14.),
+    (1976, 1, 15.),
+    (1977, 1, 16.),
+    (1978, 1, 17.),
+    (1979, 1, 18.),
+    (1980, 1, 19.),
+    (1981, 7, 20.),
+    (1982, 7, 21.),
+    (1983, 7, 22.),
+    (1985, 7, 23.),
+    (1988, 1, 24.),
+    (1990, 1, 25.),
+    (1991, 1, 26.),
+    (1992, 7, 27.),
+    (1993, 7, 28.),
+    (1994, 7, 29.),
+    (1996, 1, 30.),
+    (1997, 7, 31.),
+    (1999, 1, 32.),
+    (2006, 1, 33.),
+    (2009, 1, 34.),
+    (2012, 7, 35.),
+    (2015, 7, 36.),
+    (2017, 1, 37.),
+];
+
+//a Calendar functions
+//fp days_from_civil
+/// The number of days from 1970-01-01 to a Gregorian calendar date
+pub fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year } as i64;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let mp = if m > 2 { m - 3 } else { m + 9 };
+    let doy = (153 * mp + 2) / 5 + (day as i64) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+//a Time scale functions
+//fp jd_of_unix_time
+/// The (UTC) Julian date of a Unix time in seconds
+pub fn jd_of_unix_time(unix_time: f64) -> f64 {
+    unix_time / SECONDS_PER_DAY + JD_UNIX_EPOCH
+}
+
+//fp unix_time_of_jd
+/// The Unix time in seconds of a (UTC) Julian date
+pub fn unix_time_of_jd(julian_date: f64) -> f64 {
+    (julian_date - JD_UNIX_EPOCH) * SECONDS_PER_DAY
+}
+
+//fp tai_minus_utc
+/// TAI-UTC in seconds (the accumulated leap seconds) at a Unix time
+pub fn tai_minus_utc(unix_time: f64) -> f64 {
+    let mut offset = 10.;
+    for (year, month, tai_utc) in LEAP_SECONDS.iter() {
+        let start = days_from_civil(*year, *month, 1) as f64 * SECONDS_PER_DAY;
+        if unix_time < start {
+            break;
+        }
+        offset = *tai_utc;
+    }
+    offset
+}
+
+//fp tt_minus_utc
+/// TT-UTC in seconds at a Unix time
+pub fn tt_minus_utc(unix_time: f64) -> f64 {
+    tai_minus_utc(unix_time) + TT_MINUS_TAI
+}
+
+//fp jd_tt_of_unix_time
+/// The Julian date in Terrestrial Time of a Unix time
+pub fn jd_tt_of_unix_time(unix_time: f64) -> f64 {
+    jd_of_unix_time(unix_time + tt_minus_utc(unix_time))
+}
+
+//a Sidereal time functions
+//fp earth_rotation_angle
+/// The Earth rotation angle (radians, 0 to 2*PI) for a UT1 Julian date
+pub fn earth_rotation_angle(jd_ut1: f64) -> f64 {
+    let du = jd_ut1 - JD_J2000;
+    let turns = 0.7790572732640 + 0.00273781191135448 * du + du.fract();
+    turns.rem_euclid(1.0) * std::f64::consts::TAU
+}
+
+//fp gmst
+/// Greenwich mean sidereal time (radians, 0 to 2*PI) for UT1 and TT
+/// Julian dates
+pub fn gmst(jd_ut1: f64, jd_tt: f64) -> f64 {
+    let t = precession::centuries_since_j2000(jd_tt);
+    let poly = 0.014506
+        + t * (4612.156534
+            + t * (1.3915817 + t * (-0.00000044 + t * (-0.000029956 + t * (-0.0000000368)))));
+    (earth_rotation_angle(jd_ut1) + poly * RAD_PER_ARCSEC).rem_euclid(std::f64::consts::TAU)
+}
+
+//fp gast
+/// Greenwich apparent sidereal time (radians, 0 to 2*PI) for UT1 and
+/// TT Julian dates
+pub fn gast(jd_ut1: f64, jd_tt: f64) -> f64 {
+    (gmst(jd_ut1, jd_tt) + precession::equation_of_the_equinoxes(jd_tt))
+        .rem_euclid(std::f64::consts::TAU)
+}
+
+//a Time
+//tp Time
+/// An instant in time, held as a Unix time with a DUT1 to give UT1
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Time {
+    /// Unix time in seconds (UTC)
+    unix_time: f64,
+    /// UT1-UTC in seconds
+    dut1: f64,
+}
+
+//ip Time
+impl Time {
+    //cp of_unix_time
+    pub fn of_unix_time(unix_time: f64) -> Self {
+        Self {
+            unix_time,
+            dut1: 0.,
+        }
+    }
+
+    //cp of_julian_date
+    /// Create from a UTC Julian date
+    pub fn of_julian_date(julian_date: f64) -> Self {
+        Self::of_unix_time(unix_time_of_jd(julian_date))
+    }
+
+    //bp with_dut1
+    /// Set the UT1-UTC in seconds
+    pub fn with_dut1(mut self, dut1: f64) -> Self {
+        self.dut1 = dut1;
+        self
+    }
+
+    //mp add_seconds
+    /// Get the time a number of seconds later
+    pub fn add_seconds(&self, seconds: f64) -> Self {
+        Self {
+            unix_time: self.unix_time + seconds,
+            dut1: self.dut1,
+        }
+    }
+
+    //ap unix_time
+    pub fn unix_time(&self) -> f64 {
+        self.unix_time
+    }
+
+    //ap dut1
+    pub fn dut1(&self) -> f64 {
+        self.dut1
+    }
+
+    //ap jd_utc
+    pub fn jd_utc(&self) -> f64 {
+        jd_of_unix_time(self.unix_time)
+    }
+
+    //ap jd_ut1
+    pub fn jd_ut1(&self) -> f64 {
+        jd_of_unix_time(self.unix_time + self.dut1)
+    }
+
+    //ap jd_tt
+    pub fn jd_tt(&self) -> f64 {
+        jd_tt_of_unix_time(self.unix_time)
+    }
+
+    //ap gmst
+    /// Greenwich mean sidereal time in radians
+    pub fn gmst(&self) -> f64 {
+        gmst(self.jd_ut1(), self.jd_tt())
+    }
+
+    //ap gast
+    /// Greenwich apparent sidereal time in radians
+    pub fn gast(&self) -> f64 {
+        gast(self.jd_ut1(), self.jd_tt())
+    }
+
+    //mp local_sidereal_time
+    /// Local apparent sidereal time in radians for an east longitude in
+    /// radians
+    pub fn local_sidereal_time(&self, longitude: f64) -> f64 {
+        (self.gast() + longitude).rem_euclid(std::f64::consts::TAU)
+    }
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Radians per second of time
+    const RAD_PER_SECOND: f64 = std::f64::consts::TAU / SECONDS_PER_DAY;
+
+    #[test]
+    fn calendar() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 1, 1), 10957);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(
+            days_from_civil(2024, 3, 1) - days_from_civil(2024, 2, 28),
+            2
+        );
+    }
+
+    #[test]
+    fn leap_seconds() {
+        let new_year_2017 = days_from_civil(2017, 1, 1) as f64 * SECONDS_PER_DAY;
+        assert_eq!(tai_minus_utc(new_year_2017 - 1.), 36.);
+        assert_eq!(tai_minus_utc(new_year_2017), 37.);
+        assert_eq!(tt_minus_utc(new_year_2017), 69.184);
+        assert_eq!(tai_minus_utc(0.), 10.);
+    }
+
+    #[test]
+    fn delta_t() {
+        // With a DUT1 of -0.1772 seconds (IERS Bulletin A), delta T =
+        // TT - UT1 was 69.36 seconds at the start of 2020
+        let unix_time = days_from_civil(2020, 1, 1) as f64 * SECONDS_PER_DAY;
+        let time = Time::of_unix_time(unix_time).with_dut1(-0.1772);
+        let delta_t = (time.jd_tt() - time.jd_ut1()) * SECONDS_PER_DAY;
+        assert!((delta_t - 69.3612).abs() < 1e-4, "{delta_t}");
+    }
+
+    #[test]
+    fn earth_rotation_angle_at_j2000() {
+        let era = earth_rotation_angle(JD_J2000);
+        assert!((era.to_degrees() - 280.46061837504).abs() < 1e-9);
+        // One UT1 day later the Earth has turned slightly more than once
+        let era_1 = earth_rotation_angle(JD_J2000 + 1.);
+        let turn = (era_1 - era).rem_euclid(std::f64::consts::TAU);
+        assert!((turn - 0.00273781191135448 * std::f64::consts::TAU).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sidereal_time() {
+        // GMST at 2000 January 1 12h UT1 is 18h 41m 50.548s
+        let expected = (18. * 3600. + 41. * 60. + 50.548) * RAD_PER_SECOND;
+        let error =
+            (gmst(JD_J2000, JD_J2000 + 64.184 / SECONDS_PER_DAY) - expected) / RAD_PER_SECOND;
+        assert!(error.abs() < 0.01, "{error}");
+        // Meeus, Astronomical Algorithms, example 12.a: GMST at 1987
+        // April 10 0h UT is 13h 10m 46.3668s
+        let jd = 2446895.5;
+        let expected = (13. * 3600. + 10. * 60. + 46.3668) * RAD_PER_SECOND;
+        let error = (gmst(jd, jd + 55.184 / SECONDS_PER_DAY) - expected) / RAD_PER_SECOND;
+        assert!(error.abs() < 0.01, "{error}");
+        // Meeus example 12.a: GAST is 13h 10m 46.1351s
+        let expected = (13. * 3600. + 10. * 60. + 46.1351) * RAD_PER_SECOND;
+        let error = (gast(jd, jd + 55.184 / SECONDS_PER_DAY) - expected) / RAD_PER_SECOND;
+        assert!(error.abs() < 0.01, "{error}");
+    }
+}
@@ -0,0 +1,324 @@
+//a Documentation
+//! NORAD two-line element sets
+//!
+//! A set is two 69 column lines, optionally preceded by a line with
+//! the name of the satellite (the 'three line' form). Line checksums
+//! are verified when present, and catalog numbers of 100000 and over
+//! may be in the Alpha-5 form (a letter for the leading digits)
+//!
+//! Text of many sets can be parsed, with an error for each malformed
+//! set; parsing then continues at the next line that starts a set
+
+//a Imports
+use crate::time::{self, SECONDS_PER_DAY};
+
+//a Tle
+//tp Tle
+/// The mean elements of a satellite from a two-line element set
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tle {
+    /// Name of the satellite, if given; else the catalog number
+    pub name: String,
+    /// NORAD catalog number
+    pub catalog_number: u32,
+    /// Epoch as a Unix time in seconds (UTC)
+    pub epoch: f64,
+    /// Drag term (B*) in inverse Earth radii
+    pub bstar: f64,
+    /// Inclination in radians
+    pub inclination: f64,
+    /// Right ascension of the ascending node in radians
+    pub ascending_node: f64,
+    /// Eccentricity
+    pub eccentricity: f64,
+    /// Argument of perigee in radians
+    pub arg_perigee: f64,
+    /// Mean anomaly in radians
+    pub mean_anomaly: f64,
+    /// Mean motion in radians per minute
+    pub mean_motion: f64,
+}
+
+//fi column
+/// Get the trimmed text of 1-based inclusive columns of a line
+fn column(text: &str, first: usize, last: usize) -> &str {
+    text.get(first - 1..last.min(text.len()))
+        .unwrap_or("")
+        .trim()
+}
+
+//fi parse_column
+fn parse_column<T: std::str::FromStr>(
+    line: usize,
+    text: &str,
+    first: usize,
+    last: usize,
+    what: &str,
+) -> Result<T, String> {
+    let f = column(text, first, last);
+    f.parse::<T>()
+        .map_err(|_| format!("line {line}: could not parse {what} from '{f}'"))
+}
+
+//fi parse_exponent
+/// Parse a TLE field with an implied leading decimal point and an
+/// exponent, such as ' 28098-4' for 0.28098e-4
+fn parse_exponent(
+    line: usize,
+    text: &str,
+    first: usize,
+    last: usize,
+    what: &str,
+) -> Result<f64, String> {
+    let f = column(text, first, last);
+    let err = || format!("line {line}: could not parse {what} from '{f}'");
+    if f.len() < 2 {
+        return f.parse::<f64>().map_err(|_| err());
+    }
+    let (mantissa, exponent) = f.split_at(f.len() - 2);
+    let (sign, digits) = match mantissa.strip_prefix('-') {
+        Some(d) => ("-", d),
+        None => ("", mantissa.strip_prefix('+').unwrap_or(mantissa)),
+    };
+    format!("{sign}0.{digits}e{exponent}")
+        .parse::<f64>()
+        .map_err(|_| err())
+}
+
+//fi parse_catalog_number
+/// Parse the catalog number in columns 3 to 7 of a line; the Alpha-5
+/// form has a letter (A to Z, skipping I and O) for 10 to 33 in place
+/// of the first digit
+fn parse_catalog_number(line: usize, text: &str) -> Result<u32, String> {
+    let f = column(text, 3, 7);
+    let err = || format!("line {line}: could not parse catalog number from '{f}'");
+    let Some(first) = f.chars().next() else {
+        return Err(err());
+    };
+    if !first.is_ascii_uppercase() {
+        return f.parse::<u32>().map_err(|_| err());
+    }
+    let leading = match first {
+        'I' | 'O' => return Err(err()),
+        'A'..='H' => first as u32 - 'A' as u32 + 10,
+        'J'..='N' => first as u32 - 'A' as u32 + 9,
+        _ => first as u32 - 'A' as u32 + 8,
+    };
+    let rest = &f[1..];
+    if rest.len() != 4 || !rest.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(err());
+    }
+    Ok(leading * 10000 + rest.parse::<u32>().map_err(|_| err())?)
+}
+
+//fi checksum
+/// Verify the checksum in column 69 of a line, if present
+fn checksum(line: usize, text: &str) -> Result<(), String> {
+    let Some(c) = text.as_bytes().get(68) else {
+        return Ok(());
+    };
+    if !c.is_ascii_digit() {
+        return Ok(());
+    }
+    let sum: u32 = text.as_bytes()[0..68]
+        .iter()
+        .map(|b| match b {
+            b'0'..=b'9' => (b - b'0') as u32,
+            b'-' => 1,
+            _ => 0,
+        })
+        .sum();
+    if sum % 10 != (c - b'0') as u32 {
+        Err(format!("line {line}: checksum mismatch"))
+    } else {
+        Ok(())
+    }
+}
+
+//ip Tle
+impl Tle {
+    //cp parse_lines
+    /// Parse the two lines of an element set, with an optional name;
+    /// 'line' is the line number of the first line, for errors
+    pub fn parse_lines(
+        name: Option<&str>,
+        line: usize,
+        line1: &str,
+        line2: &str,
+    ) -> Result<Self, String> {
+        let line1 = line1.trim_end();
+        let line2 = line2.trim_end();
+        if !line1.is_ascii() || !line1.starts_with("1 ") || line1.len() < 64 {
+            return Err(format!("line {line}: not the first line of a TLE"));
+        }
+        if !line2.is_ascii() || !line2.starts_with("2 ") || line2.len() < 63 {
+            return Err(format!("line {}: not the second line of a TLE", line + 1));
+        }
+        checksum(line, line1)?;
+        checksum(line + 1, line2)?;
+        let catalog_number = parse_catalog_number(line, line1)?;
+        let catalog_number_2 = parse_catalog_number(line + 1, line2)?;
+        if catalog_number != catalog_number_2 {
+            return Err(format!(
+                "line {}: catalog number {catalog_number_2} does not match {catalog_number}",
+                line + 1
+            ));
+        }
+        let year: i32 = parse_column(line, line1, 19, 20, "epoch year")?;
+        let day: f64 = parse_column(line, line1, 21, 32, "epoch day")?;
+        let year = if year < 57 { year + 2000 } else { year + 1900 };
+        let epoch = (time::days_from_civil(year, 1, 1) as f64 + day - 1.) * SECONDS_PER_DAY;
+        let bstar = parse_exponent(line, line1, 54, 61, "drag term")?;
+
+        let l = line + 1;
+        let inclination: f64 = parse_column(l, line2, 9, 16, "inclination")?;
+        let ascending_node: f64 = parse_column(l, line2, 18, 25, "ascending node")?;
+        let eccentricity: f64 =
+            parse_column(l, line2, 27, 33, "eccentricity").map(|e: u32| e as f64 * 1.0e-7)?;
+        let arg_perigee: f64 = parse_column(l, line2, 35, 42, "argument of perigee")?;
+        let mean_anomaly: f64 = parse_column(l, line2, 44, 51, "mean anomaly")?;
+        let mean_motion: f64 = parse_column(l, line2, 53, 63, "mean motion")?;
+        if mean_motion <= 0. {
+            return Err(format!("line {l}: mean motion must be positive"));
+        }
+
+        let name = name
+            .map(|n| n.trim().trim_start_matches("0 ").to_string())
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| catalog_number.to_string());
+        Ok(Self {
+            name,
+            catalog_number,
+            epoch,
+            bstar,
+            inclination: inclination.to_radians(),
+            ascending_node: ascending_node.to_radians(),
+            eccentricity,
+            arg_perigee: arg_perigee.to_radians(),
+            mean_anomaly: mean_anomaly.to_radians(),
+            mean_motion: mean_motion * std::f64::consts::TAU / 1440.0,
+        })
+    }
+}
+
+//fp parse
+/// Parse text of two or three line element sets; returns the good sets
+/// and an error for each malformed set
+pub fn parse(text: &str) -> (Vec<Tle>, Vec<String>) {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut tles = vec![];
+    let mut errors = vec![];
+    let mut name = None;
+    let mut n = 0;
+    while n < lines.len() {
+        let l = lines[n];
+        if l.trim().is_empty() {
+            n += 1;
+            continue;
+        }
+        if l.starts_with("2 ") {
+            errors.push(format!(
+                "line {}: second line of TLE without a first",
+                n + 1
+            ));
+            name = None;
+            n += 1;
+            continue;
+        }
+        if !l.starts_with("1 ") {
+            name = Some(l);
+            n += 1;
+            continue;
+        }
+        let Some(line2) = lines.get(n + 1) else {
+            errors.push(format!("line {}: missing second line of TLE", n + 1));
+            break;
+        };
+        match Tle::parse_lines(name.take(), n + 1, l, line2) {
+            Ok(tle) => {
+                tles.push(tle);
+                n += 2;
+            }
+            Err(e) => {
+                // Skip the second line of the set too, so that it is
+                // not taken as the name of the next set
+                errors.push(e);
+                n += if line2.starts_with("2 ") { 2 } else { 1 };
+            }
+        }
+    }
+    (tles, errors)
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISS_1: &str = "1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927";
+    const ISS_2: &str = "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537";
+
+    #[test]
+    fn iss() {
+        let (tles, errors) = parse(&format!("ISS (ZARYA)\n{ISS_1}\n{ISS_2}\n"));
+        assert!(errors.is_empty(), "{errors:?}");
+        let tle = &tles[0];
+        assert_eq!(tle.name, "ISS (ZARYA)");
+        assert_eq!(tle.catalog_number, 25544);
+        assert!((tle.bstar + 0.11606e-4).abs() < 1e-12);
+        assert!((tle.eccentricity - 0.0006703).abs() < 1e-12);
+        assert!((tle.inclination.to_degrees() - 51.6416).abs() < 1e-9);
+        assert!((tle.mean_motion * 1440. / std::f64::consts::TAU - 15.72125391).abs() < 1e-9);
+        // 2008 day 264.51782528 is September 20 12:25:40.1 UTC
+        let epoch = time::days_from_civil(2008, 9, 20) as f64 * SECONDS_PER_DAY
+            + 12. * 3600.
+            + 25. * 60.
+            + 40.104;
+        assert!((tle.epoch - epoch).abs() < 1e-3);
+    }
+
+    #[test]
+    fn bad_sets() {
+        // A bad checksum, then an unnamed and a named good set
+        let bad_1 = ISS_1.replace("2927", "2928");
+        let text = format!("BAD\n{bad_1}\n{ISS_2}\n{ISS_1}\n{ISS_2}\nISS\n{ISS_1}\n{ISS_2}\n");
+        let (tles, errors) = parse(&text);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].starts_with("line 2:"), "{errors:?}");
+        assert_eq!(tles.len(), 2);
+        assert_eq!(tles[0].name, "25544");
+        assert_eq!(tles[1].name, "ISS");
+
+        // A second line on its own is never a name
+        let (tles, errors) = parse(&format!("{ISS_2}\n{ISS_1}\n{ISS_2}\n"));
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert_eq!(tles[0].name, "25544");
+
+        let (tles, errors) = parse(&format!("ISS\n{ISS_1}\n"));
+        assert!(tles.is_empty());
+        assert_eq!(errors.len(), 1, "{errors:?}");
+    }
+
+    #[test]
+    fn alpha_5() {
+        for (f, n) in [
+            ("1 25544U", 25544),
+            ("1 A0000U", 100000),
+            ("1 E8493U", 148493),
+            ("1 J0001U", 180001),
+            ("1 P1234U", 231234),
+            ("1 Z9999U", 339999),
+        ] {
+            assert_eq!(parse_catalog_number(1, f), Ok(n), "{f}");
+        }
+        for f in ["1 I0000U", "1 O0000U", "1 a0000U", "1 A00X0U", "1      U"] {
+            assert!(parse_catalog_number(1, f).is_err(), "{f}");
+        }
+        // Alpha-5 sets (without checksums)
+        let line1 = ISS_1.replace("25544", "E8493");
+        let line2 = ISS_2.replace("25544", "E8493");
+        let tle = Tle::parse_lines(None, 1, &line1[..68], &line2[..68]).unwrap();
+        assert_eq!(tle.catalog_number, 148493);
+        assert_eq!(tle.name, "148493");
+    }
+}
@@ -0,0 +1,323 @@
+//a Documentation
+//! Twilight, moonrise and moonset, and dark sky intervals for the
+//! night following a date at an observer's location
+//!
+//! The night runs from local noon on the date (using the mean solar
+//! time of the observer's longitude) to local noon on the next day.
+//! The true altitudes of the Sun and Moon are sampled through the
+//! night and the times of crossing each altitude found by bisection,
+//! which copes with the Moon's motion and with high latitudes where
+//! some twilights do not occur
+//!
+//! The sky is fully dark when the Sun is below astronomical twilight
+//! and the Moon is below the horizon
+
+//a Imports
+use crate::observer::Observer;
+use crate::sun_moon::SunMoon;
+use crate::time::{self, Time};
+
+//a Constants
+/// True altitude of the center of the Sun at sunrise and sunset,
+/// allowing for refraction and its semidiameter: -50 arcminutes
+pub const SUNRISE_ALTITUDE: f64 = -50.0 / 60.0 * std::f64::consts::PI / 180.0;
+
+/// True altitude of the Sun at the boundary of civil twilight
+pub const CIVIL_ALTITUDE: f64 = -6.0 * std::f64::consts::PI / 180.0;
+
+/// True altitude of the Sun at the boundary of nautical twilight
+pub const NAUTICAL_ALTITUDE: f64 = -12.0 * std::f64::consts::PI / 180.0;
+
+/// True altitude of the Sun at the boundary of astronomical twilight
+pub const ASTRONOMICAL_ALTITUDE: f64 = -18.0 * std::f64::consts::PI / 180.0;
+
+/// True altitude of the upper limb of the Moon at moonrise and
+/// moonset, allowing for refraction: -34 arcminutes
+pub const MOONRISE_ALTITUDE: f64 = -34.0 / 60.0 * std::f64::consts::PI / 180.0;
+
+/// Interval in seconds between samples of the altitudes
+const SAMPLE_INTERVAL: f64 = 600.0;
+
+/// Number of bisections of a sample interval to find a crossing, to
+/// better than a second
+const BISECTIONS: usize = 12;
+
+//a Crossing
+//tp Crossing
+/// A time at which an altitude is crossed, rising or setting
+#[derive(Debug, Clone, Copy)]
+struct Crossing {
+    time: f64,
+    rising: bool,
+}
+
+//a Twilight
+//tp Twilight
+/// Twilight boundaries, moonrise and moonset and dark intervals for a
+/// night, as Unix times in seconds
+///
+/// Each event is the first in the night, and is None if it does not
+/// occur (such as astronomical twilight in a high latitude summer)
+#[derive(Debug, Clone, Default)]
+pub struct Twilight {
+    /// Start of the night (local noon)
+    pub start: f64,
+    /// End of the night (local noon the next day)
+    pub end: f64,
+    pub sunset: Option<f64>,
+    pub civil_dusk: Option<f64>,
+    pub nautical_dusk: Option<f64>,
+    pub astronomical_dusk: Option<f64>,
+    pub astronomical_dawn: Option<f64>,
+    pub nautical_dawn: Option<f64>,
+    pub civil_dawn: Option<f64>,
+    pub sunrise: Option<f64>,
+    pub moonrise: Option<f64>,
+    pub moonset: Option<f64>,
+    /// Intervals (start, end) when the Sun is below astronomical
+    /// twilight and the Moon is below the horizon
+    pub dark: Vec<(f64, f64)>,
+}
+
+//ip Twilight
+impl Twilight {
+    //cp of_date
+    /// Find the twilight for the night following a Gregorian calendar
+    /// date (month 1 to 12) at the observer's location
+    ///
+    /// The observer's time is ignored, other than its DUT1; refraction
+    /// is always included (using the standard allowances)
+    pub fn of_date(observer: &Observer, year: i32, month: u32, day: u32) -> Self {
+        let days = time::days_from_civil(year, month, day) as f64;
+        let noon =
+            (days + 0.5 - observer.longitude() / std::f64::consts::TAU) * time::SECONDS_PER_DAY;
+        Self::of_interval(observer, noon, noon + time::SECONDS_PER_DAY)
+    }
+
+    //cp of_interval
+    /// Find the twilight events between two Unix times at the
+    /// observer's location
+    pub fn of_interval(observer: &Observer, start: f64, end: f64) -> Self {
+        let mut observer = observer.clone();
+        observer.set_refraction(None);
+        let dut1 = observer.time().dut1();
+        let altitudes_at = |t: f64| {
+            let mut observer = observer.clone();
+            observer.set_time(Time::of_unix_time(t).with_dut1(dut1));
+            altitudes(&observer)
+        };
+        let sun_altitude = |t: f64| altitudes_at(t).0;
+        let moon_altitude = |t: f64| altitudes_at(t).1;
+
+        let n = ((end - start) / SAMPLE_INTERVAL).ceil().max(1.) as usize;
+        let times: Vec<f64> = (0..=n)
+            .map(|i| start + (end - start) * (i as f64) / (n as f64))
+            .collect();
+        let samples: Vec<(f64, f64)> = times.iter().map(|t| altitudes_at(*t)).collect();
+        let sun_samples: Vec<f64> = samples.iter().map(|s| s.0).collect();
+        let moon_samples: Vec<f64> = samples.iter().map(|s| s.1).collect();
+
+        let sun = |h| crossings(&times, &sun_samples, h, &sun_altitude);
+        let sunrise_sunset = sun(SUNRISE_ALTITUDE);
+        let civil = sun(CIVIL_ALTITUDE);
+        let nautical = sun(NAUTICAL_ALTITUDE);
+        let astronomical = sun(ASTRONOMICAL_ALTITUDE);
+        let moon = crossings(&times, &moon_samples, MOONRISE_ALTITUDE, &moon_altitude);
+
+        let is_dark = |t: f64| {
+            let (s, m) = altitudes_at(t);
+            s < ASTRONOMICAL_ALTITUDE && m < MOONRISE_ALTITUDE
+        };
+        let mut boundaries = vec![start, end];
+        boundaries.extend(astronomical.iter().map(|c| c.time));
+        boundaries.extend(moon.iter().map(|c| c.time));
+        boundaries.sort_by(f64::total_cmp);
+        let mut dark: Vec<(f64, f64)> = vec![];
+        for w in boundaries.windows(2) {
+            if w[1] <= w[0] || !is_dark((w[0] + w[1]) / 2.) {
+                continue;
+            }
+            match dark.last_mut() {
+                Some(last) if last.1 == w[0] => last.1 = w[1],
+                _ => dark.push((w[0], w[1])),
+            }
+        }
+
+        Self {
+            start,
+            end,
+            sunset: first(&sunrise_sunset, false),
+            civil_dusk: first(&civil, false),
+            nautical_dusk: first(&nautical, false),
+            astronomical_dusk: first(&astronomical, false),
+            astronomical_dawn: first(&astronomical, true),
+            nautical_dawn: first(&nautical, true),
+            civil_dawn: first(&civil, true),
+            sunrise: first(&sunrise_sunset, true),
+            moonrise: first(&moon, true),
+            moonset: first(&moon, false),
+            dark,
+        }
+    }
+}
+
+//a Functions
+//fi altitudes
+/// The true altitudes (radians) of the center of the Sun and the upper
+/// limb of the Moon for an observer
+fn altitudes(observer: &Observer) -> (f64, f64) {
+    let sun_moon = SunMoon::of_observer(observer);
+    let (sun, _) = observer.alt_az_of_vector(&sun_moon.topocentric_sun());
+    let (moon, _) = observer.alt_az_of_vector(&sun_moon.topocentric_moon());
+    (sun, moon + sun_moon.moon_semi_diameter())
+}
+
+//fi crossings
+/// Find the times at which sampled altitudes cross 'h', refining each
+/// by bisection with the altitude function
+fn crossings<F: Fn(f64) -> f64>(
+    times: &[f64],
+    samples: &[f64],
+    h: f64,
+    altitude: &F,
+) -> Vec<Crossing> {
+    let mut result = vec![];
+    for i in 1..times.len() {
+        let below_before = samples[i - 1] < h;
+        let below_after = samples[i] < h;
+        if below_before == below_after {
+            continue;
+        }
+        let (mut t0, mut t1) = (times[i - 1], times[i]);
+        for _ in 0..BISECTIONS {
+            let t = (t0 + t1) / 2.;
+            if (altitude(t) < h) == below_before {
+                t0 = t;
+            } else {
+                t1 = t;
+            }
+        }
+        result.push(Crossing {
+            time: (t0 + t1) / 2.,
+            rising: below_before,
+        });
+    }
+    result
+}
+
+//fi first
+/// The time of the first rising or setting crossing
+fn first(crossings: &[Crossing], rising: bool) -> Option<f64> {
+    crossings
+        .iter()
+        .find(|c| c.rising == rising)
+        .map(|c| c.time)
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::days_from_civil;
+
+    fn london() -> Observer {
+        Observer::new(
+            51.5074_f64.to_radians(),
+            (-0.1278_f64).to_radians(),
+            0.,
+            Time::default(),
+        )
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hours: f64, minutes: f64) -> f64 {
+        days_from_civil(year, month, day) as f64 * time::SECONDS_PER_DAY
+            + (hours * 60. + minutes) * 60.
+    }
+
+    /// Check that the Sun or Moon is at an altitude at a time
+    fn at_altitude(observer: &Observer, t: f64, moon: bool, h: f64) {
+        let mut observer = observer.clone();
+        observer.set_time(Time::of_unix_time(t));
+        let (sun_altitude, moon_altitude) = altitudes(&observer);
+        let altitude = if moon { moon_altitude } else { sun_altitude };
+        assert!((altitude - h).abs() < 1e-4, "{altitude} {h}");
+    }
+
+    #[test]
+    fn winter_night() {
+        let observer = london();
+        let twilight = Twilight::of_date(&observer, 2024, 12, 21);
+        // Published times: sunset 15:53 and sunrise on the 22nd 08:04 UTC
+        let sunset = twilight.sunset.unwrap();
+        let sunrise = twilight.sunrise.unwrap();
+        assert!(
+            (sunset - utc(2024, 12, 21, 15., 53.)).abs() < 90.,
+            "{sunset}"
+        );
+        assert!(
+            (sunrise - utc(2024, 12, 22, 8., 4.)).abs() < 90.,
+            "{sunrise}"
+        );
+
+        let events = [
+            (sunset, SUNRISE_ALTITUDE),
+            (twilight.civil_dusk.unwrap(), CIVIL_ALTITUDE),
+            (twilight.nautical_dusk.unwrap(), NAUTICAL_ALTITUDE),
+            (twilight.astronomical_dusk.unwrap(), ASTRONOMICAL_ALTITUDE),
+            (twilight.astronomical_dawn.unwrap(), ASTRONOMICAL_ALTITUDE),
+            (twilight.nautical_dawn.unwrap(), NAUTICAL_ALTITUDE),
+            (twilight.civil_dawn.unwrap(), CIVIL_ALTITUDE),
+            (sunrise, SUNRISE_ALTITUDE),
+        ];
+        for w in events.windows(2) {
+            assert!(w[0].0 < w[1].0);
+        }
+        for (t, h) in events {
+            at_altitude(&observer, t, false, h);
+        }
+
+        // The waning Moon, near last quarter, rises late in the evening
+        let moonrise = twilight.moonrise.unwrap();
+        at_altitude(&observer, moonrise, true, MOONRISE_ALTITUDE);
+
+        // Dark from the end of astronomical twilight until moonrise
+        assert_eq!(
+            twilight.dark,
+            vec![(twilight.astronomical_dusk.unwrap(), moonrise)]
+        );
+    }
+
+    #[test]
+    fn summer_nights() {
+        // London in midsummer has no astronomical darkness, and the Sun
+        // is only 15 degrees below the horizon at midnight
+        let twilight = Twilight::of_date(&london(), 2024, 6, 21);
+        // Published times: sunset 20:21 and sunrise on the 22nd 03:43 UTC
+        let sunset = twilight.sunset.unwrap();
+        assert!(
+            (sunset - utc(2024, 6, 21, 20., 21.)).abs() < 90.,
+            "{sunset}"
+        );
+        let sunrise = twilight.sunrise.unwrap();
+        assert!(
+            (sunrise - utc(2024, 6, 22, 3., 43.)).abs() < 90.,
+            "{sunrise}"
+        );
+        assert!(twilight.nautical_dusk.is_some());
+        assert!(twilight.astronomical_dusk.is_none());
+        assert!(twilight.astronomical_dawn.is_none());
+        assert!(twilight.dark.is_empty());
+
+        // Tromso has the midnight Sun
+        let tromso = Observer::new(
+            69.6492_f64.to_radians(),
+            18.9553_f64.to_radians(),
+            0.,
+            Time::default(),
+        );
+        let twilight = Twilight::of_date(&tromso, 2024, 6, 21);
+        assert!(twilight.sunset.is_none() && twilight.sunrise.is_none());
+        assert!(twilight.civil_dusk.is_none() && twilight.civil_dawn.is_none());
+        assert!(twilight.dark.is_empty());
+    }
+}
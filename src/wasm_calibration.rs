@@ -0,0 +1,78 @@
+//a Imports
+use geo_nd_wasm::WasmQuatf64;
+use wasm_bindgen::prelude::*;
+
+use crate::calibration::Calibration;
+use crate::rotation;
+use crate::wasm_lens::WasmLensModel;
+
+//a WasmLensCalibration
+//tp WasmLensCalibration
+/// A lens model and camera orientation fitted to image positions of
+/// stars, with the residuals of the fit
+#[wasm_bindgen]
+pub struct WasmLensCalibration {
+    calibration: Calibration,
+    /// The lens model with the fitted lens
+    lens_model: WasmLensModel,
+}
+
+//ip WasmLensCalibration
+#[wasm_bindgen]
+impl WasmLensCalibration {
+    //ap lens_model
+    /// The lens model with the fitted focal length, principal point and
+    /// distortion
+    #[wasm_bindgen(getter)]
+    pub fn lens_model(&self) -> WasmLensModel {
+        self.lens_model.clone()
+    }
+
+    //ap orientation
+    /// The rotation from the camera frame (+X along the optical axis,
+    /// +Y left, +Z up the image) to the frame of the catalog
+    #[wasm_bindgen(getter)]
+    pub fn orientation(&self) -> WasmQuatf64 {
+        rotation::wasm_quat_of_matrix(&self.calibration.orientation)
+    }
+
+    //ap count
+    /// The number of image positions fitted
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.calibration.residuals.len()
+    }
+
+    //ap rms
+    /// The root mean square residual in pixels
+    #[wasm_bindgen(getter)]
+    pub fn rms(&self) -> f64 {
+        self.calibration.rms
+    }
+
+    //mp residuals
+    /// The image position of each star less its matched image position
+    /// in pixels, as dx0, dy0, dx1, dy1, ...; NaN if the lens cannot
+    /// image the star
+    pub fn residuals(&self) -> Vec<f64> {
+        self.calibration.residuals.iter().flat_map(|r| *r).collect()
+    }
+}
+
+//ip WasmLensCalibration
+impl WasmLensCalibration {
+    //cp new
+    /// Create from a calibration of a lens model
+    pub fn new(lens_model: &WasmLensModel, calibration: Calibration) -> Self {
+        let lens_model = lens_model.with_lens(calibration.lens);
+        Self {
+            calibration,
+            lens_model,
+        }
+    }
+
+    //ap calibration
+    pub fn calibration(&self) -> &Calibration {
+        &self.calibration
+    }
+}
@@ -1,37 +1,175 @@
+use std::collections::HashMap;
+
 use geo_nd_wasm::WasmQuatf64;
 use js_sys::Array;
 use wasm_bindgen::prelude::*;
 
 use star_catalog::{
-    Catalog, CatalogIndex, StarFilter, StarTriangleMatch, StarTriangleSearch, Subcube,
+    Catalog, CatalogIndex, Star, StarFilter, StarTriangleMatch, StarTriangleSearch, Subcube,
 };
 
+use crate::apparent_place::ApparentPlace;
+use crate::catalog_csv;
+use crate::precession;
+use crate::proper_motion::{self, ProperMotion};
+use crate::rotation;
+use crate::star_buffer::{self, WasmStarBuffer};
+use crate::star_layout::WasmStarLayout;
+use crate::wasm_observer::WasmObserver;
+use crate::wasm_rise_set::WasmRiseSet;
 use crate::Rrc;
 use crate::{Vec3f64, WasmStar, WasmVec3f64};
 
+//a StarTest
+//tp StarTest
+/// A test that stars must pass to be selected by the filter of a
+/// [WasmCatalog], matching a [StarFilter] added to its catalog
+#[derive(Debug, Clone, Copy)]
+enum StarTest {
+    /// Brighter than a magnitude
+    BrighterThan(f32),
+    /// Within an angle of a unit vector, given the cosine of the angle
+    CosToGt([f64; 3], f64),
+}
+
+//ip StarTest
+impl StarTest {
+    //mp passes
+    /// Return true if the star passes the test
+    fn passes(&self, star: &Star) -> bool {
+        match self {
+            Self::BrighterThan(magnitude) => star.brighter_than(*magnitude),
+            Self::CosToGt(v, cos) => {
+                rotation::dot(&rotation::vec_of_ra_de(star.ra(), star.de()), v) > *cos
+            }
+        }
+    }
+}
+
 //a WasmCatalog
 //tp WasmCatalog
 #[wasm_bindgen]
 pub struct WasmCatalog {
     cat: Rrc<Catalog>,
+    /// Names given to stars (by id) through CSV or [WasmCatalog::name_star]
+    names: Rrc<HashMap<usize, String>>,
+    /// Proper motions of stars, by id
+    motions: HashMap<usize, ProperMotion>,
+    /// Julian date of the epoch of the catalog positions
+    catalog_epoch: f64,
+    /// Julian date to which star positions are propagated, if any
+    epoch: Option<f64>,
+    /// Apparent place transformations for the epoch, if star positions
+    /// are to be apparent places
+    apparent_place: Option<ApparentPlace>,
+    /// The tests of the filter added to the catalog, for selecting
+    /// stars by index
+    filter: Rrc<Vec<StarTest>>,
 }
 
 //ip WasmCatalog
 #[wasm_bindgen]
 impl WasmCatalog {
     //cp new
-    /// Create a new [WasmCatalog]
+    /// Create a new [WasmCatalog]; "hipp_bright" selects the built-in
+    /// bright Hipparcos catalog, and anything else is parsed as a JSON
+    /// catalog
     #[wasm_bindgen(constructor)]
     pub fn new(json: &str) -> Result<WasmCatalog, JsValue> {
-        let mut catalog = Catalog::default();
         if json == "hipp_bright" {
-            catalog = postcard::from_bytes(star_catalog::hipparcos::HIPP_BRIGHT_PST)
-                .map_err(|s| s.to_string())?;
+            let mut s = Self::of_postcard(star_catalog::hipparcos::HIPP_BRIGHT_PST)?;
+            s.catalog_epoch = proper_motion::JD_HIPPARCOS;
+            Ok(s)
+        } else {
+            Self::of_json(json)
         }
-        catalog.sort();
-        catalog.derive_data();
-        let cat = catalog.into();
-        Ok(Self { cat })
+    }
+
+    //cp of_postcard
+    /// Create a new [WasmCatalog] from postcard-serialized bytes
+    pub fn of_postcard(data: &[u8]) -> Result<WasmCatalog, JsValue> {
+        let catalog: Catalog = postcard::from_bytes(data)
+            .map_err(|e| format!("Failed to parse postcard catalog: {e}"))?;
+        Ok(Self::of_catalog(catalog))
+    }
+
+    //cp of_json
+    /// Create a new [WasmCatalog] from a JSON string
+    pub fn of_json(json: &str) -> Result<WasmCatalog, JsValue> {
+        let catalog: Catalog =
+            serde_json::from_str(json).map_err(|e| format!("Failed to parse JSON catalog: {e}"))?;
+        Ok(Self::of_catalog(catalog))
+    }
+
+    //cp of_csv
+    /// Create a new [WasmCatalog] from CSV text
+    ///
+    /// Each line is 'id,ra,de,distance,magnitude,bv[,name]' with
+    /// angles in degrees
+    pub fn of_csv(csv: &str) -> Result<WasmCatalog, JsValue> {
+        let csv_stars =
+            catalog_csv::parse(csv).map_err(|e| format!("Failed to parse CSV catalog: {e}"))?;
+        let mut catalog = Catalog::default();
+        let mut names = vec![];
+        for s in csv_stars {
+            if let Some(name) = s.name {
+                names.push((s.star.id(), name));
+            }
+            catalog.add_star(s.star);
+        }
+        let s = Self::of_catalog(catalog);
+        for (id, name) in names {
+            s.name_star(id, &name)?;
+        }
+        Ok(s)
+    }
+
+    //mp to_postcard
+    /// Serialize the current catalog (including names) to postcard bytes
+    pub fn to_postcard(&self) -> Result<Vec<u8>, JsValue> {
+        let data = postcard::to_allocvec(&*self.cat.borrow())
+            .map_err(|e| format!("Failed to serialize catalog to postcard: {e}"))?;
+        Ok(data)
+    }
+
+    //mp to_json
+    /// Serialize the current catalog (including names) to a JSON string
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        let json = serde_json::to_string(&*self.cat.borrow())
+            .map_err(|e| format!("Failed to serialize catalog to JSON: {e}"))?;
+        Ok(json)
+    }
+
+    //mp to_csv
+    /// Write the current catalog as CSV text, in the format accepted by
+    /// [WasmCatalog::of_csv]
+    ///
+    /// Each star is written with one of its names in the catalog (the
+    /// one given through CSV or [WasmCatalog::name_star] if there is
+    /// one, else the first alphabetically); this fails if a name
+    /// contains a line break
+    pub fn to_csv(&self) -> Result<String, JsValue> {
+        let catalog = self.cat.borrow();
+        let mut names = self.names.borrow().clone();
+        let mut catalog_names: Vec<(&str, usize)> = catalog
+            .iter_names()
+            .map(|(name, id)| (name.as_str(), *id))
+            .collect();
+        catalog_names.sort();
+        for (name, id) in catalog_names {
+            names.entry(id).or_insert_with(|| name.to_string());
+        }
+        let mut csv = String::new();
+        csv.push_str(catalog_csv::CSV_HEADER);
+        csv.push('\n');
+        for i in 0..catalog.len() {
+            let index: CatalogIndex = i.into();
+            let star = &catalog[index];
+            let name = names.get(&star.id()).map(|n| n.as_str());
+            csv.push_str(&catalog_csv::format_star(star, name)?);
+            csv.push('\n');
+        }
+        Ok(csv)
     }
 
     //mp count
@@ -40,6 +178,160 @@ impl WasmCatalog {
         self.cat.borrow().len()
     }
 
+    //ap catalog_epoch
+    /// The Julian date of the epoch of the catalog positions
+    #[wasm_bindgen(getter)]
+    pub fn catalog_epoch(&self) -> f64 {
+        self.catalog_epoch
+    }
+
+    //mp set_catalog_epoch
+    /// Set the Julian date of the epoch of the catalog positions; this
+    /// is J2000 unless the catalog is the built-in Hipparcos catalog
+    /// (J1991.25)
+    pub fn set_catalog_epoch(&mut self, julian_date: f64) {
+        self.catalog_epoch = julian_date;
+    }
+
+    //ap epoch
+    /// The Julian date to which star positions are propagated, if any
+    #[wasm_bindgen(getter)]
+    pub fn epoch(&self) -> Option<f64> {
+        self.epoch
+    }
+
+    //mp set_epoch
+    /// Set the Julian date to which star positions are propagated using
+    /// their proper motions, or undefined to use the catalog positions
+    ///
+    /// This applies to the stars returned by [WasmCatalog::star],
+    /// [WasmCatalog::set_star] and the star buffers; searches use the
+    /// catalog positions
+    pub fn set_epoch(&mut self, julian_date: Option<f64>) {
+        self.epoch = julian_date;
+        if self.apparent_place.is_some() {
+            self.set_apparent(true);
+        }
+    }
+
+    //ap apparent
+    /// True if star positions at the epoch are apparent places
+    #[wasm_bindgen(getter)]
+    pub fn apparent(&self) -> bool {
+        self.apparent_place.is_some()
+    }
+
+    //mp set_apparent
+    /// Select whether the star positions at the epoch (if one is set)
+    /// are apparent places - with precession, nutation and annual
+    /// aberration applied after proper motion - or just moved by their
+    /// proper motion
+    ///
+    /// Apparent places are relative to the true equator and equinox of
+    /// the epoch
+    pub fn set_apparent(&mut self, apparent: bool) {
+        self.apparent_place = {
+            if apparent {
+                Some(ApparentPlace::new(self.epoch.unwrap_or(self.catalog_epoch)))
+            } else {
+                None
+            }
+        };
+    }
+
+    //mp apparent_vector
+    /// Get the apparent place of the star at an index for a Julian
+    /// date, as a unit vector relative to the true equator and equinox
+    /// of the date
+    ///
+    /// This applies proper motion (if known), precession, nutation and
+    /// annual aberration regardless of the catalog epoch settings
+    pub fn apparent_vector(&self, index: usize, julian_date: f64) -> Result<WasmVec3f64, JsValue> {
+        let index = self.checked_index(index)?;
+        let catalog = self.cat.borrow();
+        let star = &catalog[index];
+        let v = self.vector_at(star, julian_date);
+        Ok(ApparentPlace::new(julian_date).apply(&v).into())
+    }
+
+    //mp set_proper_motion
+    /// Set the proper motion (milliarcseconds per year, RA including
+    /// cos(declination)), parallax (milliarcseconds) and radial
+    /// velocity (km/s) of the star with the given id
+    pub fn set_proper_motion(
+        &mut self,
+        id: usize,
+        pm_ra: f64,
+        pm_de: f64,
+        parallax: f64,
+        rv: f64,
+    ) -> Result<(), JsValue> {
+        if self.cat.borrow().find_sorted(id).is_none() {
+            return Err(range_error(format!("No star with id {id} in the catalog")));
+        }
+        self.motions
+            .insert(id, ProperMotion::new(pm_ra, pm_de, parallax, rv));
+        Ok(())
+    }
+
+    //mp add_proper_motions_csv
+    /// Add proper motions from CSV text with lines of
+    /// 'id,pm_ra,pm_de[,parallax[,rv]]'; stars not in the catalog are
+    /// ignored
+    ///
+    /// Returns the number of stars whose proper motion was set
+    pub fn add_proper_motions_csv(&mut self, csv: &str) -> Result<usize, JsValue> {
+        let motions = catalog_csv::parse_motions(csv)
+            .map_err(|e| format!("Failed to parse proper motion CSV: {e}"))?;
+        let mut n = 0;
+        for (id, pm) in motions {
+            if self.cat.borrow().find_sorted(id).is_some() {
+                self.motions.insert(id, pm);
+                n += 1;
+            }
+        }
+        Ok(n)
+    }
+
+    //fp j2000_to_date
+    /// Get the quaternion that applies precession and nutation to a
+    /// J2000 (catalog) vector to give its direction relative to the
+    /// true equator and equinox of a Julian date
+    pub fn j2000_to_date(julian_date: f64) -> WasmQuatf64 {
+        rotation::wasm_quat_of_matrix(&precession::precession_nutation_matrix(julian_date))
+    }
+
+    //fp date_to_j2000
+    /// Get the quaternion that takes a vector relative to the true
+    /// equator and equinox of a Julian date back to J2000
+    pub fn date_to_j2000(julian_date: f64) -> WasmQuatf64 {
+        rotation::wasm_quat_of_matrix(&rotation::transpose(
+            &precession::precession_nutation_matrix(julian_date),
+        ))
+    }
+
+    //mp rise_set
+    /// Find the next rise, transits and set of the star at an index
+    /// after the observer's time
+    ///
+    /// The star's apparent place at the observer's time is used; the
+    /// horizon altitude (radians) is where it appears to rise and set,
+    /// usually zero, and refraction is included if the observer has it
+    pub fn rise_set(
+        &self,
+        index: usize,
+        observer: &WasmObserver,
+        horizon_altitude: f64,
+    ) -> Result<WasmRiseSet, JsValue> {
+        let index = self.checked_index(index)?;
+        let observer = observer.observer();
+        let jd = observer.time().jd_tt();
+        let catalog = self.cat.borrow();
+        let v = ApparentPlace::new(jd).apply(&self.vector_at(&catalog[index], jd));
+        let (ra, de) = rotation::ra_de_of_vec(&v);
+        Ok(observer.rise_set_of_ra_de(ra, de, horizon_altitude).into())
+    }
+
     //mp max_magnitude
     pub fn max_magnitude(&self, magnitude: f32) {
         self.cat
@@ -63,33 +355,38 @@ impl WasmCatalog {
         Some(index.as_usize())
     }
 
-    pub fn set_star(&self, star: &mut WasmStar, index: usize) -> bool {
-        let cat = self.cat.borrow();
-        if index >= cat.len() {
-            return false;
-        }
-        let index: CatalogIndex = index.into();
-        *star = cat[index].clone().into();
-        true
+    //mp set_star
+    /// Set a [WasmStar] to be a copy of the star at an index in the
+    /// catalog; this fails with a RangeError if the index is out of
+    /// range, leaving the star unchanged
+    pub fn set_star(&self, star: &mut WasmStar, index: usize) -> Result<(), JsValue> {
+        let index = self.checked_index(index)?;
+        *star = self.wasm_star(index);
+        Ok(())
     }
 
     //mp star
+    /// Get a copy of the star at an index in the catalog, or undefined
+    /// if the index is out of range
     pub fn star(&self, index: usize) -> Option<WasmStar> {
-        let index: CatalogIndex = index.into();
-        Some(self.cat.borrow()[index].clone().into())
+        let index = check_index(index, self.cat.borrow().len()).ok()?;
+        Some(self.wasm_star(index.into()))
     }
 
     //mp name_star
-    pub fn name_star(&self, id: usize, name: &str) -> bool {
+    /// Add a name to the star with the given id; the catalog must be
+    /// sorted, and it must contain the star
+    pub fn name_star(&self, id: usize, name: &str) -> Result<(), JsValue> {
         let mut catalog = self.cat.borrow_mut();
         if !catalog.is_sorted() {
-            return false;
+            return Err("Catalog must be sorted to name a star".into());
         }
         let Some(index) = catalog.find_sorted(id) else {
-            return false;
+            return Err(range_error(format!("No star with id {id} in the catalog")));
         };
         catalog.add_name(index, name);
-        true
+        self.names.borrow_mut().insert(id, name.into());
+        Ok(())
     }
 
     //mp closest_to_ra_de
@@ -104,10 +401,14 @@ impl WasmCatalog {
     //mp clear_filter
     pub fn clear_filter(&self) {
         self.cat.borrow_mut().clear_filter();
+        self.filter.borrow_mut().clear();
     }
 
     //mp filter_max_magnitude
     pub fn filter_max_magnitude(&self, magnitude: f32) {
+        self.filter
+            .borrow_mut()
+            .push(StarTest::BrighterThan(magnitude));
         self.cat
             .borrow_mut()
             .add_filter(StarFilter::brighter_than(magnitude));
@@ -116,6 +417,9 @@ impl WasmCatalog {
     //mp filter_closer_to
     pub fn filter_closer_to(&self, v: &WasmVec3f64, angle: f64) {
         let v: Vec3f64 = v.into();
+        self.filter
+            .borrow_mut()
+            .push(StarTest::CosToGt([v[0], v[1], v[2]], angle.cos()));
         self.cat
             .borrow_mut()
             .add_filter(StarFilter::cos_to_gt(v.into(), angle.cos()));
@@ -139,12 +443,47 @@ impl WasmCatalog {
         for index in self.cat.borrow().find_stars_around(&v, max_angle) {
             result.push(&index.as_usize().into());
         }
-        self.cat.borrow_mut().clear_filter();
+        self.clear_filter();
         result
     }
 
+    //mp star_buffer
+    /// Encode every star that passes the current filter as three u32
+    /// for the sky shader, in catalog order
+    pub fn star_buffer(&self) -> WasmStarBuffer {
+        let catalog = self.cat.borrow();
+        let mut data = Vec::with_capacity(catalog.len() * 3);
+        for index in self.filtered_indices() {
+            let moved = self.star_at_epoch(&catalog[index]);
+            let star = moved.as_ref().unwrap_or(&catalog[index]);
+            data.extend(star_buffer::encode_star(star));
+        }
+        WasmStarBuffer::new(data, 3)
+    }
+
+    //mp star_buffer_of_layout
+    /// Create an interleaved vertex buffer with the given layout for
+    /// every star that passes the current filter, in catalog order
+    pub fn star_buffer_of_layout(&self, layout: &WasmStarLayout) -> WasmStarBuffer {
+        let catalog = self.cat.borrow();
+        let indices = self.filtered_indices();
+        let mut bytes = Vec::with_capacity(indices.len() * layout.stride());
+        for index in indices {
+            let moved = self.star_at_epoch(&catalog[index]);
+            let star = moved.as_ref().unwrap_or(&catalog[index]);
+            layout.write_star(star, &mut bytes);
+        }
+        let data = bytes
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        WasmStarBuffer::new(data, layout.stride() / 4)
+    }
+
     //mp find_star_triangles
-    /// Find stars around a vector within a given angle in radians
+    /// Find triangles of stars whose sides subtend the given angles (in
+    /// radians), returning the catalog indices of each triangle's
+    /// stars as consecutive triples
     pub fn find_star_triangles(
         &self,
         max_angle_delta: f64,
@@ -152,7 +491,7 @@ impl WasmCatalog {
         a1: f64,
         a2: f64,
         _max_triangles: usize,
-    ) -> Vec<u32> {
+    ) -> Result<Vec<u32>, JsValue> {
         //        let f_orig = self
         //            .cat
         //            .borrow_mut()
@@ -168,7 +507,8 @@ impl WasmCatalog {
                     .borrow_mut()
                     .add_filter(StarFilter::brighter_than(5.0));
         */
-        let search = StarTriangleSearch::of_angles(angles_to_find, max_angle_delta).unwrap();
+        let search = StarTriangleSearch::of_angles(angles_to_find, max_angle_delta)
+            .map_err(|e| format!("Bad star triangle search: {e}"))?;
         let (finished, mut candidates) =
             self.cat
                 .borrow()
@@ -177,11 +517,12 @@ impl WasmCatalog {
         candidates.sort_by(StarTriangleMatch::compare_angle_sum);
         for tm in candidates {
             let t = tm.triangle();
-            result.push(t.0.as_usize() as u32);
-            result.push(t.1.as_usize() as u32);
-            result.push(t.2.as_usize() as u32);
+            for index in [t.0, t.1, t.2] {
+                let index = self.checked_index(index.as_usize())?;
+                result.push(index.as_usize() as u32);
+            }
         }
-        result
+        Ok(result)
     }
 
     pub fn find_best_star_mappings(
@@ -202,7 +543,7 @@ impl WasmCatalog {
 
         crate::console_log!("Found {} candidates", candidates.len());
 
-        candidates.sort_by(|a, b| a.quality.partial_cmp(&b.quality).unwrap());
+        candidates.sort_by(|a, b| a.quality.total_cmp(&b.quality));
         let mut r = vec![];
         for c in candidates {
             let rijk = {
@@ -223,3 +564,209 @@ impl WasmCatalog {
 
     //zz All done
 }
+
+//ip WasmCatalog
+impl WasmCatalog {
+    //cp of_catalog
+    /// Create a [WasmCatalog] from a [Catalog], sorting it and deriving
+    /// its data
+    fn of_catalog(mut catalog: Catalog) -> Self {
+        catalog.sort();
+        catalog.derive_data();
+        let cat = catalog.into();
+        let names = HashMap::default().into();
+        Self {
+            cat,
+            names,
+            motions: HashMap::default(),
+            catalog_epoch: proper_motion::JD_J2000,
+            epoch: None,
+            apparent_place: None,
+            filter: Rrc::default(),
+        }
+    }
+
+    //ap catalog
+    /// Borrow the catalog, with any filter that has been set
+    pub fn catalog(&self) -> std::cell::Ref<'_, Catalog> {
+        self.cat.borrow()
+    }
+
+    //mp star_vector
+    /// Get the unit vector of a star at the current epoch, with its
+    /// proper motion and as an apparent place if those are set
+    pub fn star_vector(&self, index: CatalogIndex) -> [f64; 3] {
+        let catalog = self.cat.borrow();
+        let star = &catalog[index];
+        match self.star_at_epoch(star) {
+            Some(moved) => rotation::vec_of_ra_de(moved.ra(), moved.de()),
+            None => rotation::vec_of_ra_de(star.ra(), star.de()),
+        }
+    }
+
+    //mi vector_at
+    /// Get the J2000 unit vector of a star at a Julian date, moved by
+    /// its proper motion if that is known
+    fn vector_at(&self, star: &Star, julian_date: f64) -> [f64; 3] {
+        match self.motions.get(&star.id()) {
+            Some(pm) => {
+                let years =
+                    (julian_date - self.catalog_epoch) / proper_motion::DAYS_PER_JULIAN_YEAR;
+                pm.propagate(star.ra(), star.de(), years)
+            }
+            None => rotation::vec_of_ra_de(star.ra(), star.de()),
+        }
+    }
+
+    //mi star_at_epoch
+    /// Get a star moved to the current epoch, if there is an epoch and
+    /// the star has a known proper motion or apparent places are
+    /// required
+    fn star_at_epoch(&self, star: &Star) -> Option<Star> {
+        let epoch = self.epoch?;
+        let Some(apparent_place) = &self.apparent_place else {
+            let pm = self.motions.get(&star.id())?;
+            return Some(pm.propagate_star(star, self.catalog_epoch, epoch));
+        };
+        let v = apparent_place.apply(&self.vector_at(star, epoch));
+        let (ra, de) = rotation::ra_de_of_vec(&v);
+        Some(Star::new(
+            star.id(),
+            ra,
+            de,
+            star.distance(),
+            star.magnitude(),
+            star.bv(),
+        ))
+    }
+
+    //mi wasm_star
+    /// Get a [WasmStar] for a valid index, at the current epoch and with
+    /// its proper motion
+    fn wasm_star(&self, index: CatalogIndex) -> WasmStar {
+        let catalog = self.cat.borrow();
+        let star = &catalog[index];
+        let moved = self.star_at_epoch(star);
+        let pm = self.motions.get(&star.id()).copied().unwrap_or_default();
+        let j2000 = {
+            match self.epoch {
+                Some(epoch) => self.vector_at(star, epoch),
+                None => rotation::vec_of_ra_de(star.ra(), star.de()),
+            }
+        };
+        WasmStar::with_motion(moved.unwrap_or_else(|| star.clone()), j2000, pm)
+    }
+
+    //mi filtered_indices
+    /// Get the indices of all the stars that pass the current filter, in
+    /// catalog order
+    pub fn filtered_indices(&self) -> Vec<CatalogIndex> {
+        let catalog = self.cat.borrow();
+        let filter = self.filter.borrow();
+        let mut indices = vec![];
+        for i in 0..catalog.len() {
+            let index: CatalogIndex = i.into();
+            if filter.iter().all(|t| t.passes(&catalog[index])) {
+                indices.push(index);
+            }
+        }
+        indices
+    }
+
+    //mi checked_index
+    /// Convert an index into a [CatalogIndex], returning a RangeError if
+    /// it is not within the catalog
+    fn checked_index(&self, index: usize) -> Result<CatalogIndex, JsValue> {
+        let index = check_index(index, self.cat.borrow().len()).map_err(range_error)?;
+        Ok(index.into())
+    }
+}
+
+//a Functions
+//fi check_index
+/// Check that an index is within a catalog of the given number of stars
+fn check_index(index: usize, len: usize) -> Result<usize, String> {
+    if index >= len {
+        Err(format!(
+            "Star index {index} out of range for catalog of {len} stars"
+        ))
+    } else {
+        Ok(index)
+    }
+}
+
+//fi range_error
+/// Create a JavaScript RangeError with the given message
+fn range_error(msg: String) -> JsValue {
+    js_sys::RangeError::new(&msg).into()
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_in_range() {
+        assert_eq!(check_index(0, 1), Ok(0));
+        assert_eq!(check_index(9, 10), Ok(9));
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        assert!(check_index(0, 0).is_err());
+        assert!(check_index(10, 10).is_err());
+        assert!(check_index(usize::MAX, 10).is_err());
+        assert_eq!(
+            check_index(3, 2),
+            Err("Star index 3 out of range for catalog of 2 stars".into())
+        );
+    }
+
+    fn catalog() -> WasmCatalog {
+        WasmCatalog::of_csv(
+            "1,10.0,20.0,100.0,1.5,0.5,Alpha\n2,30.0,-40.0,50.0,3.0,1.0\n3,200.0,60.0,20.0,4.5,0.1\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn star_in_range() {
+        let catalog = catalog();
+        for index in 0..3 {
+            assert_eq!(catalog.star(index).unwrap().id(), index + 1);
+        }
+        let mut star = catalog.star(0).unwrap();
+        catalog.set_star(&mut star, 2).unwrap();
+        assert_eq!(star.id(), 3);
+        catalog.name_star(2, "Beta").unwrap();
+    }
+
+    #[test]
+    fn star_out_of_range() {
+        let catalog = catalog();
+        assert!(catalog.star(3).is_none());
+        assert!(catalog.star(usize::MAX).is_none());
+        assert!(WasmCatalog::of_csv("").unwrap().star(0).is_none());
+    }
+
+    #[test]
+    fn vector_of_date() {
+        let jd = 2462502.5;
+        let mut catalog = catalog();
+        let mean = catalog.star(1).unwrap().vector_of_date(jd);
+        catalog.set_epoch(Some(jd));
+        catalog.set_apparent(true);
+        let star = catalog.star(1).unwrap();
+        let of_date: Vec3f64 = star.vector_of_date(jd).into();
+        let mean: Vec3f64 = mean.into();
+        let apparent: Vec3f64 = star.vector().into();
+        for i in 0..3 {
+            assert!((of_date[i] - mean[i]).abs() < 1e-12);
+        }
+        // The apparent place is only aberration away from the vector of
+        // date, not precessed twice
+        let d: f64 = (0..3).map(|i| (of_date[i] - apparent[i]).powi(2)).sum();
+        assert!(d.sqrt() < 2e-4, "{}", d.sqrt());
+    }
+}
@@ -0,0 +1,231 @@
+//a Imports
+use wasm_bindgen::prelude::*;
+
+use crate::image_sources::{self, ExtractParameters, Image, ImageSource};
+
+//a WasmSourceExtractor
+//tp WasmSourceExtractor
+/// Extraction of the stars from camera images, with the parameters
+/// controlling it
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmSourceExtractor {
+    parameters: ExtractParameters,
+}
+
+//ip WasmSourceExtractor
+#[wasm_bindgen]
+impl WasmSourceExtractor {
+    //cp new
+    /// Create with the default parameters: a threshold of 5 standard
+    /// deviations, 3 to 2500 pixels per source, 64 pixel background
+    /// cells, saturation at 255, a gain of 1 and at most 500 sources
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmSourceExtractor {
+        Self::default()
+    }
+
+    //mp set_threshold
+    /// Set the detection threshold in standard deviations of the noise
+    /// above the background
+    pub fn set_threshold(&mut self, threshold: f64) {
+        self.parameters.threshold = threshold;
+    }
+
+    //mp set_pixels
+    /// Set the fewest and most pixels above the threshold in a source
+    pub fn set_pixels(&mut self, min_pixels: usize, max_pixels: usize) {
+        self.parameters.min_pixels = min_pixels.max(1);
+        self.parameters.max_pixels = max_pixels;
+    }
+
+    //mp set_background_cell
+    /// Set the size in pixels of the cells used to estimate the
+    /// background; this should be several times the size of the stars
+    pub fn set_background_cell(&mut self, cell: usize) {
+        self.parameters.background_cell = cell;
+    }
+
+    //mp set_saturation
+    /// Set the level of an image byte at or above which a pixel is
+    /// saturated
+    pub fn set_saturation(&mut self, saturation: u8) {
+        self.parameters.saturation = saturation;
+    }
+
+    //mp set_gain
+    /// Set the electrons per image unit, for the signal to noise ratios
+    pub fn set_gain(&mut self, gain: f64) {
+        self.parameters.gain = gain;
+    }
+
+    //mp set_max_sources
+    pub fn set_max_sources(&mut self, max_sources: usize) {
+        self.parameters.max_sources = max_sources;
+    }
+
+    //mp extract
+    /// Extract the sources from image data (greyscale, RGB or RGBA bytes,
+    /// such as the data of a canvas ImageData) of a width and height
+    pub fn extract(
+        &self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<WasmImageSources, JsValue> {
+        let image = Image::of_bytes(data, width, height, self.parameters.saturation)?;
+        let (background, sources) = image_sources::extract_sources(&image, &self.parameters);
+        Ok(WasmImageSources {
+            background: background.median_level() as f64,
+            noise: background.median_noise() as f64,
+            sources,
+        })
+    }
+}
+
+//a WasmImageSources
+//tp WasmImageSources
+/// The sources found in an image, brightest first, with the background
+/// of the image
+#[wasm_bindgen]
+pub struct WasmImageSources {
+    background: f64,
+    noise: f64,
+    sources: Vec<ImageSource>,
+}
+
+//ip WasmImageSources
+#[wasm_bindgen]
+impl WasmImageSources {
+    //ap count
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.sources.len()
+    }
+
+    //ap background
+    /// The median background level of the image
+    #[wasm_bindgen(getter)]
+    pub fn background(&self) -> f64 {
+        self.background
+    }
+
+    //ap noise
+    /// The median standard deviation of the noise of the background
+    #[wasm_bindgen(getter)]
+    pub fn noise(&self) -> f64 {
+        self.noise
+    }
+
+    //mp source
+    /// Get a source by index, if there is one
+    pub fn source(&self, n: usize) -> Option<WasmImageSource> {
+        self.sources.get(n).map(|s| s.clone().into())
+    }
+
+    //mp sources
+    pub fn sources(&self) -> Vec<WasmImageSource> {
+        self.sources.iter().map(|s| s.clone().into()).collect()
+    }
+
+    //mp xy
+    /// The centroids of the sources in image pixels, as x0, y0, x1, y1,
+    /// ...
+    pub fn xy(&self) -> Vec<f64> {
+        self.sources.iter().flat_map(|s| [s.x, s.y]).collect()
+    }
+
+    //mp fluxes
+    pub fn fluxes(&self) -> Vec<f64> {
+        self.sources.iter().map(|s| s.flux).collect()
+    }
+}
+
+//ip WasmImageSources
+impl WasmImageSources {
+    //ap image_sources
+    pub fn image_sources(&self) -> &[ImageSource] {
+        &self.sources
+    }
+}
+
+//a WasmImageSource
+//tp WasmImageSource
+/// A source found in an image
+#[wasm_bindgen]
+pub struct WasmImageSource {
+    source: ImageSource,
+}
+
+//ip WasmImageSource
+#[wasm_bindgen]
+impl WasmImageSource {
+    //ap x
+    /// Column of the centroid in image pixels from the left edge
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> f64 {
+        self.source.x
+    }
+
+    //ap y
+    /// Row of the centroid in image pixels from the top edge
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> f64 {
+        self.source.y
+    }
+
+    //ap flux
+    /// Sum of the pixel values above the background
+    #[wasm_bindgen(getter)]
+    pub fn flux(&self) -> f64 {
+        self.source.flux
+    }
+
+    //ap peak
+    /// Largest pixel value above the background
+    #[wasm_bindgen(getter)]
+    pub fn peak(&self) -> f64 {
+        self.source.peak
+    }
+
+    //ap background
+    #[wasm_bindgen(getter)]
+    pub fn background(&self) -> f64 {
+        self.source.background
+    }
+
+    //ap fwhm
+    /// Full width at half maximum in pixels
+    #[wasm_bindgen(getter)]
+    pub fn fwhm(&self) -> f64 {
+        self.source.fwhm
+    }
+
+    //ap snr
+    /// Signal to noise ratio of the flux
+    #[wasm_bindgen(getter)]
+    pub fn snr(&self) -> f64 {
+        self.source.snr
+    }
+
+    //ap pixels
+    /// Number of pixels above the detection threshold
+    #[wasm_bindgen(getter)]
+    pub fn pixels(&self) -> usize {
+        self.source.pixels
+    }
+
+    //ap saturated
+    /// True if any pixel of the source is saturated
+    #[wasm_bindgen(getter)]
+    pub fn saturated(&self) -> bool {
+        self.source.saturated
+    }
+}
+
+//ip From<ImageSource> for WasmImageSource
+impl From<ImageSource> for WasmImageSource {
+    fn from(source: ImageSource) -> Self {
+        Self { source }
+    }
+}
@@ -0,0 +1,457 @@
+//a Imports
+use geo_nd_wasm::WasmQuatf64;
+use wasm_bindgen::prelude::*;
+
+use crate::calibration::{self, CalibrationParameters};
+use crate::distortion::Distortion;
+use crate::lens::{Lens, LensProjection, FULL_FRAME_WIDTH};
+use crate::rotation::{self, Mat3};
+use crate::wasm_calibration::WasmLensCalibration;
+use crate::{Vec3f64, WasmVec3f64};
+
+//a Types
+//tp Matches
+/// Image positions in pixels and the unit vectors of their matched
+/// stars
+type Matches = (Vec<[f64; 2]>, Vec<[f64; 3]>);
+
+//a WasmLensModel
+//tp WasmLensModel
+/// A lens and the image it forms, mapping image positions in pixels
+/// (origin top left, Y down) to directions in the camera frame (+X
+/// along the optical axis, +Y left, +Z up the image) and back
+///
+/// The focal length may be set in pixels, in mm for the sensor width,
+/// or as a 35mm equivalent; the principal point is an offset in pixels
+/// from the center of the image
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct WasmLensModel {
+    lens: Lens,
+    /// Width and height of the image in pixels
+    size: [f64; 2],
+    /// Width of the sensor in mm
+    sensor_width: f64,
+}
+
+//ip WasmLensModel
+#[wasm_bindgen]
+impl WasmLensModel {
+    //cp new
+    /// Create a lens model for a projection ("rectilinear",
+    /// "stereographic", "equidistant", "equisolid" or "orthographic"),
+    /// an image width and height in pixels and a 35mm equivalent focal
+    /// length in mm, with the principal point at the center of the image
+    /// and square pixels
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        projection: &str,
+        width: f64,
+        height: f64,
+        mm_equiv: f64,
+    ) -> Result<WasmLensModel, JsValue> {
+        let projection = Self::projection_of_name(projection)?;
+        if !is_positive(width) || !is_positive(height) {
+            return Err(format!("Bad image size {width} by {height}").into());
+        }
+        if !is_positive(mm_equiv) {
+            return Err("Focal lengths must be positive".into());
+        }
+        let focal_length = mm_equiv * width / FULL_FRAME_WIDTH;
+        Ok(Self {
+            lens: Lens::new(projection, focal_length, [width / 2., height / 2.]),
+            size: [width, height],
+            sensor_width: FULL_FRAME_WIDTH,
+        })
+    }
+
+    //ap projection
+    /// The name of the projection of the lens
+    #[wasm_bindgen(getter)]
+    pub fn projection(&self) -> String {
+        self.lens.projection.name().into()
+    }
+
+    //mp set_projection
+    /// Set the projection of the lens by name
+    pub fn set_projection(&mut self, projection: &str) -> Result<(), JsValue> {
+        self.lens.projection = Self::projection_of_name(projection)?;
+        Ok(())
+    }
+
+    //ap width
+    /// The width of the image in pixels
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> f64 {
+        self.size[0]
+    }
+
+    //ap height
+    /// The height of the image in pixels
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> f64 {
+        self.size[1]
+    }
+
+    //mp set_image_size
+    /// Set the size of the image in pixels, keeping the 35mm equivalent
+    /// focal length and the principal point offset (in pixels)
+    pub fn set_image_size(&mut self, width: f64, height: f64) -> Result<(), JsValue> {
+        if !is_positive(width) || !is_positive(height) {
+            return Err(format!("Bad image size {width} by {height}").into());
+        }
+        let mm_equiv = self.mm_equiv();
+        let offset = self.principal_point();
+        self.size = [width, height];
+        self.lens.focal_length = mm_equiv * width / FULL_FRAME_WIDTH;
+        self.set_principal_point(offset[0], offset[1]);
+        Ok(())
+    }
+
+    //ap focal_length
+    /// The focal length in pixels (pixel widths)
+    #[wasm_bindgen(getter)]
+    pub fn focal_length(&self) -> f64 {
+        self.lens.focal_length
+    }
+
+    //mp set_focal_length
+    /// Set the focal length in pixels (pixel widths)
+    pub fn set_focal_length(&mut self, focal_length: f64) -> Result<(), JsValue> {
+        if !is_positive(focal_length) {
+            return Err("Focal lengths must be positive".into());
+        }
+        self.lens.focal_length = focal_length;
+        Ok(())
+    }
+
+    //ap mm_equiv
+    /// The 35mm equivalent focal length in mm (for the width of the
+    /// image)
+    #[wasm_bindgen(getter)]
+    pub fn mm_equiv(&self) -> f64 {
+        self.lens.focal_length * FULL_FRAME_WIDTH / self.size[0]
+    }
+
+    //mp set_mm_equiv
+    /// Set the focal length as a 35mm equivalent in mm
+    pub fn set_mm_equiv(&mut self, mm_equiv: f64) -> Result<(), JsValue> {
+        if !is_positive(mm_equiv) {
+            return Err("Focal lengths must be positive".into());
+        }
+        self.lens.focal_length = mm_equiv * self.size[0] / FULL_FRAME_WIDTH;
+        Ok(())
+    }
+
+    //ap focal_length_mm
+    /// The focal length in mm for the width of the sensor
+    #[wasm_bindgen(getter)]
+    pub fn focal_length_mm(&self) -> f64 {
+        self.lens.focal_length * self.sensor_width / self.size[0]
+    }
+
+    //mp set_focal_length_mm
+    /// Set the focal length in mm for the width of the sensor
+    pub fn set_focal_length_mm(&mut self, focal_length: f64) -> Result<(), JsValue> {
+        if !is_positive(focal_length) {
+            return Err("Focal lengths must be positive".into());
+        }
+        self.lens.focal_length = focal_length * self.size[0] / self.sensor_width;
+        Ok(())
+    }
+
+    //ap sensor_width
+    /// The width of the sensor in mm
+    #[wasm_bindgen(getter)]
+    pub fn sensor_width(&self) -> f64 {
+        self.sensor_width
+    }
+
+    //ap sensor_height
+    /// The height of the sensor in mm
+    #[wasm_bindgen(getter)]
+    pub fn sensor_height(&self) -> f64 {
+        self.sensor_width * self.size[1] / self.size[0] * self.lens.aspect_ratio
+    }
+
+    //mp set_sensor_size
+    /// Set the width and height of the sensor in mm, keeping the focal
+    /// length in pixels; this sets the aspect ratio of the pixels
+    pub fn set_sensor_size(&mut self, width: f64, height: f64) -> Result<(), JsValue> {
+        if !is_positive(width) || !is_positive(height) {
+            return Err(format!("Bad sensor size {width} by {height}").into());
+        }
+        self.sensor_width = width;
+        self.lens.aspect_ratio = (height / self.size[1]) / (width / self.size[0]);
+        Ok(())
+    }
+
+    //ap aspect_ratio
+    /// The height of a pixel over its width
+    #[wasm_bindgen(getter)]
+    pub fn aspect_ratio(&self) -> f64 {
+        self.lens.aspect_ratio
+    }
+
+    //mp set_aspect_ratio
+    /// Set the height of a pixel over its width
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f64) -> Result<(), JsValue> {
+        if !is_positive(aspect_ratio) {
+            return Err(format!("Bad pixel aspect ratio {aspect_ratio}").into());
+        }
+        self.lens.aspect_ratio = aspect_ratio;
+        Ok(())
+    }
+
+    //ap principal_point
+    /// The offset in pixels of the optical axis from the center of the
+    /// image, as [dx, dy]
+    #[wasm_bindgen(getter)]
+    pub fn principal_point(&self) -> Vec<f64> {
+        vec![
+            self.lens.center[0] - self.size[0] / 2.,
+            self.lens.center[1] - self.size[1] / 2.,
+        ]
+    }
+
+    //mp set_principal_point
+    /// Set the offset in pixels of the optical axis from the center of
+    /// the image
+    pub fn set_principal_point(&mut self, dx: f64, dy: f64) {
+        self.lens.center = [self.size[0] / 2. + dx, self.size[1] / 2. + dy];
+    }
+
+    //ap distortion
+    /// The distortion coefficients as [k1, k2, k3, p1, p2]
+    #[wasm_bindgen(getter)]
+    pub fn distortion(&self) -> Vec<f64> {
+        self.lens.distortion.coefficients().to_vec()
+    }
+
+    //mp set_distortion
+    /// Set the radial (k1, k2, k3) and tangential (p1, p2) distortion
+    /// coefficients
+    pub fn set_distortion(&mut self, k1: f64, k2: f64, k3: f64, p1: f64, p2: f64) {
+        self.lens.distortion = Distortion::new([k1, k2, k3], [p1, p2]);
+    }
+
+    //ap max_angle
+    /// The largest angle (radians) from the optical axis that the
+    /// projection can image
+    #[wasm_bindgen(getter)]
+    pub fn max_angle(&self) -> f64 {
+        self.lens.projection.max_angle()
+    }
+
+    //mp vector_of_xy
+    /// The unit vector in the camera frame of the direction imaged at an
+    /// image position in pixels
+    pub fn vector_of_xy(&self, x: f64, y: f64) -> WasmVec3f64 {
+        self.lens.vector_of_xy([x, y]).into()
+    }
+
+    //mp xy_of_vector
+    /// The image position in pixels, as [x, y], of a direction in the
+    /// camera frame; undefined if the projection cannot image it
+    pub fn xy_of_vector(&self, v: &WasmVec3f64) -> Option<Vec<f64>> {
+        let v: Vec3f64 = v.into();
+        self.lens
+            .xy_of_vector(&[v[0], v[1], v[2]])
+            .map(|xy| xy.to_vec())
+    }
+
+    //mp vectors_of_xy
+    /// The unit vectors in the camera frame, as x0, y0, z0, x1, ..., of
+    /// image positions given as x0, y0, x1, y1, ...
+    pub fn vectors_of_xy(&self, xy: &[f64]) -> Vec<f64> {
+        xy.chunks_exact(2)
+            .flat_map(|p| self.lens.vector_of_xy([p[0], p[1]]))
+            .collect()
+    }
+
+    //mp xy_of_vectors
+    /// The image positions in pixels, as x0, y0, x1, y1, ..., of
+    /// directions in the camera frame given as x0, y0, z0, x1, ...;
+    /// positions that cannot be imaged are NaN
+    pub fn xy_of_vectors(&self, v: &[f64]) -> Vec<f64> {
+        v.chunks_exact(3)
+            .flat_map(|v| {
+                self.lens
+                    .xy_of_vector(&[v[0], v[1], v[2]])
+                    .unwrap_or([f64::NAN; 2])
+            })
+            .collect()
+    }
+
+    //mp distort
+    /// Map image positions as imaged by an undistorted lens to where
+    /// this lens images them, both in pixels as x0, y0, x1, y1, ...
+    pub fn distort(&self, xy: &[f64]) -> Vec<f64> {
+        let undistorted = Lens {
+            distortion: Distortion::default(),
+            ..self.lens
+        };
+        xy.chunks_exact(2)
+            .flat_map(|p| self.lens.xy_of_ideal(undistorted.ideal_of_xy([p[0], p[1]])))
+            .collect()
+    }
+
+    //mp undistort
+    /// Map image positions as imaged by this lens to where an
+    /// undistorted lens would image them, both in pixels as x0, y0, x1,
+    /// y1, ...
+    pub fn undistort(&self, xy: &[f64]) -> Vec<f64> {
+        let undistorted = Lens {
+            distortion: Distortion::default(),
+            ..self.lens
+        };
+        xy.chunks_exact(2)
+            .flat_map(|p| undistorted.xy_of_ideal(self.lens.ideal_of_xy([p[0], p[1]])))
+            .collect()
+    }
+
+    //mp calibrate
+    /// Calibrate the lens from image positions (x0, y0, x1, y1, ...)
+    /// matched to the vectors of their catalog stars (such as
+    /// [crate::WasmStar::vector]), starting from this lens model
+    ///
+    /// The focal length is always fitted, with the first 'radial_terms'
+    /// (0 to 3) radial distortion coefficients, the tangential
+    /// coefficients if 'tangential', and the principal point if
+    /// 'principal_point'; other coefficients are zero
+    pub fn calibrate(
+        &self,
+        xy: &[f64],
+        star_vectors: Vec<WasmVec3f64>,
+        radial_terms: usize,
+        tangential: bool,
+        principal_point: bool,
+    ) -> Result<WasmLensCalibration, JsValue> {
+        let (xy, stars) = Self::matches_of(xy, &star_vectors)?;
+        let parameters = Self::calibration_parameters(radial_terms, tangential, principal_point);
+        let calibration = calibration::calibrate(&self.lens, &xy, &stars, &parameters)
+            .ok_or("Too few stars to calibrate the lens")?;
+        Ok(WasmLensCalibration::new(self, calibration))
+    }
+
+    //mp refine
+    /// Refine the lens and an approximate orientation of the camera
+    /// (such as from [crate::WasmCatalog::find_best_star_mappings]) by
+    /// Levenberg-Marquardt, given image positions (x0, y0, x1, y1, ...)
+    /// matched to the vectors of their catalog stars
+    ///
+    /// The orientation and focal length are always refined, with the
+    /// first 'radial_terms' (0 to 3) radial distortion coefficients, the
+    /// tangential coefficients if 'tangential', and the principal point
+    /// if 'principal_point'; other terms keep their values
+    pub fn refine(
+        &self,
+        orientation: &WasmQuatf64,
+        xy: &[f64],
+        star_vectors: Vec<WasmVec3f64>,
+        radial_terms: usize,
+        tangential: bool,
+        principal_point: bool,
+    ) -> Result<WasmLensCalibration, JsValue> {
+        let (xy, stars) = Self::matches_of(xy, &star_vectors)?;
+        self.refine_matches(
+            &rotation::matrix_of_wasm_quat(orientation),
+            &xy,
+            &stars,
+            radial_terms,
+            tangential,
+            principal_point,
+        )
+    }
+}
+
+//ip WasmLensModel
+impl WasmLensModel {
+    //fi matches_of
+    /// Image positions and star vectors of matches from their wasm forms
+    fn matches_of(xy: &[f64], star_vectors: &[WasmVec3f64]) -> Result<Matches, JsValue> {
+        let xy: Vec<[f64; 2]> = xy.chunks_exact(2).map(|p| [p[0], p[1]]).collect();
+        if xy.len() != star_vectors.len() {
+            return Err(format!(
+                "Calibration needs one star per image position, but has {} positions and {} stars",
+                xy.len(),
+                star_vectors.len()
+            )
+            .into());
+        }
+        let stars = star_vectors
+            .iter()
+            .map(|v| {
+                let v: Vec3f64 = v.into();
+                [v[0], v[1], v[2]]
+            })
+            .collect();
+        Ok((xy, stars))
+    }
+
+    //fi calibration_parameters
+    fn calibration_parameters(
+        radial_terms: usize,
+        tangential: bool,
+        principal_point: bool,
+    ) -> CalibrationParameters {
+        CalibrationParameters {
+            radial_terms: radial_terms.min(3),
+            tangential,
+            principal_point,
+            ..Default::default()
+        }
+    }
+
+    //mp refine_matches
+    /// Refine the lens and an orientation (from the camera frame to the
+    /// catalog frame) by Levenberg-Marquardt, given image positions
+    /// matched to the unit vectors of their stars
+    pub fn refine_matches(
+        &self,
+        orientation: &Mat3,
+        xy: &[[f64; 2]],
+        stars: &[[f64; 3]],
+        radial_terms: usize,
+        tangential: bool,
+        principal_point: bool,
+    ) -> Result<WasmLensCalibration, JsValue> {
+        let parameters = Self::calibration_parameters(radial_terms, tangential, principal_point);
+        let calibration = calibration::refine(&self.lens, orientation, xy, stars, &parameters)
+            .ok_or("Too few stars to refine the lens")?;
+        Ok(WasmLensCalibration::new(self, calibration))
+    }
+
+    //fi projection_of_name
+    fn projection_of_name(projection: &str) -> Result<LensProjection, JsValue> {
+        LensProjection::of_name(projection)
+            .ok_or_else(|| format!("Unknown lens projection '{projection}'").into())
+    }
+
+    //ap lens
+    pub fn lens(&self) -> &Lens {
+        &self.lens
+    }
+
+    //ap size
+    /// The width and height of the image in pixels
+    pub fn size(&self) -> [f64; 2] {
+        self.size
+    }
+
+    //cp with_lens
+    /// The same lens model (image and sensor size) with a different lens
+    pub fn with_lens(&self, lens: Lens) -> Self {
+        Self {
+            lens,
+            ..self.clone()
+        }
+    }
+}
+
+//a Functions
+//fi is_positive
+/// True if a value is finite and positive (so not NaN)
+fn is_positive(value: f64) -> bool {
+    value.is_finite() && value > 0.
+}
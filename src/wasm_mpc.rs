@@ -0,0 +1,140 @@
+//a Imports
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::mpc::{self, MpcElements};
+use crate::wasm_orbit::WasmOrbit;
+
+//a WasmMpcOrbits
+//tp WasmMpcOrbits
+/// Orbits of minor planets and comets read from Minor Planet Center
+/// one-line element text (MPCORB or CometEls), keyed by designation
+///
+/// Malformed lines are skipped, with an error recorded for each
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmMpcOrbits {
+    elements: Vec<MpcElements>,
+    index: HashMap<String, usize>,
+    errors: Vec<String>,
+}
+
+//ip WasmMpcOrbits
+#[wasm_bindgen]
+impl WasmMpcOrbits {
+    //cp new
+    /// Create an empty set of orbits
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmMpcOrbits {
+        Self::default()
+    }
+
+    //cp of_mpcorb
+    /// Create from MPCORB format minor planet text
+    pub fn of_mpcorb(text: &str) -> WasmMpcOrbits {
+        let mut s = Self::new();
+        s.add_mpcorb(text);
+        s
+    }
+
+    //cp of_comet_els
+    /// Create from CometEls format comet text
+    pub fn of_comet_els(text: &str) -> WasmMpcOrbits {
+        let mut s = Self::new();
+        s.add_comet_els(text);
+        s
+    }
+
+    //mp add_mpcorb
+    /// Add orbits from MPCORB format text, returning the number added;
+    /// an orbit replaces any with the same designation
+    pub fn add_mpcorb(&mut self, text: &str) -> usize {
+        let (elements, errors) = mpc::parse_mpcorb(text);
+        self.add(elements, errors)
+    }
+
+    //mp add_comet_els
+    /// Add orbits from CometEls format text, returning the number
+    /// added; an orbit replaces any with the same designation
+    pub fn add_comet_els(&mut self, text: &str) -> usize {
+        let (elements, errors) = mpc::parse_comet_els(text);
+        self.add(elements, errors)
+    }
+
+    //ap count
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.elements.len()
+    }
+
+    //ap designations
+    /// The designations of the orbits, in the order they were added
+    #[wasm_bindgen(getter)]
+    pub fn designations(&self) -> Vec<String> {
+        self.elements
+            .iter()
+            .map(|e| e.designation.clone())
+            .collect()
+    }
+
+    //ap errors
+    /// The errors for the malformed lines of all the text added, each
+    /// prefixed by its line number
+    #[wasm_bindgen(getter)]
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.clone()
+    }
+
+    //mp clear_errors
+    pub fn clear_errors(&mut self) {
+        self.errors.clear();
+    }
+
+    //mp orbit
+    /// Get the orbit of a designation, if it is present; the orbit is
+    /// named with the designation
+    pub fn orbit(&self, designation: &str) -> Option<WasmOrbit> {
+        let e = self.elements(designation)?;
+        let mut orbit = WasmOrbit::of_kepler(e.kepler).ok()?;
+        orbit.set_name(&e.designation);
+        Some(orbit)
+    }
+
+    //mp absolute_magnitude
+    /// Get the absolute magnitude (H) of a designation, if known
+    pub fn absolute_magnitude(&self, designation: &str) -> Option<f64> {
+        self.elements(designation)?.absolute_magnitude
+    }
+
+    //mp slope
+    /// Get the magnitude slope parameter (G, or K for comets) of a
+    /// designation, if known
+    pub fn slope(&self, designation: &str) -> Option<f64> {
+        self.elements(designation)?.slope
+    }
+}
+
+//ip WasmMpcOrbits
+impl WasmMpcOrbits {
+    //mp elements
+    pub fn elements(&self, designation: &str) -> Option<&MpcElements> {
+        self.index.get(designation).map(|n| &self.elements[*n])
+    }
+
+    //mi add
+    fn add(&mut self, elements: Vec<MpcElements>, errors: Vec<String>) -> usize {
+        let n = elements.len();
+        for e in elements {
+            if let Some(i) = self.index.get(&e.designation) {
+                self.elements[*i] = e;
+            } else {
+                self.index
+                    .insert(e.designation.clone(), self.elements.len());
+                self.elements.push(e);
+            }
+        }
+        self.errors.extend(errors);
+        n
+    }
+}
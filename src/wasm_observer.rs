@@ -0,0 +1,207 @@
+//a Imports
+use geo_nd_wasm::WasmQuatf64;
+use wasm_bindgen::prelude::*;
+
+use crate::observer::Observer;
+use crate::refraction::Refraction;
+use crate::rotation;
+use crate::time::Time;
+use crate::wasm_rise_set::WasmRiseSet;
+use crate::{Vec3f64, WasmVec3f64};
+
+//a WasmObserver
+//tp WasmObserver
+/// An observer at a latitude, longitude and elevation at a time
+///
+/// The observer frame is +X north, +Y west and +Z up; the ECEF frame
+/// is that of the star catalog (J2000), or the true equator and
+/// equinox of date if 'of_date' is set (for apparent places)
+#[wasm_bindgen]
+pub struct WasmObserver {
+    observer: Observer,
+}
+
+//ip WasmObserver
+#[wasm_bindgen]
+impl WasmObserver {
+    //cp new
+    /// Create a new [WasmObserver] at a latitude and longitude in
+    /// radians and an elevation in meters, at a Unix time in seconds
+    #[wasm_bindgen(constructor)]
+    pub fn new(latitude: f64, longitude: f64, elevation: f64, unix_time: f64) -> WasmObserver {
+        let observer = Observer::new(
+            latitude,
+            longitude,
+            elevation,
+            Time::of_unix_time(unix_time),
+        );
+        Self { observer }
+    }
+
+    //mp set_position
+    /// Set the latitude and longitude in radians and elevation in meters
+    pub fn set_position(&mut self, latitude: f64, longitude: f64, elevation: f64) {
+        self.observer.set_position(latitude, longitude, elevation);
+    }
+
+    //mp set_unix_time
+    pub fn set_unix_time(&mut self, unix_time: f64) {
+        let dut1 = self.observer.time().dut1();
+        self.observer
+            .set_time(Time::of_unix_time(unix_time).with_dut1(dut1));
+    }
+
+    //mp set_dut1
+    /// Set UT1-UTC in seconds
+    pub fn set_dut1(&mut self, dut1: f64) {
+        let time = self.observer.time().with_dut1(dut1);
+        self.observer.set_time(time);
+    }
+
+    //mp set_of_date
+    /// Select whether the ECEF frame is the true equator and equinox of
+    /// date, for use with apparent places, rather than J2000
+    pub fn set_of_date(&mut self, of_date: bool) {
+        self.observer.set_of_date(of_date);
+    }
+
+    //mp set_refraction
+    /// Apply refraction for a pressure in millibars and temperature in
+    /// Celsius to altitudes; this fails for non-physical conditions,
+    /// leaving the refraction unchanged
+    pub fn set_refraction(&mut self, pressure: f64, temperature: f64) -> Result<(), JsValue> {
+        let refraction = Refraction::of_conditions(pressure, temperature)?;
+        self.observer.set_refraction(Some(refraction));
+        Ok(())
+    }
+
+    //mp clear_refraction
+    /// Stop applying refraction to altitudes
+    pub fn clear_refraction(&mut self) {
+        self.observer.set_refraction(None);
+    }
+
+    //ap latitude
+    #[wasm_bindgen(getter)]
+    pub fn latitude(&self) -> f64 {
+        self.observer.latitude()
+    }
+
+    //ap longitude
+    #[wasm_bindgen(getter)]
+    pub fn longitude(&self) -> f64 {
+        self.observer.longitude()
+    }
+
+    //ap elevation
+    #[wasm_bindgen(getter)]
+    pub fn elevation(&self) -> f64 {
+        self.observer.elevation()
+    }
+
+    //ap unix_time
+    #[wasm_bindgen(getter)]
+    pub fn unix_time(&self) -> f64 {
+        self.observer.time().unix_time()
+    }
+
+    //ap local_sidereal_time
+    /// Local apparent sidereal time in radians
+    #[wasm_bindgen(getter)]
+    pub fn local_sidereal_time(&self) -> f64 {
+        self.observer
+            .time()
+            .local_sidereal_time(self.observer.longitude())
+    }
+
+    //ap ecef_to_observer
+    /// Quaternion mapping an ECEF direction (such as a star) to the
+    /// observer frame
+    #[wasm_bindgen(getter)]
+    pub fn ecef_to_observer(&self) -> WasmQuatf64 {
+        rotation::wasm_quat_of_matrix(self.observer.ecef_to_observer())
+    }
+
+    //ap observer_to_ecef
+    /// Quaternion mapping an observer frame direction to ECEF
+    #[wasm_bindgen(getter)]
+    pub fn observer_to_ecef(&self) -> WasmQuatf64 {
+        rotation::wasm_quat_of_matrix(&self.observer.observer_to_ecef())
+    }
+
+    //ap up
+    /// The ECEF direction of the observer's zenith
+    #[wasm_bindgen(getter)]
+    pub fn up(&self) -> WasmVec3f64 {
+        self.observer.ecef_to_observer()[2].into()
+    }
+
+    //ap north
+    /// The ECEF direction north along the observer's horizon
+    #[wasm_bindgen(getter)]
+    pub fn north(&self) -> WasmVec3f64 {
+        self.observer.ecef_to_observer()[0].into()
+    }
+
+    //ap west
+    /// The ECEF direction west along the observer's horizon
+    #[wasm_bindgen(getter)]
+    pub fn west(&self) -> WasmVec3f64 {
+        self.observer.ecef_to_observer()[1].into()
+    }
+
+    //mp alt_az_of_vector
+    /// Get [altitude, azimuth] in radians of an ECEF direction; azimuth
+    /// is from north through east
+    pub fn alt_az_of_vector(&self, v: &WasmVec3f64) -> Vec<f64> {
+        let v: Vec3f64 = v.into();
+        let (alt, az) = self.observer.alt_az_of_vector(&[v[0], v[1], v[2]]);
+        vec![alt, az]
+    }
+
+    //mp vector_of_alt_az
+    /// Get the ECEF unit vector for an altitude and azimuth in radians
+    pub fn vector_of_alt_az(&self, alt: f64, az: f64) -> WasmVec3f64 {
+        self.observer.vector_of_alt_az(alt, az).into()
+    }
+
+    //mp alt_az_of_ra_de
+    /// Get [altitude, azimuth] in radians of a right ascension and
+    /// declination in radians
+    pub fn alt_az_of_ra_de(&self, ra: f64, de: f64) -> Vec<f64> {
+        let (alt, az) = self
+            .observer
+            .alt_az_of_vector(&rotation::vec_of_ra_de(ra, de));
+        vec![alt, az]
+    }
+
+    //mp rise_set_of_ra_de
+    /// Find the next rise, transits and set after the observer's time
+    /// of an object at a right ascension and declination (radians, true
+    /// equator and equinox of date)
+    ///
+    /// The horizon altitude (radians) is where the object appears to
+    /// rise and set, usually zero; refraction is included if the
+    /// observer has it
+    pub fn rise_set_of_ra_de(&self, ra: f64, de: f64, horizon_altitude: f64) -> WasmRiseSet {
+        self.observer
+            .rise_set_of_ra_de(ra, de, horizon_altitude)
+            .into()
+    }
+
+    //mp ra_de_of_alt_az
+    /// Get [right ascension, declination] in radians of an altitude and
+    /// azimuth in radians
+    pub fn ra_de_of_alt_az(&self, alt: f64, az: f64) -> Vec<f64> {
+        let (ra, de) = rotation::ra_de_of_vec(&self.observer.vector_of_alt_az(alt, az));
+        vec![ra, de]
+    }
+}
+
+//ip WasmObserver
+impl WasmObserver {
+    //ap observer
+    pub fn observer(&self) -> &Observer {
+        &self.observer
+    }
+}
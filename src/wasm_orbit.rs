@@ -1,10 +1,41 @@
+use geo_nd_wasm::geo_nd::Quaternion;
 use geo_nd_wasm::{WasmQuatf32, WasmVec3f32};
 pub use star_catalog::{Orbit, SOLAR_SYSTEM};
 
 use wasm_bindgen::prelude::*;
+
+use crate::kepler::{self, Kepler};
+use crate::moons::MoonOrbit;
+use crate::orbit_bezier::{self, Cubic};
+use crate::planet_position::PlanetPosition;
+use crate::rotation::{self, Mat3};
+use crate::wasm_planet_position::WasmPlanetPosition;
+use crate::{Quatf32, WasmBezier3f32, WasmBezierBuilder3f32, WasmVec3f64};
+
+/// Interval in seconds either side of a time used to find the velocity
+/// of a solar system orbit
+const VELOCITY_INTERVAL: f64 = 60.0;
+
+/// Number of Bezier segments per orbit before any are split to meet
+/// the error bound
+const BEZIERS_PER_ORBIT: f64 = 8.0;
+
+/// Number of Bezier segments for a window of an open orbit before any
+/// are split to meet the error bound
+const BEZIERS_PER_WINDOW: usize = 8;
+
+/// The model of an orbit: an elliptical solar system orbit, Keplerian
+/// elements of any eccentricity, or the precessing orbit of a moon
+enum OrbitModel {
+    SolarSystem(Orbit),
+    Kepler(Kepler),
+    Moon(MoonOrbit),
+}
+
 #[wasm_bindgen]
 pub struct WasmOrbit {
-    orbit: Orbit,
+    orbit: OrbitModel,
+    name: String,
 }
 
 #[wasm_bindgen]
@@ -12,45 +43,433 @@ impl WasmOrbit {
     /// Create a new [WasmOrbit]
     #[wasm_bindgen(constructor)]
     pub fn new() -> WasmOrbit {
-        let orbit = Orbit::default();
-        Self { orbit }
+        let orbit = OrbitModel::SolarSystem(Orbit::default());
+        let name = String::new();
+        Self { orbit, name }
     }
 
     /// Create a [WasmOrbit] from an element of the solar system, if it is there
     pub fn of_solar_system(name: &str) -> Option<WasmOrbit> {
         for ns in SOLAR_SYSTEM {
             if ns.0 == name {
-                let orbit: Orbit = ns.1.into();
-                return Some(Self { orbit });
+                let orbit = OrbitModel::SolarSystem(ns.1.into());
+                let name = name.into();
+                return Some(Self { orbit, name });
             }
         }
         None
     }
 
+    /// Create a [WasmOrbit] for a moon of a planet (the Moon, the
+    /// Galilean moons or Titan), if it is known
+    ///
+    /// Positions are relative to the planet in the frame of the
+    /// planets' orbits; the shape and orientation of the orbit are
+    /// those at the epoch (a Unix time in seconds), as the orbit
+    /// precesses
+    pub fn of_moon(name: &str, epoch: f64) -> Option<WasmOrbit> {
+        let orbit = OrbitModel::Moon(MoonOrbit::of_name(name, epoch)?);
+        let name = name.into();
+        Some(Self { orbit, name })
+    }
+
+    /// Create a [WasmOrbit] from Keplerian elements of any eccentricity
+    ///
+    /// The periapsis distance is in meters, angles in radians, the time
+    /// of periapsis a Unix time in seconds, and the gravitational
+    /// parameter of the parent in m^3/s^2 (if zero, that of the Sun)
+    pub fn of_elements(
+        periapsis: f64,
+        eccentricity: f64,
+        inclination: f64,
+        ascending_node: f64,
+        arg_periapsis: f64,
+        periapsis_time: f64,
+        gm: f64,
+    ) -> Result<WasmOrbit, JsValue> {
+        let gm = if gm == 0. { kepler::GM_SUN } else { gm };
+        let kepler = Kepler::new(
+            periapsis,
+            eccentricity,
+            inclination,
+            ascending_node,
+            arg_periapsis,
+            periapsis_time,
+            gm,
+        );
+        Self::of_kepler(kepler)
+    }
+
+    /// Create a [WasmOrbit] from a semi-major axis (meters, negative for
+    /// a hyperbolic orbit), eccentricity, angles in radians and the mean
+    /// anomaly at an epoch (a Unix time in seconds)
+    ///
+    /// The gravitational parameter of the parent is in m^3/s^2 (if
+    /// zero, that of the Sun)
+    #[allow(clippy::too_many_arguments)]
+    pub fn of_mean_elements(
+        semi_major_axis: f64,
+        eccentricity: f64,
+        inclination: f64,
+        ascending_node: f64,
+        arg_periapsis: f64,
+        mean_anomaly: f64,
+        epoch: f64,
+        gm: f64,
+    ) -> Result<WasmOrbit, JsValue> {
+        if (eccentricity < 1.) != (semi_major_axis > 0.) {
+            return Err(format!(
+                "Semi-major axis {semi_major_axis} is inconsistent with eccentricity {eccentricity}"
+            )
+            .into());
+        }
+        let gm = if gm == 0. { kepler::GM_SUN } else { gm };
+        let kepler = Kepler::of_mean_anomaly(
+            semi_major_axis,
+            eccentricity,
+            inclination,
+            ascending_node,
+            arg_periapsis,
+            mean_anomaly,
+            epoch,
+            gm,
+        );
+        Self::of_kepler(kepler)
+    }
+
+    /// The name of the body in the orbit, if it has one
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Set the name of the body in the orbit
+    #[wasm_bindgen(setter)]
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.into();
+    }
+
+    /// The eccentricity of the orbit, if it is given by Keplerian
+    /// elements
+    pub fn eccentricity(&self) -> Option<f64> {
+        self.kepler().map(|k| k.eccentricity)
+    }
+
+    /// The name of the planet that the body orbits, if it is a moon
+    pub fn parent_name(&self) -> Option<String> {
+        match &self.orbit {
+            OrbitModel::Moon(m) => Some(m.parent().into()),
+            _ => None,
+        }
+    }
+
+    /// The period of the orbit in seconds; infinite for parabolic and
+    /// hyperbolic orbits
     pub fn period_of_orbit(&self) -> f64 {
-        self.orbit.period_of_orbit()
+        match &self.orbit {
+            OrbitModel::SolarSystem(o) => o.period_of_orbit(),
+            OrbitModel::Kepler(k) => k.period(),
+            OrbitModel::Moon(m) => m.period(),
+        }
     }
 
     pub fn orbit_to_parent(&self) -> WasmQuatf32 {
-        self.orbit.orbit_to_parent().into()
+        match &self.orbit {
+            OrbitModel::SolarSystem(o) => o.orbit_to_parent().into(),
+            OrbitModel::Kepler(k) => quat_of_matrix(&k.orbit_to_parent()).into(),
+            OrbitModel::Moon(m) => quat_of_matrix(&m.kepler().orbit_to_parent()).into(),
+        }
     }
 
     pub fn parent_to_orbit(&self) -> WasmQuatf32 {
-        self.orbit.parent_to_orbit().into()
+        match &self.orbit {
+            OrbitModel::SolarSystem(o) => o.parent_to_orbit().into(),
+            OrbitModel::Kepler(k) => {
+                quat_of_matrix(&rotation::transpose(&k.orbit_to_parent())).into()
+            }
+            OrbitModel::Moon(m) => {
+                quat_of_matrix(&rotation::transpose(&m.kepler().orbit_to_parent())).into()
+            }
+        }
     }
 
     pub fn perigee_distance(&self) -> f64 {
-        self.orbit.perigee_distance()
+        match &self.orbit {
+            OrbitModel::SolarSystem(o) => o.perigee_distance(),
+            OrbitModel::Kepler(k) => k.periapsis,
+            OrbitModel::Moon(m) => m.kepler().periapsis,
+        }
     }
+
+    /// The furthest distance of the orbit; infinite for parabolic and
+    /// hyperbolic orbits
     pub fn apogee_distance(&self) -> f64 {
-        self.orbit.apogee_distance()
+        match &self.orbit {
+            OrbitModel::SolarSystem(o) => o.apogee_distance(),
+            OrbitModel::Kepler(k) => k.apoapsis(),
+            OrbitModel::Moon(m) => m.kepler().apoapsis(),
+        }
     }
+
     pub fn orbit_vec_of_unix_time(&self, time_secs: f64, v: &mut WasmVec3f32) {
-        let f = self.orbit.orbit_vec_of_unix_time(time_secs as i64);
-        *v = [f[0], f[1], 0.0].into();
+        let f = self.orbit_vec_of_time(time_secs);
+        *v = [f[0] as f32, f[1] as f32, 0.0].into();
     }
+
     pub fn orbit_vec_of_true_anomaly(&self, true_anomaly: f64, v: &mut WasmVec3f32) {
-        let f = self.orbit.orbit_vec_of_true_anomlay(true_anomaly);
-        *v = [f[0], f[1], 0.0].into();
+        match &self.orbit {
+            OrbitModel::SolarSystem(o) => {
+                let f = o.orbit_vec_of_true_anomlay(true_anomaly);
+                *v = [f[0], f[1], 0.0].into();
+            }
+            OrbitModel::Kepler(k) => {
+                let f = k.orbit_vec_of_true_anomaly(true_anomaly);
+                *v = [f[0] as f32, f[1] as f32, 0.0].into();
+            }
+            OrbitModel::Moon(m) => {
+                let f = m.kepler().orbit_vec_of_true_anomaly(true_anomaly);
+                *v = [f[0] as f32, f[1] as f32, 0.0].into();
+            }
+        }
     }
+
+    /// Get a whole orbit, starting at a Unix time, as cubic Bezier
+    /// segments each within 'max_error' meters of the orbit
+    ///
+    /// The segments are in the plane of the orbit, or if 'to_parent' is
+    /// set they are transformed by [WasmOrbit::orbit_to_parent] into
+    /// the frame of the parent. The parameter of each segment is
+    /// proportional to time, so segments are shorter where the body
+    /// moves faster
+    pub fn orbit_beziers(
+        &self,
+        start: f64,
+        max_error: f64,
+        to_parent: bool,
+    ) -> Result<Vec<WasmBezier3f32>, JsValue> {
+        let period = self.period_of_orbit();
+        if !period.is_finite() {
+            return Err(format!("The orbit of '{}' is not closed", self.name).into());
+        }
+        self.window_beziers(start, start + period, max_error, to_parent)
+    }
+
+    /// Get the orbit between two Unix times as cubic Bezier segments
+    /// each within 'max_error' meters of the orbit
+    ///
+    /// The segments are in the plane of the orbit, or if 'to_parent' is
+    /// set they are transformed by [WasmOrbit::orbit_to_parent] into
+    /// the frame of the parent
+    ///
+    /// An error is returned if the orbit cannot be approximated within
+    /// 'max_error' by a reasonable number of segments
+    pub fn window_beziers(
+        &self,
+        start: f64,
+        end: f64,
+        max_error: f64,
+        to_parent: bool,
+    ) -> Result<Vec<WasmBezier3f32>, JsValue> {
+        Ok(self
+            .cubics_of_window(start, end, max_error, to_parent)?
+            .iter()
+            .map(|(_, _, c)| bezier_of_cubic(c))
+            .collect())
+    }
+
+    /// Get the orbit between two Unix times as cubic Bezier segments,
+    /// as for [WasmOrbit::window_beziers], returning just the control
+    /// points
+    ///
+    /// Each segment has 16 values, being its four control points each
+    /// as (x, y, z, 0), as used for the control point matrix of the
+    /// WebGL Bezier shader
+    pub fn window_bezier_control_points(
+        &self,
+        start: f64,
+        end: f64,
+        max_error: f64,
+        to_parent: bool,
+    ) -> Result<Vec<f32>, JsValue> {
+        let mut result = vec![];
+        for (_, _, c) in self.cubics_of_window(start, end, max_error, to_parent)? {
+            for p in c {
+                result.extend([p[0] as f32, p[1] as f32, p[2] as f32, 0.]);
+            }
+        }
+        Ok(result)
+    }
+
+    /// The position of the body relative to its parent at a Unix time,
+    /// in meters in the frame of the parent
+    pub fn position_of_unix_time(&self, time_secs: f64) -> WasmVec3f64 {
+        self.parent_vec_of_unix_time(time_secs).into()
+    }
+
+    /// The velocity of the body relative to its parent at a Unix time,
+    /// in meters per second in the frame of the parent
+    pub fn velocity_of_unix_time(&self, time_secs: f64) -> WasmVec3f64 {
+        self.state_of_time(time_secs).1.into()
+    }
+
+    /// The state vector of the body relative to its parent at a Unix
+    /// time, as [x, y, z, vx, vy, vz] in meters and meters per second
+    pub fn state_of_unix_time(&self, time_secs: f64) -> Vec<f64> {
+        let (p, v) = self.state_of_time(time_secs);
+        vec![p[0], p[1], p[2], v[0], v[1], v[2]]
+    }
+
+    /// Get the position of the body as seen from the Earth at a Unix
+    /// time, given the orbit of the Earth
+    pub fn geocentric_position(&self, earth: &WasmOrbit, unix_time: f64) -> WasmPlanetPosition {
+        PlanetPosition::of_heliocentric(
+            |t| earth.parent_vec_of_unix_time(t),
+            |t| self.parent_vec_of_unix_time(t),
+            unix_time,
+            &self.name,
+        )
+        .into()
+    }
+
+    /// Get the position of a body of the solar system as seen from the
+    /// Earth at a Unix time, if the body is there
+    pub fn planet_position(name: &str, unix_time: f64) -> Option<WasmPlanetPosition> {
+        let earth = Self::of_solar_system("Earth")?;
+        let target = Self::of_solar_system(name)?;
+        Some(target.geocentric_position(&earth, unix_time))
+    }
+}
+
+impl WasmOrbit {
+    /// Create a [WasmOrbit] from Keplerian elements, checking them
+    pub fn of_kepler(kepler: Kepler) -> Result<WasmOrbit, JsValue> {
+        kepler.validate()?;
+        let orbit = OrbitModel::Kepler(kepler);
+        let name = String::new();
+        Ok(Self { orbit, name })
+    }
+
+    /// The Keplerian elements of the orbit, if it is given by them; for
+    /// a moon, those at its epoch
+    pub fn kepler(&self) -> Option<&Kepler> {
+        match &self.orbit {
+            OrbitModel::Kepler(k) => Some(k),
+            OrbitModel::Moon(m) => Some(m.kepler()),
+            _ => None,
+        }
+    }
+
+    /// The matrix taking vectors in the plane of the orbit to the frame
+    /// of the parent
+    pub fn orbit_to_parent_matrix(&self) -> Mat3 {
+        match &self.orbit {
+            OrbitModel::SolarSystem(o) => {
+                let (r, i, j, k) = o.orbit_to_parent().as_rijk();
+                rotation::matrix_of_rijk((r as f64, i as f64, j as f64, k as f64))
+            }
+            OrbitModel::Kepler(k) => k.orbit_to_parent(),
+            OrbitModel::Moon(m) => m.kepler().orbit_to_parent(),
+        }
+    }
+
+    /// The position of the body in the plane of its orbit (in meters)
+    /// at a Unix time; for a moon this is in the plane of its orbit at
+    /// its epoch
+    pub fn orbit_vec_of_time(&self, time_secs: f64) -> [f64; 3] {
+        match &self.orbit {
+            OrbitModel::SolarSystem(o) => {
+                let f = o.orbit_vec_of_unix_time(time_secs as i64);
+                [f[0] as f64, f[1] as f64, 0.]
+            }
+            OrbitModel::Kepler(k) => k.orbit_vec_of_true_anomaly(k.true_anomaly_of_time(time_secs)),
+            OrbitModel::Moon(m) => {
+                let k = m.kepler();
+                k.orbit_vec_of_true_anomaly(k.true_anomaly_of_time(time_secs))
+            }
+        }
+    }
+
+    /// The position of the body relative to its parent (in meters, in
+    /// the frame of the parent) at a Unix time
+    pub fn parent_vec_of_unix_time(&self, time_secs: f64) -> [f64; 3] {
+        match &self.orbit {
+            OrbitModel::SolarSystem(_) => rotation::apply(
+                &self.orbit_to_parent_matrix(),
+                &self.orbit_vec_of_time(time_secs),
+            ),
+            OrbitModel::Kepler(k) => k.state_of_time(time_secs).0,
+            OrbitModel::Moon(m) => m.position_of_time(time_secs),
+        }
+    }
+
+    /// The position (meters) and velocity (meters per second) of the
+    /// body relative to its parent at a Unix time
+    ///
+    /// The velocity of a solar system orbit is found by differencing
+    /// its positions
+    pub fn state_of_time(&self, time_secs: f64) -> ([f64; 3], [f64; 3]) {
+        match &self.orbit {
+            OrbitModel::SolarSystem(_) => {
+                let before = self.parent_vec_of_unix_time(time_secs - VELOCITY_INTERVAL);
+                let after = self.parent_vec_of_unix_time(time_secs + VELOCITY_INTERVAL);
+                let v = rotation::scale(&rotation::sub(&after, &before), 0.5 / VELOCITY_INTERVAL);
+                (self.parent_vec_of_unix_time(time_secs), v)
+            }
+            OrbitModel::Kepler(k) => k.state_of_time(time_secs),
+            OrbitModel::Moon(m) => m.state_of_time(time_secs),
+        }
+    }
+
+    /// Approximate the orbit between two Unix times by cubic Bezier
+    /// segments, in the plane of the orbit or the frame of the parent
+    pub fn cubics_of_window(
+        &self,
+        start: f64,
+        end: f64,
+        max_error: f64,
+        to_parent: bool,
+    ) -> Result<Vec<(f64, f64, Cubic)>, String> {
+        let period = self.period_of_orbit();
+        let initial_segments = {
+            if period.is_finite() {
+                ((end - start).abs() / period * BEZIERS_PER_ORBIT).ceil() as usize
+            } else {
+                BEZIERS_PER_WINDOW
+            }
+        };
+        let orbit_to_parent = self.orbit_to_parent_matrix();
+        orbit_bezier::cubics_of_path(
+            |t| {
+                let v = self.orbit_vec_of_time(t);
+                if to_parent {
+                    rotation::apply(&orbit_to_parent, &v)
+                } else {
+                    v
+                }
+            },
+            start,
+            end,
+            initial_segments,
+            max_error,
+        )
+    }
+}
+
+/// Create a [WasmBezier3f32] from the control points of a cubic
+fn bezier_of_cubic(c: &Cubic) -> WasmBezier3f32 {
+    let mut builder = WasmBezierBuilder3f32::new();
+    for i in 0..4 {
+        let u = i as f64 / 3.;
+        let p = orbit_bezier::point_of_cubic(c, u);
+        builder.add_vec_pt_at(u as f32, &[p[0] as f32, p[1] as f32, p[2] as f32].into());
+    }
+    let mut bezier = WasmBezier3f32::new();
+    bezier.reconstruct(&builder);
+    bezier
+}
+
+/// Create a [Quatf32] that rotates vectors as the matrix does
+fn quat_of_matrix(m: &Mat3) -> Quatf32 {
+    let (r, i, j, k) = rotation::rijk_of_matrix(m);
+    Quatf32::of_rijk(r as f32, i as f32, j as f32, k as f32)
 }
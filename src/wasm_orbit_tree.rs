@@ -0,0 +1,329 @@
+//a Imports
+use std::collections::HashMap;
+
+use geo_nd_wasm::WasmQuatf32;
+use wasm_bindgen::prelude::*;
+
+use crate::moons::MOONS;
+use crate::planet_position::PlanetPosition;
+use crate::rotation;
+use crate::wasm_orbit::{WasmOrbit, SOLAR_SYSTEM};
+use crate::wasm_planet_position::WasmPlanetPosition;
+use crate::WasmVec3f64;
+
+//a OrbitNode
+//ti OrbitNode
+/// A body in the tree, with its orbit about its parent (none for the
+/// root)
+struct OrbitNode {
+    name: String,
+    orbit: Option<WasmOrbit>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+//a WasmOrbitTree
+//tp WasmOrbitTree
+/// A tree of named bodies, each orbiting its parent, with the root
+/// (usually the Sun) fixed at the origin
+///
+/// Each orbit gives positions relative to its parent in the frame of
+/// the planets' orbits (the J2000 ecliptic), so the position of a body
+/// relative to the root (its heliocentric position) is the sum of the
+/// positions along its path from the root
+#[wasm_bindgen]
+pub struct WasmOrbitTree {
+    nodes: Vec<OrbitNode>,
+    index: HashMap<String, usize>,
+}
+
+//ip WasmOrbitTree
+#[wasm_bindgen]
+impl WasmOrbitTree {
+    //cp new
+    /// Create a tree with just a named root
+    #[wasm_bindgen(constructor)]
+    pub fn new(root: &str) -> WasmOrbitTree {
+        let mut s = Self {
+            nodes: vec![],
+            index: HashMap::new(),
+        };
+        s.insert(root, None, None);
+        s
+    }
+
+    //cp of_solar_system
+    /// Create a tree of the Sun, the planets of the solar system, and
+    /// the known moons of those planets, with the shapes of the moons'
+    /// orbits at an epoch (a Unix time in seconds)
+    pub fn of_solar_system(epoch: f64) -> WasmOrbitTree {
+        let mut s = Self::new("Sun");
+        for (name, _) in SOLAR_SYSTEM {
+            if let Some(orbit) = WasmOrbit::of_solar_system(name) {
+                let _ = s.add("Sun", orbit);
+            }
+        }
+        for m in MOONS.iter() {
+            if let Some(orbit) = WasmOrbit::of_moon(m.name, epoch) {
+                let _ = s.add(m.parent, orbit);
+            }
+        }
+        s
+    }
+
+    //mp add
+    /// Add a named orbit as a child of a body in the tree; the name must
+    /// not already be in the tree
+    pub fn add(&mut self, parent: &str, orbit: WasmOrbit) -> Result<(), JsValue> {
+        let name = orbit.name();
+        if name.is_empty() {
+            return Err("Orbits added to a tree must be named".into());
+        }
+        if self.index.contains_key(&name) {
+            return Err(format!("Body '{name}' is already in the tree").into());
+        }
+        let parent = self.index_of(parent)?;
+        self.insert(&name, Some(orbit), Some(parent));
+        Ok(())
+    }
+
+    //ap root
+    /// The name of the root of the tree
+    #[wasm_bindgen(getter)]
+    pub fn root(&self) -> String {
+        self.nodes[0].name.clone()
+    }
+
+    //ap names
+    /// The names of all the bodies in the tree, in the order added
+    #[wasm_bindgen(getter)]
+    pub fn names(&self) -> Vec<String> {
+        self.nodes.iter().map(|n| n.name.clone()).collect()
+    }
+
+    //mp contains
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    //mp parent
+    /// The name of the parent of a body, or undefined for the root or
+    /// a body not in the tree
+    pub fn parent(&self, name: &str) -> Option<String> {
+        let n = *self.index.get(name)?;
+        self.nodes[n].parent.map(|p| self.nodes[p].name.clone())
+    }
+
+    //mp children
+    /// The names of the children of a body, empty if it has none or is
+    /// not in the tree
+    pub fn children(&self, name: &str) -> Vec<String> {
+        let Some(n) = self.index.get(name) else {
+            return vec![];
+        };
+        self.nodes[*n]
+            .children
+            .iter()
+            .map(|c| self.nodes[*c].name.clone())
+            .collect()
+    }
+
+    //mp path
+    /// The names of the bodies from the root down to a body, empty if
+    /// it is not in the tree
+    pub fn path(&self, name: &str) -> Vec<String> {
+        let mut path = vec![];
+        let mut n = self.index.get(name).copied();
+        while let Some(i) = n {
+            path.push(self.nodes[i].name.clone());
+            n = self.nodes[i].parent;
+        }
+        path.reverse();
+        path
+    }
+
+    //mp period_of_orbit
+    /// The period in seconds of the orbit of a body about its parent
+    pub fn period_of_orbit(&self, name: &str) -> Result<f64, JsValue> {
+        Ok(self.orbit(name)?.period_of_orbit())
+    }
+
+    //mp orbit_to_parent
+    /// The rotation from the plane of the orbit of a body to the frame
+    /// of its parent
+    pub fn orbit_to_parent(&self, name: &str) -> Result<WasmQuatf32, JsValue> {
+        Ok(self.orbit(name)?.orbit_to_parent())
+    }
+
+    //mp position_of_unix_time
+    /// The position of a body relative to its parent at a Unix time, in
+    /// meters
+    pub fn position_of_unix_time(
+        &self,
+        name: &str,
+        unix_time: f64,
+    ) -> Result<WasmVec3f64, JsValue> {
+        Ok(self.orbit(name)?.position_of_unix_time(unix_time))
+    }
+
+    //mp heliocentric_position
+    /// The position of a body relative to the root at a Unix time, in
+    /// meters
+    pub fn heliocentric_position(
+        &self,
+        name: &str,
+        unix_time: f64,
+    ) -> Result<WasmVec3f64, JsValue> {
+        let n = self.index_of(name)?;
+        Ok(self.heliocentric_vec_of_unix_time(n, unix_time).into())
+    }
+
+    //mp heliocentric_state
+    /// The state vector of a body relative to the root at a Unix time,
+    /// as [x, y, z, vx, vy, vz] in meters and meters per second
+    pub fn heliocentric_state(&self, name: &str, unix_time: f64) -> Result<Vec<f64>, JsValue> {
+        let n = self.index_of(name)?;
+        let (p, v) = self.heliocentric_state_of_time(n, unix_time);
+        Ok(vec![p[0], p[1], p[2], v[0], v[1], v[2]])
+    }
+
+    //mp relative_position
+    /// The position of a body relative to another at a Unix time, in
+    /// meters
+    pub fn relative_position(
+        &self,
+        name: &str,
+        from: &str,
+        unix_time: f64,
+    ) -> Result<WasmVec3f64, JsValue> {
+        let n = self.index_of(name)?;
+        let f = self.index_of(from)?;
+        let p = rotation::sub(
+            &self.heliocentric_vec_of_unix_time(n, unix_time),
+            &self.heliocentric_vec_of_unix_time(f, unix_time),
+        );
+        Ok(p.into())
+    }
+
+    //mp geocentric_position
+    /// Get the position of a body as seen from the body named "Earth"
+    /// at a Unix time, allowing for light time
+    pub fn geocentric_position(
+        &self,
+        name: &str,
+        unix_time: f64,
+    ) -> Result<WasmPlanetPosition, JsValue> {
+        let n = self.index_of(name)?;
+        let earth = self.index_of("Earth")?;
+        Ok(PlanetPosition::of_heliocentric(
+            |t| self.heliocentric_vec_of_unix_time(earth, t),
+            |t| self.heliocentric_vec_of_unix_time(n, t),
+            unix_time,
+            name,
+        )
+        .into())
+    }
+}
+
+//ip WasmOrbitTree
+impl WasmOrbitTree {
+    //mi insert
+    fn insert(&mut self, name: &str, orbit: Option<WasmOrbit>, parent: Option<usize>) {
+        let n = self.nodes.len();
+        self.nodes.push(OrbitNode {
+            name: name.into(),
+            orbit,
+            parent,
+            children: vec![],
+        });
+        if let Some(p) = parent {
+            self.nodes[p].children.push(n);
+        }
+        self.index.insert(name.into(), n);
+    }
+
+    //mp index_of
+    /// The index of a named body in the tree
+    pub fn index_of(&self, name: &str) -> Result<usize, String> {
+        self.index
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("Body '{name}' is not in the tree"))
+    }
+
+    //mp orbit
+    /// The orbit of a named body about its parent
+    pub fn orbit(&self, name: &str) -> Result<&WasmOrbit, String> {
+        self.nodes[self.index_of(name)?]
+            .orbit
+            .as_ref()
+            .ok_or_else(|| format!("Body '{name}' is the root of the tree"))
+    }
+
+    //mp heliocentric_vec_of_unix_time
+    /// The position (meters) of the body with an index relative to the
+    /// root at a Unix time
+    pub fn heliocentric_vec_of_unix_time(&self, mut n: usize, unix_time: f64) -> [f64; 3] {
+        let mut p = [0.; 3];
+        while let Some(orbit) = &self.nodes[n].orbit {
+            p = rotation::add(&p, &orbit.parent_vec_of_unix_time(unix_time));
+            let Some(parent) = self.nodes[n].parent else {
+                break;
+            };
+            n = parent;
+        }
+        p
+    }
+
+    //mp heliocentric_state_of_time
+    /// The position (meters) and velocity (meters per second) of the
+    /// body with an index relative to the root at a Unix time
+    pub fn heliocentric_state_of_time(&self, mut n: usize, unix_time: f64) -> ([f64; 3], [f64; 3]) {
+        let mut p = [0.; 3];
+        let mut v = [0.; 3];
+        while let Some(orbit) = &self.nodes[n].orbit {
+            let (dp, dv) = orbit.state_of_time(unix_time);
+            p = rotation::add(&p, &dp);
+            v = rotation::add(&v, &dv);
+            let Some(parent) = self.nodes[n].parent else {
+                break;
+            };
+            n = parent;
+        }
+        (p, v)
+    }
+}
+
+//a Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moons;
+    use crate::planet_position::METERS_PER_AU;
+
+    #[test]
+    fn moon_of_earth() {
+        // The Moon's heliocentric position is the Earth's (here on a
+        // circular orbit) plus its geocentric position from the lunar
+        // theory
+        let t = 1_700_000_000.;
+        let mut tree = WasmOrbitTree::new("Sun");
+        let mut earth = WasmOrbit::of_elements(METERS_PER_AU, 0., 0., 0., 0., t, 0.).unwrap();
+        earth.set_name("Earth");
+        tree.add("Sun", earth).unwrap();
+        tree.add("Earth", WasmOrbit::of_moon("Moon", t).unwrap())
+            .unwrap();
+        assert_eq!(tree.path("Moon"), ["Sun", "Earth", "Moon"]);
+        let earth = tree.index_of("Earth").unwrap();
+        let moon = tree.index_of("Moon").unwrap();
+        for dt in [0., 0.25e6, 3.1e7] {
+            let e = tree.heliocentric_vec_of_unix_time(earth, t + dt);
+            let m = tree.heliocentric_vec_of_unix_time(moon, t + dt);
+            let geocentric = moons::lunar_position_of_time(t + dt);
+            let d = rotation::length(&rotation::sub(&rotation::sub(&m, &e), &geocentric));
+            assert!(d < 1e-3, "{d}");
+            let r = rotation::length(&geocentric);
+            assert!(r > 356_000e3 && r < 407_000e3, "{r}");
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//a Imports
+use wasm_bindgen::prelude::*;
+
+use crate::planet_position::PlanetPosition;
+use crate::rotation;
+use crate::WasmVec3f64;
+
+//a WasmPlanetPosition
+//tp WasmPlanetPosition
+/// The position of a planet as seen from the Earth at a time, in the
+/// J2000 equatorial frame of the star catalog
+#[wasm_bindgen]
+pub struct WasmPlanetPosition {
+    position: PlanetPosition,
+}
+
+//ip WasmPlanetPosition
+#[wasm_bindgen]
+impl WasmPlanetPosition {
+    //ap ra
+    /// J2000 right ascension in radians
+    #[wasm_bindgen(getter)]
+    pub fn ra(&self) -> f64 {
+        self.position.ra_de().0
+    }
+
+    //ap de
+    /// J2000 declination in radians
+    #[wasm_bindgen(getter)]
+    pub fn de(&self) -> f64 {
+        self.position.ra_de().1
+    }
+
+    //ap vector
+    /// The (light time corrected) direction of the planet
+    #[wasm_bindgen(getter)]
+    pub fn vector(&self) -> WasmVec3f64 {
+        rotation::normalize(&self.position.position).into()
+    }
+
+    //ap position
+    /// The light time corrected geocentric position in AU
+    #[wasm_bindgen(getter)]
+    pub fn position(&self) -> WasmVec3f64 {
+        self.position.position.into()
+    }
+
+    //ap geometric_position
+    /// The geocentric position at the time, without light time
+    /// correction, in AU
+    #[wasm_bindgen(getter)]
+    pub fn geometric_position(&self) -> WasmVec3f64 {
+        self.position.geometric.into()
+    }
+
+    //ap distance
+    /// The distance from the Earth in AU
+    #[wasm_bindgen(getter)]
+    pub fn distance(&self) -> f64 {
+        self.position.distance()
+    }
+
+    //ap sun_distance
+    /// The distance from the Sun in AU
+    #[wasm_bindgen(getter)]
+    pub fn sun_distance(&self) -> f64 {
+        self.position.sun_distance
+    }
+
+    //ap light_time
+    /// The light time in seconds
+    #[wasm_bindgen(getter)]
+    pub fn light_time(&self) -> f64 {
+        self.position.light_time
+    }
+
+    //ap phase_angle
+    /// The angle Sun - planet - Earth in radians
+    #[wasm_bindgen(getter)]
+    pub fn phase_angle(&self) -> f64 {
+        self.position.phase_angle
+    }
+
+    //ap illuminated_fraction
+    #[wasm_bindgen(getter)]
+    pub fn illuminated_fraction(&self) -> f64 {
+        self.position.illuminated_fraction()
+    }
+
+    //ap magnitude
+    /// The apparent magnitude, or undefined if it is not known for the
+    /// body
+    #[wasm_bindgen(getter)]
+    pub fn magnitude(&self) -> Option<f64> {
+        self.position.magnitude
+    }
+}
+
+//ip From<PlanetPosition> for WasmPlanetPosition
+impl From<PlanetPosition> for WasmPlanetPosition {
+    fn from(position: PlanetPosition) -> Self {
+        Self { position }
+    }
+}
@@ -0,0 +1,430 @@
+//a Imports
+use geo_nd_wasm::WasmQuatf64;
+use wasm_bindgen::prelude::*;
+
+use star_catalog::{Catalog, CatalogIndex, StarTriangleSearch, Subcube};
+
+use crate::lens::FULL_FRAME_WIDTH;
+use crate::plate_solve::{self, PlateSolution, SolveParameters, StarSearch};
+use crate::rotation;
+use crate::wasm_calibration::WasmLensCalibration;
+use crate::wasm_catalog::WasmCatalog;
+use crate::wasm_lens::WasmLensModel;
+use crate::{Vec3f64, WasmVec3f64};
+
+//a Constants
+/// Most steps of a catalog triangle search
+const MAX_TRIANGLE_ITERATIONS: usize = 10 * 1000 * 1000;
+
+//a CatalogSearch
+//ti CatalogSearch
+/// The stars of a [WasmCatalog] that pass its current filter and are no
+/// fainter than a maximum magnitude, as a catalog of their own searched
+/// for plate solving
+///
+/// The stars are at their J2000 catalog positions, as used by the
+/// catalog searches
+struct CatalogSearch {
+    catalog: Catalog,
+    /// Index in the [WasmCatalog] of each star of the catalog
+    indices: Vec<usize>,
+    density: f64,
+}
+
+//ii CatalogSearch
+impl CatalogSearch {
+    //ci new
+    fn new(catalog: &WasmCatalog, max_magnitude: f32) -> Self {
+        // The catalog is sorted, so its stars added in order stay in
+        // order when the bright catalog is sorted
+        let mut bright = Catalog::default();
+        let mut indices = vec![];
+        {
+            let stars = catalog.catalog();
+            for index in catalog.filtered_indices() {
+                if stars[index].magnitude() <= max_magnitude {
+                    bright.add_star(stars[index].clone());
+                    indices.push(index.as_usize());
+                }
+            }
+        }
+        bright.sort();
+        bright.derive_data();
+        let density = bright.len() as f64 / (4. * std::f64::consts::PI);
+        Self {
+            catalog: bright,
+            indices,
+            density,
+        }
+    }
+}
+
+//ii StarSearch for CatalogSearch
+impl StarSearch for CatalogSearch {
+    fn triangles(
+        &self,
+        angles: [f64; 3],
+        tolerance: f64,
+        max_triangles: usize,
+    ) -> Result<Vec<[usize; 3]>, String> {
+        let Ok(search) = StarTriangleSearch::of_angles(angles, tolerance) else {
+            return Ok(vec![]);
+        };
+        let (finished, candidates) =
+            self.catalog
+                .find_star_triangles(Subcube::iter_all(), &search, MAX_TRIANGLE_ITERATIONS);
+        if !finished {
+            return Err(format!(
+                "Search for star triangles did not finish with {} stars; use a brighter maximum magnitude",
+                self.catalog.len()
+            ));
+        }
+        Ok(candidates
+            .iter()
+            .map(|tm| {
+                let t = tm.triangle();
+                [t.0.as_usize(), t.1.as_usize(), t.2.as_usize()]
+            })
+            .take(max_triangles)
+            .collect())
+    }
+
+    fn stars_around(&self, v: &[f64; 3], angle: f64) -> Vec<usize> {
+        let v: Vec3f64 = (*v).into();
+        self.catalog
+            .find_stars_around(&v, angle)
+            .into_iter()
+            .map(|index| index.as_usize())
+            .collect()
+    }
+
+    fn vector(&self, star: usize) -> [f64; 3] {
+        let index: CatalogIndex = star.into();
+        let v = *self.catalog[index].vector();
+        [v[0], v[1], v[2]]
+    }
+
+    fn id(&self, star: usize) -> usize {
+        let index: CatalogIndex = star.into();
+        self.catalog[index].id()
+    }
+
+    fn density(&self) -> f64 {
+        self.density
+    }
+}
+
+//a WasmPlateSolver
+//tp WasmPlateSolver
+/// A blind plate solver for images taken with a lens model (its
+/// projection, principal point and aspect ratio) with a focal length
+/// within a range
+///
+/// The image points to solve are the centroids of sources in image
+/// pixels, brightest first (as from [crate::WasmImageSources::xy])
+#[wasm_bindgen]
+pub struct WasmPlateSolver {
+    lens_model: WasmLensModel,
+    parameters: SolveParameters,
+    max_magnitude: f32,
+}
+
+//ip WasmPlateSolver
+#[wasm_bindgen]
+impl WasmPlateSolver {
+    //cp new
+    /// Create a solver for a lens model (whose focal length is ignored)
+    /// and the range of the 35mm equivalent focal length of the lens in
+    /// mm
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        lens_model: &WasmLensModel,
+        min_mm_equiv: f64,
+        max_mm_equiv: f64,
+    ) -> Result<WasmPlateSolver, JsValue> {
+        if min_mm_equiv <= 0. || max_mm_equiv <= 0. {
+            return Err("Focal lengths must be positive".into());
+        }
+        let size = lens_model.size();
+        let focal_length = [min_mm_equiv, max_mm_equiv].map(|f| f * size[0] / FULL_FRAME_WIDTH);
+        Ok(Self {
+            lens_model: lens_model.clone(),
+            parameters: SolveParameters::new(*lens_model.lens(), size, focal_length),
+            max_magnitude: 6.0,
+        })
+    }
+
+    //mp set_max_magnitude
+    /// Set the faintest catalog stars used; these should be about as
+    /// faint as the faintest sources
+    pub fn set_max_magnitude(&mut self, magnitude: f32) {
+        self.max_magnitude = magnitude;
+    }
+
+    //mp set_tolerance
+    /// Set the tolerance in radians on the angles between sources
+    pub fn set_tolerance(&mut self, tolerance: f64) -> Result<(), JsValue> {
+        if tolerance.is_nan() || tolerance <= 0. {
+            return Err(format!("Tolerance must be positive, not {tolerance}").into());
+        }
+        self.parameters.tolerance = tolerance;
+        Ok(())
+    }
+
+    //mp set_match_radius
+    /// Set the greatest distance in pixels between a source and the
+    /// image position of its star
+    pub fn set_match_radius(&mut self, match_radius: f64) {
+        self.parameters.match_radius = match_radius;
+    }
+
+    //mp set_triangles
+    /// Set the number of the brightest sources from which triangles are
+    /// formed, and the most triangles searched for
+    pub fn set_triangles(&mut self, sources: usize, max_triangles: usize) {
+        self.parameters.triangle_sources = sources.max(3);
+        self.parameters.max_triangles = max_triangles;
+    }
+
+    //mp set_min_confidence
+    /// Set the confidence at which the search stops
+    pub fn set_min_confidence(&mut self, confidence: f64) {
+        self.parameters.min_confidence = confidence;
+    }
+
+    //mp solve
+    /// Solve for the orientation of the camera and the focal length of
+    /// the lens, given image points as x0, y0, x1, y1, ...
+    ///
+    /// Only the catalog stars that pass its filter and are no fainter
+    /// than the maximum magnitude are used; if there are too many for
+    /// the searches of the catalog to finish, this is an error
+    pub fn solve(&self, catalog: &WasmCatalog, xy: &[f64]) -> Result<WasmPlateSolution, JsValue> {
+        if xy.len() < 6 {
+            return Err("At least three image points are needed to plate solve".into());
+        }
+        self.solve_with(catalog, xy, |search, sources| {
+            plate_solve::solve(search, sources, &self.parameters)
+        })
+    }
+
+    //mp solve_near
+    /// Match image points (x0, y0, x1, y1, ...) to catalog stars for an
+    /// approximate orientation of the camera (such as from
+    /// [WasmCatalog::find_best_star_mappings]) and a 35mm equivalent
+    /// focal length in mm, refining both
+    ///
+    /// The match radius should allow for the error in the orientation
+    /// and focal length. Only the catalog stars that pass its filter
+    /// and are no fainter than the maximum magnitude are used
+    pub fn solve_near(
+        &self,
+        catalog: &WasmCatalog,
+        xy: &[f64],
+        orientation: &WasmQuatf64,
+        mm_equiv: f64,
+    ) -> Result<WasmPlateSolution, JsValue> {
+        let orientation = rotation::matrix_of_wasm_quat(orientation);
+        let focal_length = mm_equiv * self.parameters.size[0] / FULL_FRAME_WIDTH;
+        self.solve_with(catalog, xy, |search, sources| {
+            Ok(plate_solve::solve_near(
+                search,
+                sources,
+                &self.parameters,
+                &orientation,
+                focal_length,
+            ))
+        })
+    }
+}
+
+//ip WasmPlateSolver
+impl WasmPlateSolver {
+    //mi solve_with
+    /// Run a solve function with a search of the stars of the catalog
+    /// no fainter than the maximum magnitude, with the stars of the
+    /// solution then given by their indices in the catalog
+    fn solve_with<F>(
+        &self,
+        catalog: &WasmCatalog,
+        xy: &[f64],
+        solve_fn: F,
+    ) -> Result<WasmPlateSolution, JsValue>
+    where
+        F: FnOnce(&CatalogSearch, &[[f64; 2]]) -> Result<Option<PlateSolution>, String>,
+    {
+        let sources: Vec<[f64; 2]> = xy.chunks_exact(2).map(|p| [p[0], p[1]]).collect();
+        let search = CatalogSearch::new(catalog, self.max_magnitude);
+        let mut solution = solve_fn(&search, &sources)?.ok_or("No plate solution found")?;
+        let star_vectors = solution
+            .matches
+            .iter()
+            .map(|m| search.vector(m.star))
+            .collect();
+        for m in solution.matches.iter_mut() {
+            m.star = search.indices[m.star];
+        }
+        let lens_model = self.lens_model.with_lens(solution.lens);
+        let xy = solution.matches.iter().map(|m| sources[m.source]).collect();
+        Ok(WasmPlateSolution {
+            solution,
+            lens_model,
+            xy,
+            star_vectors,
+        })
+    }
+}
+
+//a WasmPlateSolution
+//tp WasmPlateSolution
+/// The orientation of a camera and focal length of its lens found by
+/// plate solving, with the image points matched to catalog stars
+#[wasm_bindgen]
+pub struct WasmPlateSolution {
+    solution: PlateSolution,
+    /// The lens model with the fitted focal length
+    lens_model: WasmLensModel,
+    /// Image points of the matches
+    xy: Vec<[f64; 2]>,
+    /// Unit vectors of the stars of the matches
+    star_vectors: Vec<[f64; 3]>,
+}
+
+//ip WasmPlateSolution
+#[wasm_bindgen]
+impl WasmPlateSolution {
+    //ap orientation
+    /// The rotation from the camera frame (+X along the optical axis,
+    /// +Y left, +Z up the image) to the frame of the catalog, as used
+    /// for the view orientation
+    #[wasm_bindgen(getter)]
+    pub fn orientation(&self) -> WasmQuatf64 {
+        rotation::wasm_quat_of_matrix(&self.solution.orientation)
+    }
+
+    //ap boresight
+    /// The direction of the optical axis in the frame of the catalog
+    #[wasm_bindgen(getter)]
+    pub fn boresight(&self) -> WasmVec3f64 {
+        rotation::apply(&self.solution.orientation, &[1., 0., 0.]).into()
+    }
+
+    //ap focal_length
+    /// The focal length of the lens in pixels
+    #[wasm_bindgen(getter)]
+    pub fn focal_length(&self) -> f64 {
+        self.solution.lens.focal_length
+    }
+
+    //ap mm_equiv
+    /// The 35mm equivalent focal length of the lens in mm
+    #[wasm_bindgen(getter)]
+    pub fn mm_equiv(&self) -> f64 {
+        self.lens_model.mm_equiv()
+    }
+
+    //ap lens_model
+    /// The lens model with the fitted focal length
+    #[wasm_bindgen(getter)]
+    pub fn lens_model(&self) -> WasmLensModel {
+        self.lens_model.clone()
+    }
+
+    //ap count
+    /// The number of image points matched to stars
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.solution.matches.len()
+    }
+
+    //ap rms
+    /// The root mean square distance in pixels between the matched image
+    /// points and their stars
+    #[wasm_bindgen(getter)]
+    pub fn rms(&self) -> f64 {
+        self.solution.rms
+    }
+
+    //ap confidence
+    /// The confidence (0 to 1) that the matches are not by chance
+    #[wasm_bindgen(getter)]
+    pub fn confidence(&self) -> f64 {
+        self.solution.confidence
+    }
+
+    //ap candidates
+    /// The number of candidate orientations tried
+    #[wasm_bindgen(getter)]
+    pub fn candidates(&self) -> usize {
+        self.solution.candidates
+    }
+
+    //mp sources
+    /// The indices of the matched image points
+    pub fn sources(&self) -> Vec<u32> {
+        self.solution
+            .matches
+            .iter()
+            .map(|m| m.source as u32)
+            .collect()
+    }
+
+    //mp star_indices
+    /// The catalog indices of the stars matched to the image points
+    pub fn star_indices(&self) -> Vec<u32> {
+        self.solution
+            .matches
+            .iter()
+            .map(|m| m.star as u32)
+            .collect()
+    }
+
+    //mp star_ids
+    /// The catalog ids of the stars matched to the image points
+    pub fn star_ids(&self) -> Vec<u32> {
+        self.solution.matches.iter().map(|m| m.id as u32).collect()
+    }
+
+    //mp residuals
+    /// The image position of each matched star less that of its image
+    /// point in pixels, as dx0, dy0, dx1, dy1, ...
+    pub fn residuals(&self) -> Vec<f64> {
+        self.solution
+            .matches
+            .iter()
+            .flat_map(|m| m.residual)
+            .collect()
+    }
+
+    //mp refine
+    /// Refine the orientation, focal length and (optionally) principal
+    /// point and distortion of the lens by Levenberg-Marquardt against
+    /// all of the matched stars
+    ///
+    /// The first 'radial_terms' (0 to 3) radial distortion coefficients
+    /// are refined, with the tangential coefficients if 'tangential',
+    /// and the principal point if 'principal_point'
+    pub fn refine(
+        &self,
+        radial_terms: usize,
+        tangential: bool,
+        principal_point: bool,
+    ) -> Result<WasmLensCalibration, JsValue> {
+        self.lens_model.refine_matches(
+            &self.solution.orientation,
+            &self.xy,
+            &self.star_vectors,
+            radial_terms,
+            tangential,
+            principal_point,
+        )
+    }
+}
+
+//ip WasmPlateSolution
+impl WasmPlateSolution {
+    //ap solution
+    pub fn solution(&self) -> &PlateSolution {
+        &self.solution
+    }
+}
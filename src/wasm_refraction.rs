@@ -0,0 +1,83 @@
+//a Imports
+use wasm_bindgen::prelude::*;
+
+use crate::refraction::Refraction;
+use crate::{Vec3f64, WasmVec3f64};
+
+//a WasmRefraction
+//tp WasmRefraction
+/// Atmospheric refraction for given conditions
+#[wasm_bindgen]
+pub struct WasmRefraction {
+    refraction: Refraction,
+}
+
+//ip WasmRefraction
+#[wasm_bindgen]
+impl WasmRefraction {
+    //cp new
+    /// Create a new [WasmRefraction] for a pressure in millibars and a
+    /// temperature in Celsius; this fails for a negative pressure or a
+    /// temperature at or below absolute zero
+    #[wasm_bindgen(constructor)]
+    pub fn new(pressure: f64, temperature: f64) -> Result<WasmRefraction, JsValue> {
+        let refraction = Refraction::of_conditions(pressure, temperature)?;
+        Ok(Self { refraction })
+    }
+
+    //cp standard
+    /// Create a new [WasmRefraction] for standard conditions (1010 mbar,
+    /// 10C)
+    pub fn standard() -> WasmRefraction {
+        let refraction = Refraction::default();
+        Self { refraction }
+    }
+
+    //ap pressure
+    #[wasm_bindgen(getter)]
+    pub fn pressure(&self) -> f64 {
+        self.refraction.pressure
+    }
+
+    //ap temperature
+    #[wasm_bindgen(getter)]
+    pub fn temperature(&self) -> f64 {
+        self.refraction.temperature
+    }
+
+    //mp apparent_altitude
+    /// The apparent altitude (radians) of an object at a true altitude
+    pub fn apparent_altitude(&self, altitude: f64) -> f64 {
+        self.refraction.apparent_altitude(altitude)
+    }
+
+    //mp true_altitude
+    /// The true altitude (radians) of an object at an apparent altitude
+    pub fn true_altitude(&self, altitude: f64) -> f64 {
+        self.refraction.true_altitude(altitude)
+    }
+
+    //mp refract
+    /// Map a true observer-frame (north, west, up) direction to where it
+    /// appears
+    pub fn refract(&self, v: &WasmVec3f64) -> WasmVec3f64 {
+        let v: Vec3f64 = v.into();
+        self.refraction.refract(&[v[0], v[1], v[2]]).into()
+    }
+
+    //mp unrefract
+    /// Map an apparent observer-frame (north, west, up) direction to its
+    /// true direction
+    pub fn unrefract(&self, v: &WasmVec3f64) -> WasmVec3f64 {
+        let v: Vec3f64 = v.into();
+        self.refraction.unrefract(&[v[0], v[1], v[2]]).into()
+    }
+}
+
+//ip WasmRefraction
+impl WasmRefraction {
+    //ap refraction
+    pub fn refraction(&self) -> &Refraction {
+        &self.refraction
+    }
+}
@@ -0,0 +1,63 @@
+//a Imports
+use wasm_bindgen::prelude::*;
+
+use crate::rise_set::RiseSet;
+
+//a WasmRiseSet
+//tp WasmRiseSet
+/// Rise, transit and set times as Unix times in seconds
+#[wasm_bindgen]
+pub struct WasmRiseSet {
+    rise_set: RiseSet,
+}
+
+//ip WasmRiseSet
+#[wasm_bindgen]
+impl WasmRiseSet {
+    //ap kind
+    /// One of "rises_and_sets", "circumpolar" or "never_rises"
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        self.rise_set.kind.as_str().into()
+    }
+
+    //ap rise
+    /// The next rise, or undefined if the object does not rise and set
+    #[wasm_bindgen(getter)]
+    pub fn rise(&self) -> Option<f64> {
+        self.rise_set.rise
+    }
+
+    //ap upper_transit
+    #[wasm_bindgen(getter)]
+    pub fn upper_transit(&self) -> f64 {
+        self.rise_set.upper_transit
+    }
+
+    //ap lower_transit
+    #[wasm_bindgen(getter)]
+    pub fn lower_transit(&self) -> f64 {
+        self.rise_set.lower_transit
+    }
+
+    //ap set
+    /// The next set, or undefined if the object does not rise and set
+    #[wasm_bindgen(getter)]
+    pub fn set(&self) -> Option<f64> {
+        self.rise_set.set
+    }
+
+    //ap transit_altitude
+    /// The true altitude at upper transit in radians
+    #[wasm_bindgen(getter)]
+    pub fn transit_altitude(&self) -> f64 {
+        self.rise_set.transit_altitude
+    }
+}
+
+//ip From<RiseSet> for WasmRiseSet
+impl From<RiseSet> for WasmRiseSet {
+    fn from(rise_set: RiseSet) -> Self {
+        Self { rise_set }
+    }
+}
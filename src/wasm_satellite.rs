@@ -0,0 +1,374 @@
+//a Imports
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::satellite::{Satellite, SatellitePass, SatellitePosition};
+use crate::tle;
+use crate::wasm_observer::WasmObserver;
+use crate::WasmVec3f64;
+
+//a WasmSatellites
+//tp WasmSatellites
+/// Earth satellites read from two or three line element set text,
+/// keyed by name (the catalog number if the text has no names)
+///
+/// Malformed element sets are skipped, with an error recorded for each
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmSatellites {
+    satellites: Vec<Satellite>,
+    index: HashMap<String, usize>,
+    errors: Vec<String>,
+}
+
+//ip WasmSatellites
+#[wasm_bindgen]
+impl WasmSatellites {
+    //cp new
+    /// Create an empty set of satellites
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmSatellites {
+        Self::default()
+    }
+
+    //cp of_tle
+    /// Create from two or three line element set text
+    pub fn of_tle(text: &str) -> WasmSatellites {
+        let mut s = Self::new();
+        s.add_tle(text);
+        s
+    }
+
+    //mp add_tle
+    /// Add satellites from two or three line element set text, returning
+    /// the number added; a satellite replaces any with the same name
+    pub fn add_tle(&mut self, text: &str) -> usize {
+        let (tles, errors) = tle::parse(text);
+        self.errors.extend(errors);
+        let mut n = 0;
+        for t in tles {
+            match Satellite::new(t) {
+                Ok(s) => {
+                    if let Some(i) = self.index.get(s.name()) {
+                        self.satellites[*i] = s;
+                    } else {
+                        self.index
+                            .insert(s.name().to_string(), self.satellites.len());
+                        self.satellites.push(s);
+                    }
+                    n += 1;
+                }
+                Err(e) => self.errors.push(e),
+            }
+        }
+        n
+    }
+
+    //ap count
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.satellites.len()
+    }
+
+    //ap names
+    /// The names of the satellites, in the order they were added
+    #[wasm_bindgen(getter)]
+    pub fn names(&self) -> Vec<String> {
+        self.satellites
+            .iter()
+            .map(|s| s.name().to_string())
+            .collect()
+    }
+
+    //ap errors
+    /// The errors for the malformed element sets of all the text added
+    #[wasm_bindgen(getter)]
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.clone()
+    }
+
+    //mp clear_errors
+    pub fn clear_errors(&mut self) {
+        self.errors.clear();
+    }
+
+    //mp satellite
+    /// Get the satellite with a name, if it is present
+    pub fn satellite(&self, name: &str) -> Option<WasmSatellite> {
+        self.index
+            .get(name)
+            .map(|n| self.satellites[*n].clone().into())
+    }
+
+    //mp above
+    /// Get the names of the satellites above a minimum elevation
+    /// (radians) for the observer at the observer's time
+    pub fn above(&self, observer: &WasmObserver, min_elevation: f64) -> Vec<String> {
+        self.satellites
+            .iter()
+            .filter(|s| {
+                s.position(observer.observer())
+                    .is_ok_and(|p| p.altitude >= min_elevation)
+            })
+            .map(|s| s.name().to_string())
+            .collect()
+    }
+}
+
+//a WasmSatellite
+//tp WasmSatellite
+/// An Earth satellite propagated from its element set with SGP4/SDP4
+#[wasm_bindgen]
+pub struct WasmSatellite {
+    satellite: Satellite,
+}
+
+//ip WasmSatellite
+#[wasm_bindgen]
+impl WasmSatellite {
+    //cp of_tle
+    /// Create from the two lines of an element set and an optional name
+    pub fn of_tle(
+        name: Option<String>,
+        line1: &str,
+        line2: &str,
+    ) -> Result<WasmSatellite, JsValue> {
+        let tle = tle::Tle::parse_lines(name.as_deref(), 1, line1, line2)?;
+        Ok(Satellite::new(tle)?.into())
+    }
+
+    //ap name
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.satellite.name().to_string()
+    }
+
+    //ap catalog_number
+    #[wasm_bindgen(getter)]
+    pub fn catalog_number(&self) -> u32 {
+        self.satellite.tle().catalog_number
+    }
+
+    //ap epoch
+    /// The epoch of the element set as a Unix time in seconds
+    #[wasm_bindgen(getter)]
+    pub fn epoch(&self) -> f64 {
+        self.satellite.tle().epoch
+    }
+
+    //ap period
+    /// The period of the orbit in seconds
+    #[wasm_bindgen(getter)]
+    pub fn period(&self) -> f64 {
+        self.satellite.period()
+    }
+
+    //mp position
+    /// Get the position of the satellite for the observer at the
+    /// observer's time
+    pub fn position(&self, observer: &WasmObserver) -> Result<WasmSatellitePosition, JsValue> {
+        Ok(self.satellite.position(observer.observer())?.into())
+    }
+
+    //mp position_at
+    /// Get the position of the satellite for the observer at a Unix
+    /// time in seconds
+    pub fn position_at(
+        &self,
+        observer: &WasmObserver,
+        unix_time: f64,
+    ) -> Result<WasmSatellitePosition, JsValue> {
+        Ok(self
+            .satellite
+            .position_at(observer.observer(), unix_time)?
+            .into())
+    }
+
+    //mp passes
+    /// Find the passes of the satellite above a minimum elevation
+    /// (radians) for the observer between two Unix times, at most 31
+    /// days apart
+    pub fn passes(
+        &self,
+        observer: &WasmObserver,
+        start: f64,
+        end: f64,
+        min_elevation: f64,
+    ) -> Result<Vec<WasmSatellitePass>, JsValue> {
+        Ok(self
+            .satellite
+            .passes(observer.observer(), start, end, min_elevation)?
+            .into_iter()
+            .map(|pass| WasmSatellitePass { pass })
+            .collect())
+    }
+}
+
+//ip WasmSatellite
+impl WasmSatellite {
+    //ap satellite
+    pub fn satellite(&self) -> &Satellite {
+        &self.satellite
+    }
+}
+
+//ip From<Satellite> for WasmSatellite
+impl From<Satellite> for WasmSatellite {
+    fn from(satellite: Satellite) -> Self {
+        Self { satellite }
+    }
+}
+
+//a WasmSatellitePosition
+//tp WasmSatellitePosition
+/// The position of a satellite as seen by an observer at a time
+#[wasm_bindgen]
+pub struct WasmSatellitePosition {
+    position: SatellitePosition,
+}
+
+//ip WasmSatellitePosition
+#[wasm_bindgen]
+impl WasmSatellitePosition {
+    //ap unix_time
+    #[wasm_bindgen(getter)]
+    pub fn unix_time(&self) -> f64 {
+        self.position.unix_time
+    }
+
+    //ap altitude
+    /// Altitude in radians, refracted if the observer has refraction
+    #[wasm_bindgen(getter)]
+    pub fn altitude(&self) -> f64 {
+        self.position.altitude
+    }
+
+    //ap azimuth
+    /// Azimuth in radians from north through east
+    #[wasm_bindgen(getter)]
+    pub fn azimuth(&self) -> f64 {
+        self.position.azimuth
+    }
+
+    //ap range
+    /// Distance from the observer in km
+    #[wasm_bindgen(getter)]
+    pub fn range(&self) -> f64 {
+        self.position.range
+    }
+
+    //ap height
+    /// Height above the Earth in km
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> f64 {
+        self.position.height
+    }
+
+    //ap sunlit
+    #[wasm_bindgen(getter)]
+    pub fn sunlit(&self) -> bool {
+        self.position.sunlit
+    }
+
+    //ap vector
+    /// The ECEF direction of the satellite from the observer
+    #[wasm_bindgen(getter)]
+    pub fn vector(&self) -> WasmVec3f64 {
+        crate::rotation::normalize(&self.position.topocentric).into()
+    }
+
+    //ap topocentric_position
+    /// The ECEF position of the satellite relative to the observer in km
+    #[wasm_bindgen(getter)]
+    pub fn topocentric_position(&self) -> WasmVec3f64 {
+        self.position.topocentric.into()
+    }
+
+    //ap geocentric_position
+    /// The ECEF position of the satellite relative to the center of the
+    /// Earth in km
+    #[wasm_bindgen(getter)]
+    pub fn geocentric_position(&self) -> WasmVec3f64 {
+        self.position.geocentric.into()
+    }
+}
+
+//ip From<SatellitePosition> for WasmSatellitePosition
+impl From<SatellitePosition> for WasmSatellitePosition {
+    fn from(position: SatellitePosition) -> Self {
+        Self { position }
+    }
+}
+
+//a WasmSatellitePass
+//tp WasmSatellitePass
+/// A pass of a satellite over an observer, with its positions at the
+/// start, highest point and end
+#[wasm_bindgen]
+pub struct WasmSatellitePass {
+    pass: SatellitePass,
+}
+
+//ip WasmSatellitePass
+#[wasm_bindgen]
+impl WasmSatellitePass {
+    //ap start
+    #[wasm_bindgen(getter)]
+    pub fn start(&self) -> WasmSatellitePosition {
+        self.pass.start.clone().into()
+    }
+
+    //ap max
+    /// The position at the highest elevation of the pass
+    #[wasm_bindgen(getter)]
+    pub fn max(&self) -> WasmSatellitePosition {
+        self.pass.max.clone().into()
+    }
+
+    //ap end
+    #[wasm_bindgen(getter)]
+    pub fn end(&self) -> WasmSatellitePosition {
+        self.pass.end.clone().into()
+    }
+
+    //ap start_time
+    #[wasm_bindgen(getter)]
+    pub fn start_time(&self) -> f64 {
+        self.pass.start.unix_time
+    }
+
+    //ap max_time
+    #[wasm_bindgen(getter)]
+    pub fn max_time(&self) -> f64 {
+        self.pass.max.unix_time
+    }
+
+    //ap end_time
+    #[wasm_bindgen(getter)]
+    pub fn end_time(&self) -> f64 {
+        self.pass.end.unix_time
+    }
+
+    //ap max_elevation
+    /// The highest altitude of the pass in radians
+    #[wasm_bindgen(getter)]
+    pub fn max_elevation(&self) -> f64 {
+        self.pass.max.altitude
+    }
+
+    //ap duration
+    /// The duration of the pass in seconds
+    #[wasm_bindgen(getter)]
+    pub fn duration(&self) -> f64 {
+        self.pass.duration()
+    }
+
+    //ap sunlit
+    /// True if the satellite is in sunlight at the start, highest point
+    /// or end of the pass
+    #[wasm_bindgen(getter)]
+    pub fn sunlit(&self) -> bool {
+        self.pass.sunlit()
+    }
+}
@@ -2,11 +2,18 @@ use wasm_bindgen::prelude::*;
 
 use star_catalog::Star;
 
+use crate::precession;
+use crate::proper_motion::ProperMotion;
+use crate::rotation;
 use crate::{WasmVec3f32, WasmVec3f64};
 
 #[wasm_bindgen]
 pub struct WasmStar {
     s: Star,
+    /// Unit vector relative to the J2000 mean equator and equinox,
+    /// moved by proper motion but without any apparent place
+    j2000: [f64; 3],
+    motion: ProperMotion,
 }
 
 #[wasm_bindgen]
@@ -58,6 +65,19 @@ impl WasmStar {
         (*self.s.vector()).into()
     }
 
+    //mp vector_of_date
+    /// Get the unit vector of the star relative to the true equator and
+    /// equinox of a Julian date, applying precession and nutation to the
+    /// J2000 vector
+    ///
+    /// For a star from a catalog with apparent places this uses the
+    /// J2000 position (after proper motion) that the apparent place was
+    /// derived from, not the apparent place itself
+    pub fn vector_of_date(&self, julian_date: f64) -> WasmVec3f64 {
+        let m = precession::precession_nutation_matrix(julian_date);
+        rotation::apply(&m, &self.j2000).into()
+    }
+
     //mp set_vector
     pub fn set_vector(&self, v: &mut WasmVec3f64) {
         v.set(self.s.vector().as_ref());
@@ -76,6 +96,36 @@ impl WasmStar {
         [r, g, b].into()
     }
 
+    //ap proper_motion_ra
+    /// Proper motion in right ascension (including cos(declination)) in
+    /// milliarcseconds per year; zero if unknown
+    #[wasm_bindgen(getter)]
+    pub fn proper_motion_ra(&self) -> f64 {
+        self.motion.pm_ra
+    }
+
+    //ap proper_motion_de
+    /// Proper motion in declination in milliarcseconds per year; zero if
+    /// unknown
+    #[wasm_bindgen(getter)]
+    pub fn proper_motion_de(&self) -> f64 {
+        self.motion.pm_de
+    }
+
+    //ap parallax
+    /// Parallax in milliarcseconds; zero if unknown
+    #[wasm_bindgen(getter)]
+    pub fn parallax(&self) -> f64 {
+        self.motion.parallax
+    }
+
+    //ap radial_velocity
+    /// Radial velocity in km/s; zero if unknown
+    #[wasm_bindgen(getter)]
+    pub fn radial_velocity(&self) -> f64 {
+        self.motion.rv
+    }
+
     //ap cos_angle_between
     pub fn cos_angle_between(&self, other: &WasmStar) -> f64 {
         self.s.cos_angle_between(&other.s)
@@ -84,8 +134,23 @@ impl WasmStar {
     //zz All done
 }
 
+impl WasmStar {
+    //cp with_motion
+    /// Create a [WasmStar] with a known proper motion, and its unit
+    /// vector relative to the J2000 mean equator and equinox
+    pub fn with_motion(s: Star, j2000: [f64; 3], motion: ProperMotion) -> Self {
+        WasmStar { s, j2000, motion }
+    }
+}
+
 impl From<Star> for WasmStar {
     fn from(s: Star) -> Self {
-        WasmStar { s }
+        let v = s.vector();
+        let j2000 = [v[0], v[1], v[2]];
+        WasmStar {
+            s,
+            j2000,
+            motion: ProperMotion::default(),
+        }
     }
 }
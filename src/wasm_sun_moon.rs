@@ -0,0 +1,139 @@
+//a Imports
+use wasm_bindgen::prelude::*;
+
+use crate::rotation;
+use crate::sun_moon::{SunMoon, KM_PER_AU};
+use crate::time::Time;
+use crate::wasm_observer::WasmObserver;
+use crate::WasmVec3f64;
+
+//a WasmSunMoon
+//tp WasmSunMoon
+/// The apparent positions of the Sun and Moon at a time, geocentric or
+/// as seen by an observer, and the phase of the Moon
+///
+/// Directions are unit vectors in the ECEF frame - J2000, as for the
+/// star catalog, or the true equator and equinox of date if of_date is
+/// set (or the observer has it set)
+#[wasm_bindgen]
+pub struct WasmSunMoon {
+    sun_moon: SunMoon,
+}
+
+//ip WasmSunMoon
+#[wasm_bindgen]
+impl WasmSunMoon {
+    //cp new
+    /// Create the geocentric Sun and Moon at a Unix time in seconds
+    #[wasm_bindgen(constructor)]
+    pub fn new(unix_time: f64, of_date: bool) -> WasmSunMoon {
+        let sun_moon = SunMoon::new(Time::of_unix_time(unix_time), of_date);
+        Self { sun_moon }
+    }
+
+    //cp of_observer
+    /// Create the Sun and Moon as seen by an observer at its time
+    pub fn of_observer(observer: &WasmObserver) -> WasmSunMoon {
+        let sun_moon = SunMoon::of_observer(observer.observer());
+        Self { sun_moon }
+    }
+
+    //ap unix_time
+    #[wasm_bindgen(getter)]
+    pub fn unix_time(&self) -> f64 {
+        self.sun_moon.time().unix_time()
+    }
+
+    //ap sun_vector
+    /// The geocentric direction of the Sun
+    #[wasm_bindgen(getter)]
+    pub fn sun_vector(&self) -> WasmVec3f64 {
+        rotation::normalize(self.sun_moon.sun()).into()
+    }
+
+    //ap moon_vector
+    /// The geocentric direction of the Moon
+    #[wasm_bindgen(getter)]
+    pub fn moon_vector(&self) -> WasmVec3f64 {
+        rotation::normalize(self.sun_moon.moon()).into()
+    }
+
+    //ap topocentric_sun_vector
+    /// The direction of the Sun from the observer
+    #[wasm_bindgen(getter)]
+    pub fn topocentric_sun_vector(&self) -> WasmVec3f64 {
+        rotation::normalize(&self.sun_moon.topocentric_sun()).into()
+    }
+
+    //ap topocentric_moon_vector
+    /// The direction of the Moon from the observer
+    #[wasm_bindgen(getter)]
+    pub fn topocentric_moon_vector(&self) -> WasmVec3f64 {
+        rotation::normalize(&self.sun_moon.topocentric_moon()).into()
+    }
+
+    //ap sun_distance
+    /// The distance of the Sun from the center of the Earth in AU
+    #[wasm_bindgen(getter)]
+    pub fn sun_distance(&self) -> f64 {
+        rotation::length(self.sun_moon.sun()) / KM_PER_AU
+    }
+
+    //ap moon_distance
+    /// The distance of the Moon from the center of the Earth in km
+    #[wasm_bindgen(getter)]
+    pub fn moon_distance(&self) -> f64 {
+        rotation::length(self.sun_moon.moon())
+    }
+
+    //ap topocentric_moon_distance
+    /// The distance of the Moon from the observer in km
+    #[wasm_bindgen(getter)]
+    pub fn topocentric_moon_distance(&self) -> f64 {
+        rotation::length(&self.sun_moon.topocentric_moon())
+    }
+
+    //ap sun_semi_diameter
+    /// The angular radius of the Sun from the observer in radians
+    #[wasm_bindgen(getter)]
+    pub fn sun_semi_diameter(&self) -> f64 {
+        self.sun_moon.sun_semi_diameter()
+    }
+
+    //ap moon_semi_diameter
+    /// The angular radius of the Moon from the observer in radians
+    #[wasm_bindgen(getter)]
+    pub fn moon_semi_diameter(&self) -> f64 {
+        self.sun_moon.moon_semi_diameter()
+    }
+
+    //ap moon_phase
+    /// The fraction of the lunation: 0 new, 0.25 first quarter, 0.5
+    /// full, 0.75 last quarter
+    #[wasm_bindgen(getter)]
+    pub fn moon_phase(&self) -> f64 {
+        self.sun_moon.moon_phase()
+    }
+
+    //ap moon_phase_angle
+    /// The angle Sun-Moon-Earth in radians; zero at full Moon
+    #[wasm_bindgen(getter)]
+    pub fn moon_phase_angle(&self) -> f64 {
+        self.sun_moon.moon_phase_angle()
+    }
+
+    //ap moon_illuminated_fraction
+    /// The fraction of the Moon's disc that is lit, 0 to 1
+    #[wasm_bindgen(getter)]
+    pub fn moon_illuminated_fraction(&self) -> f64 {
+        self.sun_moon.moon_illuminated_fraction()
+    }
+}
+
+//ip WasmSunMoon
+impl WasmSunMoon {
+    //ap sun_moon
+    pub fn sun_moon(&self) -> &SunMoon {
+        &self.sun_moon
+    }
+}
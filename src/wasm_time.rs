@@ -0,0 +1,123 @@
+//a Imports
+use wasm_bindgen::prelude::*;
+
+use crate::time::{self, Time};
+
+//a WasmTime
+//tp WasmTime
+/// An instant in time, with conversions between time scales and to
+/// sidereal time
+#[wasm_bindgen]
+pub struct WasmTime {
+    time: Time,
+}
+
+//ip WasmTime
+#[wasm_bindgen]
+impl WasmTime {
+    //cp new
+    /// Create a new [WasmTime] from a Unix time in seconds (as given by
+    /// Date.getTime() / 1000)
+    #[wasm_bindgen(constructor)]
+    pub fn new(unix_time: f64) -> WasmTime {
+        let time = Time::of_unix_time(unix_time);
+        Self { time }
+    }
+
+    //cp of_julian_date
+    /// Create a new [WasmTime] from a UTC Julian date
+    pub fn of_julian_date(julian_date: f64) -> WasmTime {
+        let time = Time::of_julian_date(julian_date);
+        Self { time }
+    }
+
+    //cp of_calendar
+    /// Create a new [WasmTime] from a UTC Gregorian calendar date
+    /// (month 1 to 12) and time of day in hours
+    pub fn of_calendar(year: i32, month: u32, day: u32, hours: f64) -> WasmTime {
+        let days = time::days_from_civil(year, month, day) as f64;
+        Self::new(days * time::SECONDS_PER_DAY + hours * 3600.0)
+    }
+
+    //mp set_unix_time
+    pub fn set_unix_time(&mut self, unix_time: f64) {
+        self.time = Time::of_unix_time(unix_time).with_dut1(self.time.dut1());
+    }
+
+    //mp set_dut1
+    /// Set UT1-UTC in seconds (from IERS Bulletin A); it is zero by
+    /// default
+    pub fn set_dut1(&mut self, dut1: f64) {
+        self.time = self.time.with_dut1(dut1);
+    }
+
+    //ap unix_time
+    #[wasm_bindgen(getter)]
+    pub fn unix_time(&self) -> f64 {
+        self.time.unix_time()
+    }
+
+    //ap julian_date
+    /// The UTC Julian date
+    #[wasm_bindgen(getter)]
+    pub fn julian_date(&self) -> f64 {
+        self.time.jd_utc()
+    }
+
+    //ap julian_date_ut1
+    #[wasm_bindgen(getter)]
+    pub fn julian_date_ut1(&self) -> f64 {
+        self.time.jd_ut1()
+    }
+
+    //ap julian_date_tt
+    /// The Julian date in Terrestrial Time, as used for precession and
+    /// ephemerides
+    #[wasm_bindgen(getter)]
+    pub fn julian_date_tt(&self) -> f64 {
+        self.time.jd_tt()
+    }
+
+    //ap tt_minus_utc
+    /// TT-UTC in seconds, including leap seconds
+    #[wasm_bindgen(getter)]
+    pub fn tt_minus_utc(&self) -> f64 {
+        time::tt_minus_utc(self.time.unix_time())
+    }
+
+    //ap gmst
+    /// Greenwich mean sidereal time in radians
+    #[wasm_bindgen(getter)]
+    pub fn gmst(&self) -> f64 {
+        self.time.gmst()
+    }
+
+    //ap gast
+    /// Greenwich apparent sidereal time in radians
+    #[wasm_bindgen(getter)]
+    pub fn gast(&self) -> f64 {
+        self.time.gast()
+    }
+
+    //mp local_sidereal_time
+    /// Local apparent sidereal time in radians for an east longitude in
+    /// radians; this is the right ascension of the observer's zenith
+    pub fn local_sidereal_time(&self, longitude: f64) -> f64 {
+        self.time.local_sidereal_time(longitude)
+    }
+}
+
+//ip WasmTime
+impl WasmTime {
+    //ap time
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+}
+
+//ip From<Time> for WasmTime
+impl From<Time> for WasmTime {
+    fn from(time: Time) -> Self {
+        Self { time }
+    }
+}
@@ -0,0 +1,128 @@
+//a Imports
+use wasm_bindgen::prelude::*;
+
+use crate::twilight::Twilight;
+use crate::wasm_observer::WasmObserver;
+
+//a WasmTwilight
+//tp WasmTwilight
+/// Twilight boundaries, moonrise and moonset, and fully dark intervals
+/// for a night at an observer's location, as Unix times in seconds
+///
+/// Events that do not occur in the night are undefined
+#[wasm_bindgen]
+pub struct WasmTwilight {
+    twilight: Twilight,
+}
+
+//ip WasmTwilight
+#[wasm_bindgen]
+impl WasmTwilight {
+    //cp of_date
+    /// Find the twilight for the night (local noon to local noon)
+    /// following a UTC Gregorian calendar date (month 1 to 12) at the
+    /// observer's location
+    pub fn of_date(observer: &WasmObserver, year: i32, month: u32, day: u32) -> WasmTwilight {
+        let twilight = Twilight::of_date(observer.observer(), year, month, day);
+        Self { twilight }
+    }
+
+    //cp of_interval
+    /// Find the twilight events between two Unix times at the
+    /// observer's location
+    pub fn of_interval(observer: &WasmObserver, start: f64, end: f64) -> WasmTwilight {
+        let twilight = Twilight::of_interval(observer.observer(), start, end);
+        Self { twilight }
+    }
+
+    //ap start
+    #[wasm_bindgen(getter)]
+    pub fn start(&self) -> f64 {
+        self.twilight.start
+    }
+
+    //ap end
+    #[wasm_bindgen(getter)]
+    pub fn end(&self) -> f64 {
+        self.twilight.end
+    }
+
+    //ap sunset
+    #[wasm_bindgen(getter)]
+    pub fn sunset(&self) -> Option<f64> {
+        self.twilight.sunset
+    }
+
+    //ap civil_dusk
+    #[wasm_bindgen(getter)]
+    pub fn civil_dusk(&self) -> Option<f64> {
+        self.twilight.civil_dusk
+    }
+
+    //ap nautical_dusk
+    #[wasm_bindgen(getter)]
+    pub fn nautical_dusk(&self) -> Option<f64> {
+        self.twilight.nautical_dusk
+    }
+
+    //ap astronomical_dusk
+    #[wasm_bindgen(getter)]
+    pub fn astronomical_dusk(&self) -> Option<f64> {
+        self.twilight.astronomical_dusk
+    }
+
+    //ap astronomical_dawn
+    #[wasm_bindgen(getter)]
+    pub fn astronomical_dawn(&self) -> Option<f64> {
+        self.twilight.astronomical_dawn
+    }
+
+    //ap nautical_dawn
+    #[wasm_bindgen(getter)]
+    pub fn nautical_dawn(&self) -> Option<f64> {
+        self.twilight.nautical_dawn
+    }
+
+    //ap civil_dawn
+    #[wasm_bindgen(getter)]
+    pub fn civil_dawn(&self) -> Option<f64> {
+        self.twilight.civil_dawn
+    }
+
+    //ap sunrise
+    #[wasm_bindgen(getter)]
+    pub fn sunrise(&self) -> Option<f64> {
+        self.twilight.sunrise
+    }
+
+    //ap moonrise
+    #[wasm_bindgen(getter)]
+    pub fn moonrise(&self) -> Option<f64> {
+        self.twilight.moonrise
+    }
+
+    //ap moonset
+    #[wasm_bindgen(getter)]
+    pub fn moonset(&self) -> Option<f64> {
+        self.twilight.moonset
+    }
+
+    //ap dark
+    /// The fully dark intervals (Sun below astronomical twilight, Moon
+    /// below the horizon) as [start0, end0, start1, end1, ...]
+    #[wasm_bindgen(getter)]
+    pub fn dark(&self) -> Vec<f64> {
+        self.twilight
+            .dark
+            .iter()
+            .flat_map(|(s, e)| [*s, *e])
+            .collect()
+    }
+
+    //ap dark_duration
+    /// The total duration of the fully dark intervals in seconds
+    #[wasm_bindgen(getter)]
+    pub fn dark_duration(&self) -> f64 {
+        self.twilight.dark.iter().map(|(s, e)| e - s).sum()
+    }
+}
@@ -2,7 +2,10 @@ import {
   WasmVec3f64,
   WasmQuatf64,
   WasmCatalog,
-  WasmPolynomial,
+  WasmSourceExtractor,
+  WasmPlateSolver,
+  WasmPlateSolution,
+  WasmLensModel,
 } from "../pkg/star_catalog_wasm.js";
 import { Webgl } from "./web_gl.js";
 import { Draw } from "./draw.js";
@@ -15,108 +18,6 @@ import { WebglCanvas, WebglCanvasClient } from "./webgl_canvas.js";
 import { ViewProperties } from "./view_properties.js";
 import { Application } from "./application.js";
 
-interface LensMapping {
-  map_sensor_r_to_world_yaw(mm_equiv: number, sensor_r: number): number;
-  map_world_yaw_to_sensor_r(mm_equiv: number, world_yaw: number): number;
-}
-
-class LensMappingRectilinear {
-  map_sensor_r_to_world_yaw(mm_equiv: number, sensor_r: number): number {
-    const f = mm_equiv / 18.0;
-    return Math.atan(sensor_r / f);
-  }
-  map_world_yaw_to_sensor_r(mm_equiv: number, world_yaw: number): number {
-    const f = mm_equiv / 18.0;
-    return Math.tan(world_yaw) * f;
-  }
-}
-
-class LensMappingStereoGraphic {
-  map_sensor_r_to_world_yaw(mm_equiv: number, sensor_r: number): number {
-    const f = mm_equiv / 18.0;
-    return Math.atan(sensor_r / f / 2) * 2;
-  }
-  map_world_yaw_to_sensor_r(mm_equiv: number, world_yaw: number): number {
-    const f = mm_equiv / 18.0;
-    return Math.tan(world_yaw / 2) * f * 2;
-  }
-}
-
-class LensMappingEquisolid {
-  map_sensor_r_to_world_yaw(mm_equiv: number, sensor_r: number): number {
-    const f = mm_equiv / 18.0;
-    return Math.asin(sensor_r / f / 2) * 2;
-  }
-  map_world_yaw_to_sensor_r(mm_equiv: number, world_yaw: number): number {
-    const f = mm_equiv / 18.0;
-    return Math.sin(world_yaw / 2) * f * 2;
-  }
-}
-
-class LensMappingEquidistant {
-  map_sensor_r_to_world_yaw(mm_equiv: number, sensor_r: number): number {
-    const f = mm_equiv / 18.0;
-    return sensor_r / f;
-  }
-  map_world_yaw_to_sensor_r(mm_equiv: number, world_yaw: number): number {
-    const f = mm_equiv / 18.0;
-    return f * world_yaw;
-  }
-}
-
-class LensMappingOrthographic {
-  map_sensor_r_to_world_yaw(mm_equiv: number, sensor_r: number): number {
-    const f = mm_equiv / 18.0;
-    return Math.asin(sensor_r / f);
-  }
-  map_world_yaw_to_sensor_r(mm_equiv: number, world_yaw: number): number {
-    const f = mm_equiv / 18.0;
-    return Math.sin(world_yaw) * f;
-  }
-}
-
-class LensMappingPolynomial {
-  sensor_to_world: WasmPolynomial;
-  world_to_sensor: WasmPolynomial;
-  constructor() {
-    this.sensor_to_world = new WasmPolynomial(new Float64Array([0, 1]));
-    this.world_to_sensor = new WasmPolynomial(new Float64Array([0, 1]));
-  }
-  approximate(
-    degree: number,
-    sensor_to_world_fn: (sensor: number) => number,
-  ): boolean {
-    const length = degree * 8;
-    let xs = new Float64Array(length);
-    let ys = new Float64Array(length);
-    for (let i = 0; i < length; i++) {
-      xs[i] = i / length;
-      ys[i] = sensor_to_world_fn(i / length);
-    }
-    const okay =
-      this.sensor_to_world.min_squares(degree, xs, ys) &&
-      this.world_to_sensor.min_squares(degree, ys, xs);
-
-    this.sensor_to_world.set(0, 0);
-    this.world_to_sensor.set(0, 0);
-
-    let dsq_error = 0.0;
-    for (let i = 0; i < length; i++) {
-      const v = i / length;
-      const d = this.world_to_sensor.calc(this.sensor_to_world.calc(v)) - v;
-      dsq_error += d * d;
-    }
-    console.log("dsq_error from stw to wts ", dsq_error);
-    return okay;
-  }
-  map_sensor_r_to_world_yaw(mm_equiv: number, sensor_r: number): number {
-    return this.sensor_to_world.calc((sensor_r * 18.0) / mm_equiv);
-  }
-  map_world_yaw_to_sensor_r(mm_equiv: number, world_yaw: number): number {
-    return (this.world_to_sensor.calc(world_yaw) * mm_equiv) / 18.0;
-  }
-}
-
 class FindOrientation {
   /** The catalog to find stars in */
   catalog: WasmCatalog;
@@ -191,7 +92,7 @@ export class FindCanvas implements WebglCanvasClient {
   star_vector: WasmVec3f64;
   selected_stars: [number, number][];
 
-  lens_mapping: LensMapping;
+  lens_model: WasmLensModel;
   constructor(application: Application, canvas_div_id: string) {
     this.application = application;
     this.vp = this.application.view_properties;
@@ -207,10 +108,7 @@ export class FindCanvas implements WebglCanvasClient {
     this.canvas.height = this.current_wh[1];
     this.zoomed_window = new ZoomedWindow(this.current_wh);
 
-    const lm = new LensMappingPolynomial();
-    lm.approximate(8, (x) => Math.atan(x));
-    this.lens_mapping = lm;
-    this.lens_mapping = new LensMappingRectilinear();
+    this.lens_model = new WasmLensModel("rectilinear", 36, 24, 27);
 
     const get_image = document.querySelector("#find_get_image")!;
     get_image.addEventListener("change", this.get_image.bind(this));
@@ -218,6 +116,9 @@ export class FindCanvas implements WebglCanvasClient {
     const best_matches = document.querySelector("#find_best_matches")!;
     best_matches.addEventListener("click", this.best_matches.bind(this));
 
+    const detect_stars = document.querySelector("#find_detect_stars")!;
+    detect_stars.addEventListener("click", this.detect_stars.bind(this));
+
     const clear_selection = document.querySelector("#find_clear_selection")!;
     clear_selection.addEventListener("click", this.clear_selection.bind(this));
 
@@ -312,6 +213,7 @@ export class FindCanvas implements WebglCanvasClient {
     this.img_h = this.img!.naturalHeight;
     this.img_cx = this.img_w / 2;
     this.img_cy = this.img_h / 2;
+    this.lens_model.set_image_size(this.img_w, this.img_h);
     const img_ar = this.img_w / this.img_h;
     this.zoomed_window.set_img(this.img_w, this.img_h);
     this.canvas.height = this.canvas.width / img_ar;
@@ -333,6 +235,39 @@ export class FindCanvas implements WebglCanvasClient {
     reader.readAsDataURL(myFile);
   }
 
+  detect_stars(_e: Event) {
+    if (!this.img || this.img_w == 0) {
+      return;
+    }
+    const canvas = document.createElement("canvas");
+    canvas.width = this.img_w;
+    canvas.height = this.img_h;
+    const ctx = canvas.getContext("2d")!;
+    ctx.drawImage(this.img, 0, 0);
+    const data = ctx.getImageData(0, 0, this.img_w, this.img_h).data;
+
+    // Keep only the brightest stars for the triangle matching
+    const extractor = new WasmSourceExtractor();
+    extractor.set_max_sources(20);
+    const sources = extractor.extract(
+      new Uint8Array(data.buffer),
+      this.img_w,
+      this.img_h,
+    );
+    const xy = sources.xy();
+    this.logger.info(
+      `Detected ${sources.count} stars, background ${sources.background.toFixed(1)} noise ${sources.noise.toFixed(2)}`,
+    );
+    sources.free();
+    extractor.free();
+
+    this.selected_stars = [];
+    for (let i = 0; i + 1 < xy.length; i += 2) {
+      this.selected_stars.push([xy[i]!, xy[i + 1]!]);
+    }
+    this.redraw_canvas();
+  }
+
   clear_selection(_e: Event) {
     this.selected_stars = [];
     this.redraw_canvas();
@@ -367,31 +302,24 @@ export class FindCanvas implements WebglCanvasClient {
   }
 
   set_lens_mapping(_event: Event, value: string) {
-    switch (value) {
-      case "Orthographic": {
-        this.lens_mapping = new LensMappingOrthographic();
-        break;
-      }
-      case "Equidistant": {
-        this.lens_mapping = new LensMappingEquidistant();
-        break;
-      }
-      case "Stereographic": {
-        this.lens_mapping = new LensMappingStereoGraphic();
-        break;
-      }
-      case "Equisolid": {
-        this.lens_mapping = new LensMappingEquisolid();
-        break;
-      }
-      default: {
-        this.lens_mapping = new LensMappingRectilinear();
-        break;
-      }
+    try {
+      this.lens_model.set_projection(value);
+    } catch (_e) {
+      this.lens_model.set_projection("rectilinear");
     }
     this.vp.view_updated();
   }
 
+  /// The lens model for the image, with the current focal length
+  lens(): WasmLensModel {
+    try {
+      this.lens_model.set_mm_equiv(this.vp.mm_equiv);
+    } catch (_e) {
+      this.logger.warning(`Bad 35mm equivalent focal length ${this.vp.mm_equiv}`);
+    }
+    return this.lens_model;
+  }
+
   populate_html() {
     const a = document.getElementById("find_max_angle");
     if (a instanceof HTMLInputElement) {
@@ -412,6 +340,9 @@ export class FindCanvas implements WebglCanvasClient {
     if (this.selected_stars.length < 3) {
       return;
     }
+    if (this.plate_solve()) {
+      return;
+    }
     const star_vectors = [];
     for (const ixy of this.selected_stars) {
       star_vectors.push(this.vector_of_img_xy(ixy));
@@ -426,50 +357,130 @@ export class FindCanvas implements WebglCanvasClient {
 
     const mappings = find_orientation.find_best_star_mappings();
 
-    this.vp.view_observer_set_orientation(mappings[0]!);
+    if (!this.refine_orientation(mappings[0]!)) {
+      this.vp.view_observer_set_orientation(mappings[0]!);
+    }
   }
 
-  vector_of_img_xy(ixy: [number, number]): WasmVec3f64 {
-    const rdx = ((ixy[0] - this.img_w / 2) / this.img_w) * 2;
-    const rdy = ((ixy[1] - this.img_h / 2) / this.img_w) * 2;
-    const sensor_rf = Math.sqrt(rdx * rdx + rdy * rdy);
-    const roll = Math.atan2(rdy, rdx);
-
-    //    const sensor_yaw = Math.atan(this.vp.tan_hfovh * sensor_rf);
-    const world_yaw = this.lens_mapping.map_sensor_r_to_world_yaw(
-      this.vp.mm_equiv,
-      sensor_rf,
+  /// The selected stars as x0, y0, x1, y1, ...
+  selected_xy(): Float64Array {
+    const xy = new Float64Array(this.selected_stars.length * 2);
+    this.selected_stars.forEach((ixy, i) => {
+      xy[2 * i] = ixy[0];
+      xy[2 * i + 1] = ixy[1];
+    });
+    return xy;
+  }
+
+  /// A plate solver for the lens for a focal length near the current
+  /// one
+  plate_solver(): WasmPlateSolver {
+    const mm_equiv = this.current_mm_equiv();
+    const solver = new WasmPlateSolver(
+      this.lens_model,
+      mm_equiv / 1.5,
+      mm_equiv * 1.5,
     );
-    //    const world_yaw = Math.atan((sensor_rf * 18.0) / this.vp.mm_equiv);
-
-    // const world_yaw = sensor_yaw;
-    //
-    // viewer is right +x, up +y, out of screen +z
-    const world_dir = new WasmVec3f64(
-      Math.cos(world_yaw),
-      -Math.cos(roll) * Math.sin(world_yaw),
-      -Math.sin(roll) * Math.sin(world_yaw),
-    );
-    world_dir.set_normalized();
-    return world_dir;
+    solver.set_max_magnitude(this.vp.brightness);
+    return solver;
+  }
+
+  /// The current 35mm equivalent focal length, or a typical one
+  current_mm_equiv(): number {
+    return this.vp.mm_equiv > 0 ? this.vp.mm_equiv : 27;
+  }
+
+  /// Plate solve the selected stars (brightest first) for a focal
+  /// length near the current one, setting the view orientation and
+  /// field of view if successful
+  plate_solve(): boolean {
+    let solver = null;
+    try {
+      solver = this.plate_solver();
+      const solution = solver.solve(
+        this.application.catalog,
+        this.selected_xy(),
+      );
+      this.logger.info(
+        `Plate solved: ${solution.count} stars, rms ${solution.rms.toFixed(2)} pixels, confidence ${solution.confidence.toFixed(4)}, ${solution.mm_equiv.toFixed(1)}mm`,
+      );
+      this.use_solution(solution);
+      return true;
+    } catch (e) {
+      this.logger.info(`Plate solve failed: ${e}`);
+      return false;
+    } finally {
+      solver?.free();
+    }
   }
 
-  img_xy_of_vector(vec: WasmVec3f64): [number, number] {
-    const v = vec.array;
-    const x = v[0]!;
-    const y = -v[1]!;
-    const z = -v[2]!;
-    const r = Math.sqrt(y * y + z * z);
-    const world_yaw = Math.atan2(r, x);
-    const roll = Math.atan2(z, y);
-    const img_r = this.lens_mapping.map_world_yaw_to_sensor_r(
-      this.vp.mm_equiv,
-      world_yaw,
-    );
-    //const img_r = (Math.tan(world_yaw) / 18.0) * this.vp.mm_equiv; // / this.vp.tan_hfovh;
-    const ix = (Math.cos(roll) * img_r * this.img_w) / 2 + this.img_w / 2;
-    const iy = (Math.sin(roll) * img_r * this.img_w) / 2 + this.img_h / 2;
-    return [ix, iy];
+  /// Match the selected stars to the catalog for an approximate
+  /// orientation (from a few star mappings), refining it and the lens,
+  /// and setting the view orientation and field of view if successful
+  refine_orientation(orientation: WasmQuatf64): boolean {
+    let solver = null;
+    try {
+      solver = this.plate_solver();
+      // Allow for the error in the guessed focal length
+      solver.set_match_radius(this.img_w / 50);
+      const solution = solver.solve_near(
+        this.application.catalog,
+        this.selected_xy(),
+        orientation,
+        this.current_mm_equiv(),
+      );
+      this.logger.info(
+        `Matched ${solution.count} stars, rms ${solution.rms.toFixed(2)} pixels, ${solution.mm_equiv.toFixed(1)}mm`,
+      );
+      this.use_solution(solution);
+      return true;
+    } catch (e) {
+      this.logger.info(`Star match failed: ${e}`);
+      return false;
+    } finally {
+      solver?.free();
+    }
+  }
+
+  /// Refine the orientation, focal length and (with enough stars) the
+  /// principal point and distortion of the lens from the stars of a
+  /// plate solution, and use them for the view
+  use_solution(solution: WasmPlateSolution) {
+    let orientation = solution.orientation;
+    let mm_equiv = solution.mm_equiv;
+    try {
+      const distortion = solution.count >= 12;
+      const calibration = solution.refine(
+        distortion ? 2 : 0,
+        distortion,
+        distortion,
+      );
+      this.lens_model.free();
+      this.lens_model = calibration.lens_model;
+      orientation = calibration.orientation;
+      mm_equiv = this.lens_model.mm_equiv;
+      this.logger.info(
+        `Refined lens: ${calibration.count} stars, rms ${calibration.rms.toFixed(2)} pixels, ${mm_equiv.toFixed(1)}mm`,
+      );
+      calibration.free();
+    } catch (e) {
+      this.logger.info(`Lens refinement failed: ${e}`);
+    }
+    solution.free();
+    this.vp.fovh = this.vp.map_mm_equiv_to_fovh(mm_equiv);
+    this.vp.view_observer_set_orientation(orientation);
+  }
+
+  vector_of_img_xy(ixy: [number, number]): WasmVec3f64 {
+    return this.lens().vector_of_xy(ixy[0], ixy[1]);
+  }
+
+  img_xy_of_vector(vec: WasmVec3f64): [number, number] | undefined {
+    const xy = this.lens().xy_of_vector(vec);
+    if (xy === undefined) {
+      return undefined;
+    }
+    return [xy[0]!, xy[1]!];
   }
 
   update() {
@@ -515,6 +526,9 @@ export class FindCanvas implements WebglCanvasClient {
         fa[2] = sin_yaw * Math.sin(x);
         v.set(fa);
         const ixy = this.img_xy_of_vector(v);
+        if (ixy === undefined) {
+          continue;
+        }
         const sxy = this.zoomed_window.scr_xy_of_img_xy(ixy);
         ctx.lineTo(sxy[0], sxy[1]);
       }
@@ -4,7 +4,6 @@ import {
   WasmQuatf32,
   WasmMat4f32,
   WasmBezier3f32,
-  WasmBezierBuilder3f32,
 } from "../pkg/star_catalog_wasm.js";
 
 import { Webgl, WebglTexture, WebglUniform } from "./web_gl.js";
@@ -14,7 +13,7 @@ import { Webgl3DObj } from "./web_gl_3d_obj.js";
 export class Planet {
   orbit: WasmOrbit;
   orbit_to_parent: WasmQuatf32;
-  orbit_bezier: WasmBezier3f32;
+  orbit_beziers: WasmBezier3f32[] = [];
   mat: WasmMat4f32;
   vec: WasmVec3f32;
   planet_scale: number = 0.002;
@@ -24,7 +23,6 @@ export class Planet {
 
   constructor(name: string, image_filename: string) {
     this.orbit = WasmOrbit.of_solar_system(name)!;
-    this.orbit_bezier = new WasmBezier3f32();
     this.orbit_to_parent = this.orbit.orbit_to_parent();
     this.vec = new WasmVec3f32(0, 0, 0);
     this.mat = WasmMat4f32.identity();
@@ -36,17 +34,21 @@ export class Planet {
     this.texture.image!.src = this.image_filename;
   }
 
-  set_time(secs_since_epoch: number, builder: WasmBezierBuilder3f32) {
+  set_time(secs_since_epoch: number) {
     this.orbit_to_parent = this.orbit.orbit_to_parent();
     const orbit_period = this.orbit.period_of_orbit();
-    builder.clear();
+    const max_error = this.orbit.perigee_distance() * 1e-4;
 
-    for (let t = 0; t <= 3; t++) {
-      const time = secs_since_epoch + orbit_period * (t - 1) * 0.025;
-      this.orbit.orbit_vec_of_unix_time(time, this.vec);
-      builder.add_vec_pt_at(t / 3.0, this.vec);
+    for (const b of this.orbit_beziers) {
+      b.free();
     }
-    this.orbit_bezier.reconstruct(builder);
+    this.orbit_beziers = this.orbit.window_beziers(
+      secs_since_epoch - orbit_period * 0.025,
+      secs_since_epoch + orbit_period * 0.05,
+      max_error,
+      false,
+    );
+    this.orbit.orbit_vec_of_unix_time(secs_since_epoch, this.vec);
   }
 
   draw_orbit(
@@ -58,12 +60,13 @@ export class Planet {
     this.mat.set_scale3(distance_scale);
     webgl.set_color(this.planet_color);
     webgl.set_uniform_mat4(WebglUniform.Model, this.mat.array, true);
-    bezier.set_bezier(this.orbit_bezier);
-    webgl.draw(bezier);
+    for (const b of this.orbit_beziers) {
+      bezier.set_bezier(b);
+      webgl.draw(bezier);
+    }
   }
 
   draw_planet(webgl: Webgl, icosphere: Webgl3DObj, distance_scale: number) {
-    this.orbit_bezier.set_vec_point_at(this.vec, 1 / 3.0);
     this.orbit_to_parent.set_vec_apply(this.vec);
     this.vec.set_mulf(distance_scale);
     this.mat.set_identity();
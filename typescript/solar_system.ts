@@ -1,7 +1,4 @@
-import {
-  WasmMat4f32,
-  WasmBezierBuilder3f32,
-} from "../pkg/star_catalog_wasm.js";
+import { WasmMat4f32 } from "../pkg/star_catalog_wasm.js";
 import { Webgl, WebglTexture, WebglUniform } from "./web_gl.js";
 import { WebglCubicBezierObj } from "./web_gl_bezier.js";
 import { Webgl3DObj } from "./web_gl_3d_obj.js";
@@ -36,9 +33,8 @@ export class SolarSystem {
   }
 
   set_time(secs_since_epoch: number) {
-    const builder = new WasmBezierBuilder3f32();
     for (const o of this.planets) {
-      o.set_time(secs_since_epoch, builder);
+      o.set_time(secs_since_epoch);
     }
   }
 
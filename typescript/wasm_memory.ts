@@ -1,4 +1,8 @@
-import { WasmVec3f64, WasmVec3f32 } from "../pkg/star_catalog_wasm.js";
+import {
+  WasmVec3f64,
+  WasmVec3f32,
+  WasmStarBuffer,
+} from "../pkg/star_catalog_wasm.js";
 
 export class WasmMemory {
   memory: WebAssembly.Memory;
@@ -23,4 +27,11 @@ export class WasmMemory {
   float_array_of_vec3f32(vec: WasmVec3f32): Float32Array {
     return this.float32_array(vec.buffer, 3);
   }
+
+  private uint32_array(ptr: number, num_words: number): Uint32Array {
+    return new Uint32Array(this.memory.buffer, ptr, num_words);
+  }
+  uint32_array_of_star_buffer(star_buffer: WasmStarBuffer): Uint32Array {
+    return this.uint32_array(star_buffer.buffer, star_buffer.length);
+  }
 }